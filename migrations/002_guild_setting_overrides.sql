@@ -0,0 +1,36 @@
+-- Per-guild threshold columns become optional overrides of the file configuration.
+-- Nothing wrote to these columns before, so existing values are just the old defaults.
+-- Guarded so that re-running it on a database migrated by hand keeps the overrides.
+
+DO $$
+BEGIN
+    IF EXISTS (
+        SELECT 1 FROM information_schema.columns
+        WHERE table_schema = current_schema()
+          AND table_name = 'guilds'
+          AND column_name = 'raid_threshold_5s'
+          AND is_nullable = 'NO'
+    ) THEN
+        ALTER TABLE guilds ALTER COLUMN raid_threshold_5s DROP NOT NULL;
+        ALTER TABLE guilds ALTER COLUMN raid_threshold_5s DROP DEFAULT;
+        ALTER TABLE guilds ALTER COLUMN raid_threshold_30s DROP NOT NULL;
+        ALTER TABLE guilds ALTER COLUMN raid_threshold_30s DROP DEFAULT;
+        ALTER TABLE guilds ALTER COLUMN raid_threshold_1m DROP NOT NULL;
+        ALTER TABLE guilds ALTER COLUMN raid_threshold_1m DROP DEFAULT;
+        ALTER TABLE guilds ALTER COLUMN raid_threshold_5m DROP NOT NULL;
+        ALTER TABLE guilds ALTER COLUMN raid_threshold_5m DROP DEFAULT;
+        ALTER TABLE guilds ALTER COLUMN new_account_days DROP NOT NULL;
+        ALTER TABLE guilds ALTER COLUMN new_account_days DROP DEFAULT;
+        ALTER TABLE guilds ALTER COLUMN auto_mod_enabled DROP NOT NULL;
+        ALTER TABLE guilds ALTER COLUMN auto_mod_enabled DROP DEFAULT;
+
+        UPDATE guilds
+        SET raid_threshold_5s = NULL,
+            raid_threshold_30s = NULL,
+            raid_threshold_1m = NULL,
+            raid_threshold_5m = NULL,
+            new_account_days = NULL,
+            auto_mod_enabled = NULL;
+    END IF;
+END
+$$;
@@ -0,0 +1,11 @@
+-- Original @everyone permission overwrites of channels touched by a lockdown
+
+CREATE TABLE IF NOT EXISTS lockdown_snapshots (
+    guild_id BIGINT NOT NULL REFERENCES guilds(guild_id) ON DELETE CASCADE,
+    channel_id BIGINT NOT NULL,
+    had_overwrite BOOLEAN NOT NULL,
+    allow_bits BIGINT NOT NULL DEFAULT 0,
+    deny_bits BIGINT NOT NULL DEFAULT 0,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (guild_id, channel_id)
+);
@@ -0,0 +1,15 @@
+-- Users that are banned or kicked as soon as they join
+
+CREATE TABLE IF NOT EXISTS blacklisted_users (
+    id SERIAL PRIMARY KEY,
+    guild_id BIGINT NOT NULL REFERENCES guilds(guild_id) ON DELETE CASCADE,
+    user_id BIGINT NOT NULL,
+    reason TEXT,
+    action VARCHAR(10) NOT NULL DEFAULT 'ban',
+    added_by BIGINT NOT NULL,
+    expires_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE(guild_id, user_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_blacklisted_users_user ON blacklisted_users(user_id);
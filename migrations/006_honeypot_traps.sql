@@ -0,0 +1,12 @@
+-- Honeypot traps configured per guild, loaded into the honeypot system at startup
+
+CREATE TABLE IF NOT EXISTS honeypot_traps (
+    id SERIAL PRIMARY KEY,
+    guild_id BIGINT NOT NULL REFERENCES guilds(guild_id) ON DELETE CASCADE,
+    trap_type VARCHAR(50) NOT NULL,
+    target TEXT NOT NULL,
+    label TEXT,
+    created_by BIGINT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE(guild_id, trap_type, target)
+);
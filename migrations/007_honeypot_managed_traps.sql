@@ -0,0 +1,3 @@
+-- Traps whose channel or role was created by `/kitsune honeypot setup` and is removed on teardown
+
+ALTER TABLE honeypot_traps ADD COLUMN IF NOT EXISTS managed BOOLEAN NOT NULL DEFAULT FALSE;
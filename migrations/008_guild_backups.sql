@@ -0,0 +1,14 @@
+-- Versioned snapshots of a guild's roles, channels, permission overwrites and Kitsune config
+
+CREATE TABLE IF NOT EXISTS guild_backups (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    guild_id BIGINT NOT NULL REFERENCES guilds(guild_id) ON DELETE CASCADE,
+    version INTEGER NOT NULL,
+    label TEXT,
+    data JSONB NOT NULL,
+    created_by BIGINT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE(guild_id, version)
+);
+
+CREATE INDEX IF NOT EXISTS idx_guild_backups_guild ON guild_backups(guild_id, version DESC);
@@ -0,0 +1,9 @@
+-- Full-text search over forensic event content. The 'simple' configuration
+-- doesn't stem, so it behaves the same for every language a server uses.
+-- Queries must repeat this exact expression for the index to be used.
+
+CREATE INDEX IF NOT EXISTS idx_forensic_events_content_fts
+    ON forensic_events USING GIN (to_tsvector('simple', COALESCE(content, '')));
+
+CREATE INDEX IF NOT EXISTS idx_honeypot_catches_guild_user
+    ON honeypot_catches(guild_id, user_id, created_at DESC);
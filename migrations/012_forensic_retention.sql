@@ -0,0 +1,25 @@
+-- Archive tables for the retention worker. Expired rows are moved here when
+-- [forensics] archive is enabled; columns match the live tables followed by
+-- the time the row was archived.
+
+CREATE TABLE IF NOT EXISTS forensic_events_archive (
+    LIKE forensic_events INCLUDING DEFAULTS,
+    archived_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE IF NOT EXISTS incidents_archive (
+    LIKE incidents INCLUDING DEFAULTS,
+    archived_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE IF NOT EXISTS honeypot_catches_archive (
+    LIKE honeypot_catches INCLUDING DEFAULTS,
+    archived_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_forensic_events_archive_guild_time ON forensic_events_archive(guild_id, created_at);
+CREATE INDEX IF NOT EXISTS idx_incidents_archive_guild_time ON incidents_archive(guild_id, created_at);
+CREATE INDEX IF NOT EXISTS idx_honeypot_catches_archive_guild_time ON honeypot_catches_archive(guild_id, created_at);
+
+-- Lets the worker find expired resolved incidents without scanning open ones.
+CREATE INDEX IF NOT EXISTS idx_incidents_resolved_time ON incidents(guild_id, created_at) WHERE resolved = true;
@@ -0,0 +1,10 @@
+-- Raid mode of guilds outside Normal, so a restart mid-raid can pick up where
+-- it left off and still lift a lockdown raid mode engaged itself.
+
+CREATE TABLE IF NOT EXISTS raid_mode_states (
+    guild_id BIGINT PRIMARY KEY REFERENCES guilds(guild_id) ON DELETE CASCADE,
+    mode VARCHAR(20) NOT NULL,
+    since TIMESTAMPTZ NOT NULL,
+    auto_locked BOOLEAN NOT NULL DEFAULT false,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
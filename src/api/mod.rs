@@ -0,0 +1,117 @@
+mod routes;
+
+use anyhow::{Context, Result};
+use axum::extract::FromRequestParts;
+use axum::http::{header, request::Parts, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::{Json, Router};
+use poise::serenity_prelude as serenity;
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::config::ApiConfig;
+use crate::database::{models::ApiToken, queries};
+use crate::security::{guild_config::GuildConfigCache, raid_mode::RaidModeTracker};
+
+const TOKEN_PREFIX: &str = "kit_";
+
+#[derive(Clone)]
+pub struct ApiState {
+    pub pool: PgPool,
+    pub http: Arc<serenity::Http>,
+    pub guild_configs: Arc<GuildConfigCache>,
+    pub raid_modes: Arc<RaidModeTracker>,
+    pub max_page_size: i64,
+}
+
+/// Creates a new random API token. Only its hash is ever stored.
+pub fn generate_token() -> String {
+    format!("{}{}{}", TOKEN_PREFIX, Uuid::new_v4().simple(), Uuid::new_v4().simple())
+}
+
+pub fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+pub fn spawn(config: ApiConfig, state: ApiState) {
+    tokio::spawn(async move {
+        if let Err(e) = serve(&config.bind_address, state).await {
+            tracing::error!("REST API stopped: {:#}", e);
+        }
+    });
+}
+
+async fn serve(bind_address: &str, state: ApiState) -> Result<()> {
+    let app = Router::new()
+        .nest("/api/v1", routes::router())
+        .with_state(state);
+
+    let listener = tokio::net::TcpListener::bind(bind_address).await
+        .with_context(|| format!("Failed to bind REST API to {}", bind_address))?;
+    tracing::info!("REST API listening on {}", listener.local_addr()?);
+
+    axum::serve(listener, app).await?;
+    Ok(())
+}
+
+/// The guild a request acts on, resolved from its `Authorization: Bearer`
+/// token.
+pub struct Authenticated {
+    pub guild_id: i64,
+    pub token: ApiToken,
+}
+
+#[axum::async_trait]
+impl FromRequestParts<ApiState> for Authenticated {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &ApiState) -> Result<Self, Self::Rejection> {
+        let bearer = parts.headers.get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .map(str::trim)
+            .filter(|token| token.starts_with(TOKEN_PREFIX))
+            .ok_or(ApiError::Unauthorized)?;
+
+        let token = queries::authenticate_api_token(&state.pool, &hash_token(bearer)).await?
+            .ok_or(ApiError::Unauthorized)?;
+
+        Ok(Self { guild_id: token.guild_id, token })
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ApiError {
+    #[error("Missing or invalid API token")]
+    Unauthorized,
+    #[error("{0}")]
+    NotFound(String),
+    #[error("{0}")]
+    BadRequest(String),
+    #[error(transparent)]
+    Internal(#[from] anyhow::Error),
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let status = match &self {
+            ApiError::Unauthorized => StatusCode::UNAUTHORIZED,
+            ApiError::NotFound(_) => StatusCode::NOT_FOUND,
+            ApiError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            ApiError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+
+        let message = match &self {
+            ApiError::Internal(e) => {
+                tracing::error!("REST API request failed: {:#}", e);
+                "Internal server error".to_string()
+            }
+            other => other.to_string(),
+        };
+
+        (status, Json(json!({ "error": message }))).into_response()
+    }
+}
@@ -0,0 +1,508 @@
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::routing::{get, put};
+use axum::{Json, Router};
+use chrono::{DateTime, Utc};
+use poise::serenity_prelude as serenity;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::bot::{blacklist, lockdown, raid_mode, scheduler::{self, Job}};
+use crate::database::models::{
+    BlacklistedUser, ForensicEvent, ForensicFilter, GuildSettings, Incident, IncidentFilter, WhitelistedUser,
+};
+use crate::database::queries;
+
+use super::{ApiError, ApiState, Authenticated};
+
+const DEFAULT_PAGE_SIZE: i64 = 50;
+
+type ApiResult<T> = Result<Json<T>, ApiError>;
+
+pub fn router() -> Router<ApiState> {
+    Router::new()
+        .route("/incidents", get(list_incidents))
+        .route("/incidents/:incident_id", get(get_incident))
+        .route("/forensics", get(list_forensic_events))
+        .route("/config", get(get_config).patch(update_config))
+        .route("/whitelist", get(list_whitelist))
+        .route("/whitelist/:user_id", put(add_whitelist).delete(remove_whitelist))
+        .route("/blacklist", get(list_blacklist))
+        .route("/blacklist/:user_id", put(add_blacklist).delete(remove_blacklist))
+        .route("/lockdown", get(get_lockdown).put(set_lockdown))
+}
+
+/// Snowflakes are exchanged as strings so JavaScript clients don't lose
+/// precision. Discord never issues 0, and serenity panics on it.
+fn parse_snowflake(value: &str) -> Result<i64, ApiError> {
+    value.trim().parse::<u64>().ok()
+        .filter(|id| *id != 0)
+        .map(|id| id as i64)
+        .ok_or_else(|| ApiError::BadRequest(format!("Invalid Discord ID: {}", value)))
+}
+
+fn page_size(state: &ApiState, limit: Option<i64>) -> i64 {
+    limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, state.max_page_size.max(1))
+}
+
+/// A page of results, newest first. `next_before` and `next_before_id` are
+/// passed back as `before` and `before_id` to fetch the following page; the
+/// ID breaks ties between rows created at the same instant.
+#[derive(Serialize)]
+struct Page<T> {
+    items: Vec<T>,
+    next_before: Option<DateTime<Utc>>,
+    next_before_id: Option<Uuid>,
+}
+
+impl<T> Page<T> {
+    fn new(items: Vec<T>, limit: i64, key: impl Fn(&T) -> (DateTime<Utc>, Uuid)) -> Self {
+        let next = (items.len() as i64 >= limit)
+            .then(|| items.last().map(&key))
+            .flatten();
+        Self {
+            items,
+            next_before: next.map(|(created_at, _)| created_at),
+            next_before_id: next.map(|(_, id)| id),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct IncidentView {
+    id: Uuid,
+    user_id: String,
+    incident_type: String,
+    severity: String,
+    threat_score: f32,
+    evidence: serde_json::Value,
+    action_taken: Option<String>,
+    moderator_id: Option<String>,
+    resolved: bool,
+    created_at: DateTime<Utc>,
+}
+
+impl From<Incident> for IncidentView {
+    fn from(incident: Incident) -> Self {
+        Self {
+            id: incident.id,
+            user_id: incident.user_id.to_string(),
+            incident_type: incident.incident_type,
+            severity: incident.severity,
+            threat_score: incident.threat_score,
+            evidence: incident.evidence,
+            action_taken: incident.action_taken,
+            moderator_id: incident.moderator_id.map(|id| id.to_string()),
+            resolved: incident.resolved,
+            created_at: incident.created_at,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct ForensicEventView {
+    id: Uuid,
+    user_id: Option<String>,
+    event_type: String,
+    content: Option<String>,
+    metadata: serde_json::Value,
+    threat_score: f32,
+    related_events: Vec<Uuid>,
+    tags: Vec<String>,
+    created_at: DateTime<Utc>,
+}
+
+impl From<ForensicEvent> for ForensicEventView {
+    fn from(event: ForensicEvent) -> Self {
+        Self {
+            id: event.id,
+            user_id: event.user_id.map(|id| id.to_string()),
+            event_type: event.event_type,
+            content: event.content,
+            metadata: event.metadata,
+            threat_score: event.threat_score,
+            related_events: event.related_events.unwrap_or_default(),
+            tags: event.tags.unwrap_or_default(),
+            created_at: event.created_at,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct WhitelistView {
+    user_id: String,
+    reason: Option<String>,
+    added_by: String,
+    created_at: DateTime<Utc>,
+}
+
+impl From<WhitelistedUser> for WhitelistView {
+    fn from(entry: WhitelistedUser) -> Self {
+        Self {
+            user_id: entry.user_id.to_string(),
+            reason: entry.reason,
+            added_by: entry.added_by.to_string(),
+            created_at: entry.created_at,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct BlacklistView {
+    user_id: String,
+    reason: Option<String>,
+    action: String,
+    added_by: String,
+    expires_at: Option<DateTime<Utc>>,
+    created_at: DateTime<Utc>,
+}
+
+impl From<BlacklistedUser> for BlacklistView {
+    fn from(entry: BlacklistedUser) -> Self {
+        Self {
+            user_id: entry.user_id.to_string(),
+            reason: entry.reason,
+            action: entry.action,
+            added_by: entry.added_by.to_string(),
+            expires_at: entry.expires_at,
+            created_at: entry.created_at,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct IncidentQuery {
+    #[serde(rename = "type")]
+    incident_type: Option<String>,
+    severity: Option<String>,
+    user_id: Option<String>,
+    resolved: Option<bool>,
+    since: Option<DateTime<Utc>>,
+    before: Option<DateTime<Utc>>,
+    before_id: Option<Uuid>,
+    limit: Option<i64>,
+}
+
+async fn list_incidents(
+    State(state): State<ApiState>,
+    auth: Authenticated,
+    Query(query): Query<IncidentQuery>,
+) -> ApiResult<Page<IncidentView>> {
+    let filter = IncidentFilter {
+        incident_type: query.incident_type,
+        severity: query.severity,
+        user_id: query.user_id.as_deref().map(parse_snowflake).transpose()?,
+        resolved: query.resolved,
+        since: query.since,
+        before: query.before,
+        before_id: query.before_id,
+        limit: page_size(&state, query.limit),
+    };
+
+    let incidents = queries::search_incidents(&state.pool, auth.guild_id, &filter).await?;
+    let items = incidents.into_iter().map(IncidentView::from).collect();
+
+    Ok(Json(Page::new(items, filter.limit, |incident: &IncidentView| (incident.created_at, incident.id))))
+}
+
+async fn get_incident(
+    State(state): State<ApiState>,
+    auth: Authenticated,
+    Path(incident_id): Path<Uuid>,
+) -> ApiResult<IncidentView> {
+    let incident = queries::get_incident(&state.pool, auth.guild_id, incident_id).await?
+        .ok_or_else(|| ApiError::NotFound(format!("Incident {} not found", incident_id)))?;
+
+    Ok(Json(incident.into()))
+}
+
+#[derive(Deserialize)]
+struct ForensicQuery {
+    q: Option<String>,
+    #[serde(rename = "type")]
+    event_type: Option<String>,
+    user_id: Option<String>,
+    tag: Option<String>,
+    min_score: Option<f32>,
+    max_score: Option<f32>,
+    since: Option<DateTime<Utc>>,
+    before: Option<DateTime<Utc>>,
+    before_id: Option<Uuid>,
+    limit: Option<i64>,
+}
+
+async fn list_forensic_events(
+    State(state): State<ApiState>,
+    auth: Authenticated,
+    Query(query): Query<ForensicQuery>,
+) -> ApiResult<Page<ForensicEventView>> {
+    let filter = ForensicFilter {
+        text: query.q.filter(|q| !q.trim().is_empty()),
+        event_type: query.event_type,
+        user_id: query.user_id.as_deref().map(parse_snowflake).transpose()?,
+        tag: query.tag,
+        min_score: query.min_score,
+        max_score: query.max_score,
+        since: query.since,
+        before: query.before,
+        before_id: query.before_id,
+        limit: page_size(&state, query.limit),
+        offset: 0,
+    };
+
+    let events = queries::search_forensic_events(&state.pool, auth.guild_id, &filter).await?;
+    let items = events.into_iter().map(ForensicEventView::from).collect();
+
+    Ok(Json(Page::new(items, filter.limit, |event: &ForensicEventView| (event.created_at, event.id))))
+}
+
+/// The guild's stored overrides next to the configuration Kitsune actually
+/// uses once they are layered over the file defaults.
+async fn get_config(State(state): State<ApiState>, auth: Authenticated) -> ApiResult<serde_json::Value> {
+    config_response(&state, auth.guild_id).await
+}
+
+async fn config_response(state: &ApiState, guild_id: i64) -> ApiResult<serde_json::Value> {
+    let guild = queries::get_guild(&state.pool, guild_id).await?
+        .ok_or_else(|| ApiError::NotFound("Guild not found".to_string()))?;
+    let effective = state.guild_configs.reload(&state.pool, guild_id).await?;
+
+    Ok(Json(json!({
+        "overrides": guild.config,
+        "effective": *effective,
+    })))
+}
+
+/// Applies a partial update to the guild overrides. Keys are the fields of
+/// `GuildSettings`; `null` clears an override.
+async fn update_config(
+    State(state): State<ApiState>,
+    auth: Authenticated,
+    Json(patch): Json<serde_json::Map<String, serde_json::Value>>,
+) -> ApiResult<serde_json::Value> {
+    let guild_id = auth.guild_id;
+    let known = serde_json::to_value(GuildSettings::default())
+        .map_err(anyhow::Error::from)?;
+
+    if let Some(key) = patch.keys().find(|key| known.get(key.as_str()).is_none()) {
+        return Err(ApiError::BadRequest(format!("Unknown setting `{}`", key)));
+    }
+
+    let guild = queries::get_guild(&state.pool, guild_id).await?
+        .ok_or_else(|| ApiError::NotFound("Guild not found".to_string()))?;
+    let mut merged = guild.config.as_object().cloned().unwrap_or_default();
+    merged.extend(patch.clone());
+    serde_json::from_value::<GuildSettings>(serde_json::Value::Object(merged))
+        .map_err(|e| ApiError::BadRequest(format!("Invalid settings: {}", e)))?
+        .validate()
+        .map_err(|e| ApiError::BadRequest(format!("Invalid settings: {}", e)))?;
+
+    for (key, value) in &patch {
+        queries::set_guild_setting(&state.pool, guild_id, key, value.clone()).await?;
+    }
+    let config = state.guild_configs.reload(&state.pool, guild_id).await?;
+
+    if patch.contains_key("backup_frequency") {
+        match config.backup.frequency {
+            Some(frequency) => { scheduler::schedule_backup(&state.pool, guild_id, frequency).await?; }
+            None => { queries::cancel_jobs_of_type(&state.pool, guild_id, Job::CREATE_BACKUP).await?; }
+        }
+    }
+
+    tracing::info!("API token {} updated settings of guild {}: {:?}", auth.token.id, guild_id, patch.keys().collect::<Vec<_>>());
+    config_response(&state, guild_id).await
+}
+
+async fn list_whitelist(State(state): State<ApiState>, auth: Authenticated) -> ApiResult<Vec<WhitelistView>> {
+    let entries = queries::get_whitelisted_users(&state.pool, auth.guild_id).await?;
+    Ok(Json(entries.into_iter().map(WhitelistView::from).collect()))
+}
+
+#[derive(Deserialize, Default)]
+struct WhitelistRequest {
+    reason: Option<String>,
+}
+
+/// Whitelist entries reference the users table, so the user is looked up on
+/// Discord and stored first.
+async fn add_whitelist(
+    State(state): State<ApiState>,
+    auth: Authenticated,
+    Path(user_id): Path<String>,
+    body: Option<Json<WhitelistRequest>>,
+) -> ApiResult<WhitelistView> {
+    let user_id = parse_snowflake(&user_id)?;
+    let request = body.map(|Json(request)| request).unwrap_or_default();
+
+    let user = state.http.get_user(serenity::UserId::new(user_id as u64)).await
+        .map_err(|_| ApiError::NotFound(format!("Unknown Discord user {}", user_id)))?;
+    queries::upsert_user(&state.pool, user_id, &user.name, user.discriminator.as_ref().map(|d| d.to_string()).as_deref()).await?;
+
+    queries::add_to_whitelist(&state.pool, auth.guild_id, user_id, request.reason.as_deref(), auth.token.created_by).await?;
+    tracing::info!("API token {} whitelisted user {} in guild {}", auth.token.id, user_id, auth.guild_id);
+
+    let entry = queries::get_whitelisted_users(&state.pool, auth.guild_id).await?
+        .into_iter()
+        .find(|entry| entry.user_id == user_id)
+        .ok_or_else(|| anyhow::anyhow!("Whitelist entry for {} disappeared", user_id))?;
+
+    Ok(Json(entry.into()))
+}
+
+async fn remove_whitelist(
+    State(state): State<ApiState>,
+    auth: Authenticated,
+    Path(user_id): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    let user_id = parse_snowflake(&user_id)?;
+
+    if !queries::is_whitelisted(&state.pool, auth.guild_id, user_id).await? {
+        return Err(ApiError::NotFound(format!("User {} is not whitelisted", user_id)));
+    }
+
+    queries::remove_from_whitelist(&state.pool, auth.guild_id, user_id).await?;
+    tracing::info!("API token {} removed user {} from the whitelist of guild {}", auth.token.id, user_id, auth.guild_id);
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn list_blacklist(State(state): State<ApiState>, auth: Authenticated) -> ApiResult<Vec<BlacklistView>> {
+    let entries = queries::get_blacklisted_users(&state.pool, auth.guild_id).await?;
+    Ok(Json(entries.into_iter().map(BlacklistView::from).collect()))
+}
+
+#[derive(Deserialize, Default)]
+struct BlacklistRequest {
+    reason: Option<String>,
+    action: Option<String>,
+    expires_at: Option<DateTime<Utc>>,
+}
+
+/// Adds or replaces a blacklist entry. A user already in the server is
+/// removed right away, the same as `/kitsune blacklist`.
+async fn add_blacklist(
+    State(state): State<ApiState>,
+    auth: Authenticated,
+    Path(user_id): Path<String>,
+    body: Option<Json<BlacklistRequest>>,
+) -> ApiResult<serde_json::Value> {
+    let user_id = parse_snowflake(&user_id)?;
+    let request = body.map(|Json(request)| request).unwrap_or_default();
+
+    let action = blacklist::parse_action(request.action.as_deref().unwrap_or("ban"))
+        .ok_or_else(|| ApiError::BadRequest("Action must be `ban` or `kick`".to_string()))?;
+    if request.expires_at.is_some_and(|t| t <= Utc::now()) {
+        return Err(ApiError::BadRequest("expires_at is in the past".to_string()));
+    }
+
+    queries::add_to_blacklist(
+        &state.pool,
+        auth.guild_id,
+        user_id,
+        request.reason.as_deref(),
+        action,
+        auth.token.created_by,
+        request.expires_at
+    ).await?;
+    tracing::info!("API token {} blacklisted user {} in guild {}", auth.token.id, user_id, auth.guild_id);
+
+    let enforcement = blacklist::enforce_on_member(
+        state.http.as_ref(),
+        serenity::GuildId::new(auth.guild_id as u64),
+        serenity::UserId::new(user_id as u64),
+        action,
+        request.reason.as_deref()
+    ).await;
+
+    let entry = queries::get_blacklist_entry(&state.pool, auth.guild_id, user_id).await?
+        .ok_or_else(|| anyhow::anyhow!("Blacklist entry for {} disappeared", user_id))?;
+
+    Ok(Json(json!({
+        "entry": BlacklistView::from(entry),
+        "removed_from_server": matches!(enforcement, Ok(true)),
+        "error": enforcement.err().map(|e| e.to_string()),
+    })))
+}
+
+async fn remove_blacklist(
+    State(state): State<ApiState>,
+    auth: Authenticated,
+    Path(user_id): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    let user_id = parse_snowflake(&user_id)?;
+
+    if !queries::remove_from_blacklist(&state.pool, auth.guild_id, user_id).await? {
+        return Err(ApiError::NotFound(format!("User {} is not blacklisted", user_id)));
+    }
+    tracing::info!("API token {} removed user {} from the blacklist of guild {}", auth.token.id, user_id, auth.guild_id);
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn scheduled_lift(state: &ApiState, guild_id: i64) -> Result<Option<DateTime<Utc>>, ApiError> {
+    Ok(queries::get_pending_jobs(&state.pool, guild_id).await?
+        .into_iter()
+        .find(|job| job.job_type == Job::LIFT_LOCKDOWN)
+        .map(|job| job.run_at))
+}
+
+async fn get_lockdown(State(state): State<ApiState>, auth: Authenticated) -> ApiResult<serde_json::Value> {
+    let active = queries::get_guild(&state.pool, auth.guild_id).await?
+        .map(|guild| guild.lockdown_active)
+        .unwrap_or(false);
+
+    Ok(Json(json!({
+        "active": active,
+        "scheduled_lift": scheduled_lift(&state, auth.guild_id).await?,
+    })))
+}
+
+#[derive(Deserialize)]
+struct LockdownRequest {
+    active: bool,
+    duration_minutes: Option<u64>,
+}
+
+/// Engages or lifts the lockdown exactly like `/kitsune lockdown`.
+async fn set_lockdown(
+    State(state): State<ApiState>,
+    auth: Authenticated,
+    Json(request): Json<LockdownRequest>,
+) -> ApiResult<serde_json::Value> {
+    let guild_id = auth.guild_id;
+    if request.duration_minutes.is_some_and(|minutes| minutes > scheduler::MAX_DELAY_MINUTES) {
+        return Err(ApiError::BadRequest(format!(
+            "duration_minutes is limited to {} (one year)",
+            scheduler::MAX_DELAY_MINUTES
+        )));
+    }
+
+    // An operator taking over means raid mode must not lift this lockdown on its own.
+    state.raid_modes.set_auto_locked(guild_id, false);
+    raid_mode::persist(&state.pool, &state.raid_modes, guild_id).await?;
+
+    let report = if request.active {
+        let guild_config = state.guild_configs.get(&state.pool, guild_id).await?;
+        lockdown::engage(&state.http, &state.pool, guild_id, &guild_config).await?
+    } else {
+        lockdown::lift(&state.http, &state.pool, guild_id).await?
+    };
+
+    match request.duration_minutes.filter(|_| request.active) {
+        Some(minutes) => {
+            scheduler::schedule_lift(&state.pool, guild_id, minutes.max(1), Some(auth.token.created_by)).await?;
+        }
+        None => {
+            queries::cancel_jobs_of_type(&state.pool, guild_id, Job::LIFT_LOCKDOWN).await?;
+        }
+    }
+    tracing::info!("API token {} {} the lockdown of guild {}", auth.token.id, if request.active { "engaged" } else { "lifted" }, guild_id);
+
+    Ok(Json(json!({
+        "active": request.active,
+        "channels": report.channels,
+        "failed": report.failed,
+        "scheduled_lift": scheduled_lift(&state, guild_id).await?,
+    })))
+}
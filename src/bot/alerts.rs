@@ -0,0 +1,97 @@
+use poise::serenity_prelude as serenity;
+
+use crate::config::GuildConfig;
+use crate::database::models::{Incident, ThreatLevel};
+use crate::metrics;
+
+pub struct IncidentAlert<'a> {
+    pub incident: &'a Incident,
+    pub threat_level: ThreatLevel,
+    pub breakdown: Vec<(&'static str, String)>,
+    pub reasons: Vec<String>,
+    pub message_link: Option<String>,
+}
+
+pub async fn dispatch_incident(http: &serenity::Http, config: &GuildConfig, alert: IncidentAlert<'_>) {
+    let embed = incident_embed(&alert);
+
+    if let Some(channel_id) = config.alerts.alert_channel_id {
+        let mut message = serenity::CreateMessage::new().embed(embed.clone());
+
+        if alert.threat_level >= config.alerts.notify_level && !config.alerts.notify_roles.is_empty() {
+            let mentions = config.alerts.notify_roles.iter()
+                .map(|id| format!("<@&{}>", id))
+                .collect::<Vec<_>>()
+                .join(" ");
+            message = message
+                .content(mentions)
+                .allowed_mentions(serenity::CreateAllowedMentions::new().roles(
+                    config.alerts.notify_roles.iter().map(|id| serenity::RoleId::new(*id)).collect::<Vec<_>>()
+                ));
+        }
+
+        send(http, channel_id, message).await;
+    }
+
+    if let Some(channel_id) = config.alerts.log_channel_id {
+        send(http, channel_id, serenity::CreateMessage::new().embed(embed)).await;
+    }
+}
+
+pub async fn dispatch_alert(http: &serenity::Http, config: &GuildConfig, embed: serenity::CreateEmbed) {
+    if let Some(channel_id) = config.alerts.alert_channel_id {
+        send(http, channel_id, serenity::CreateMessage::new().embed(embed)).await;
+    }
+}
+
+pub async fn dispatch_report(http: &serenity::Http, config: &GuildConfig, embed: serenity::CreateEmbed) {
+    if let Some(channel_id) = config.alerts.report_channel_id {
+        send(http, channel_id, serenity::CreateMessage::new().embed(embed)).await;
+    }
+}
+
+fn incident_embed(alert: &IncidentAlert<'_>) -> serenity::CreateEmbed {
+    let incident = alert.incident;
+
+    let title = match incident.incident_type.as_str() {
+        "raid_detection" => "🚨 Raid Detected",
+        "behavioral_threat" => "⚠️ Behavioral Threat",
+        "blacklist_match" => "🚫 Blacklisted User Joined",
+        "anti_nuke" => "💣 Anti-Nuke Triggered",
+        _ => "🛡️ Security Incident",
+    };
+
+    let mut embed = serenity::CreateEmbed::new()
+        .title(title)
+        .color(alert.threat_level.color())
+        .field("User", format!("<@{}> ({})", incident.user_id, incident.user_id), true)
+        .field("Threat", format!("{} ({:.2})", alert.threat_level.as_str(), incident.threat_score), true)
+        .field("Action", incident.action_taken.as_deref().unwrap_or("none"), true);
+
+    for (name, value) in &alert.breakdown {
+        embed = embed.field(*name, value, true);
+    }
+
+    if !alert.reasons.is_empty() {
+        let reasons = alert.reasons.iter()
+            .map(|r| format!("• {}", r))
+            .collect::<Vec<_>>()
+            .join("\n");
+        embed = embed.field("Reasons", reasons, false);
+    }
+
+    if let Some(link) = &alert.message_link {
+        embed = embed.field("Message", format!("[Jump to message]({})", link), false);
+    }
+
+    embed
+        .footer(serenity::CreateEmbedFooter::new(format!("Incident {}", incident.id)))
+        .timestamp(serenity::Timestamp::from_unix_timestamp(incident.created_at.timestamp()).unwrap_or_else(|_| serenity::Timestamp::now()))
+}
+
+async fn send(http: &serenity::Http, channel_id: u64, message: serenity::CreateMessage) {
+    if let Err(e) = serenity::ChannelId::new(channel_id).send_message(http, message).await {
+        tracing::warn!("Failed to deliver alert to channel {}: {}", channel_id, e);
+        metrics::discord_failure("send_alert");
+    }
+}
@@ -0,0 +1,245 @@
+use anyhow::Result;
+use chrono::Utc;
+use poise::serenity_prelude as serenity;
+use serenity::audit_log::{Action, ChannelAction, MemberAction, RoleAction, WebhookAction};
+use serde_json::json;
+
+use crate::database::{models::ThreatLevel, queries};
+use crate::metrics;
+use crate::security::anti_nuke::{NukeAction, NukeVerdict};
+
+use super::alerts::{self, IncidentAlert};
+use super::{webhooks, Data};
+
+/// Permissions that let an account damage the server or hand out the ability
+/// to do so.
+const DANGEROUS_PERMISSIONS: serenity::Permissions = serenity::Permissions::ADMINISTRATOR
+    .union(serenity::Permissions::BAN_MEMBERS)
+    .union(serenity::Permissions::KICK_MEMBERS)
+    .union(serenity::Permissions::MANAGE_GUILD)
+    .union(serenity::Permissions::MANAGE_ROLES)
+    .union(serenity::Permissions::MANAGE_CHANNELS)
+    .union(serenity::Permissions::MANAGE_WEBHOOKS);
+
+/// Feeds an audit log entry into the anti-nuke tracker and responds when its
+/// actor crosses a threshold.
+pub async fn handle_audit_entry(
+    ctx: &serenity::Context,
+    guild_id: serenity::GuildId,
+    entry: &serenity::AuditLogEntry,
+    data: &Data,
+) -> Result<()> {
+    let actor = entry.user_id;
+    if actor == ctx.cache.current_user().id {
+        return Ok(());
+    }
+
+    let Some(action) = classify(ctx, guild_id, entry).await else {
+        return Ok(());
+    };
+
+    let guild = guild_id.get() as i64;
+    let actor_id = actor.get() as i64;
+
+    // The auto-mod whitelist doesn't count here: staff are usually on it, and a
+    // compromised staff account is exactly what anti-nuke is for.
+    let exempt = queries::get_guild(&data.pool, guild).await?
+        .is_some_and(|g| g.owner_id == actor_id || g.settings().anti_nuke_trusted.contains(&actor_id));
+    if exempt {
+        return Ok(());
+    }
+
+    if let Some(verdict) = data.anti_nuke.record(guild, actor_id, action, Utc::now()) {
+        respond(ctx, guild_id, actor, verdict, data).await?;
+    }
+
+    Ok(())
+}
+
+async fn classify(
+    ctx: &serenity::Context,
+    guild_id: serenity::GuildId,
+    entry: &serenity::AuditLogEntry,
+) -> Option<NukeAction> {
+    let changes = entry.changes.as_deref().unwrap_or_default();
+
+    match entry.action {
+        Action::Channel(ChannelAction::Delete) => Some(NukeAction::ChannelDelete),
+        Action::Role(RoleAction::Delete) => Some(NukeAction::RoleDelete),
+        Action::Member(MemberAction::BanAdd) => Some(NukeAction::Ban),
+        Action::Member(MemberAction::Kick) => Some(NukeAction::Kick),
+        Action::Webhook(WebhookAction::Create) => Some(NukeAction::WebhookCreate),
+        Action::Role(RoleAction::Create | RoleAction::Update) => {
+            let escalated = changes.iter().any(|change| match change {
+                serenity::Change::Permissions { old, new: Some(new) } => {
+                    let gained = *new - old.unwrap_or_else(serenity::Permissions::empty);
+                    gained.intersects(DANGEROUS_PERMISSIONS)
+                }
+                _ => false,
+            });
+            escalated.then_some(NukeAction::PermissionEscalation)
+        }
+        Action::Member(MemberAction::RoleUpdate) => {
+            let added: Vec<serenity::RoleId> = changes.iter()
+                .filter_map(|change| match change {
+                    serenity::Change::RolesAdded { new: Some(roles), .. } => Some(roles.iter().map(|r| r.id)),
+                    _ => None,
+                })
+                .flatten()
+                .collect();
+            if added.is_empty() {
+                return None;
+            }
+
+            let dangerous = role_permissions(ctx, guild_id, &added).await
+                .into_iter()
+                .any(|permissions| permissions.intersects(DANGEROUS_PERMISSIONS));
+            dangerous.then_some(NukeAction::PermissionEscalation)
+        }
+        _ => None,
+    }
+}
+
+async fn role_permissions(
+    ctx: &serenity::Context,
+    guild_id: serenity::GuildId,
+    role_ids: &[serenity::RoleId],
+) -> Vec<serenity::Permissions> {
+    let cached: Option<Vec<serenity::Permissions>> = ctx.cache.guild(guild_id).map(|guild| {
+        role_ids.iter()
+            .filter_map(|id| guild.roles.get(id).map(|role| role.permissions))
+            .collect()
+    });
+    if let Some(permissions) = cached {
+        return permissions;
+    }
+
+    match guild_id.roles(&ctx.http).await {
+        Ok(roles) => role_ids.iter()
+            .filter_map(|id| roles.get(id).map(|role| role.permissions))
+            .collect(),
+        Err(e) => {
+            tracing::warn!("Failed to fetch roles of guild {}: {}", guild_id, e);
+            Vec::new()
+        }
+    }
+}
+
+/// Bans a bot actor outright; a human actor loses every role Kitsune can
+/// remove, which takes away their permissions until a human reviews it.
+/// Roles it can't reach are listed in the alert.
+async fn respond(
+    ctx: &serenity::Context,
+    guild_id: serenity::GuildId,
+    actor: serenity::UserId,
+    verdict: NukeVerdict,
+    data: &Data,
+) -> Result<()> {
+    let guild = guild_id.get() as i64;
+    let actor_id = actor.get() as i64;
+    let reason = format!(
+        "Kitsune anti-nuke: {} {} in {}s",
+        verdict.count,
+        verdict.action.label().to_lowercase(),
+        verdict.window_secs
+    );
+
+    let user = actor.to_user(ctx).await?;
+    queries::upsert_user(&data.pool, actor_id, &user.name, user.discriminator.as_ref().map(|d| d.to_string()).as_deref()).await?;
+
+    tracing::warn!("Anti-nuke triggered in guild {} by {} ({}): {}", guild, user.name, actor_id, reason);
+
+    let (action_taken, result) = if user.bot {
+        ("ban", guild_id.ban_with_reason(ctx, actor, 0, &reason).await.map(|_| Vec::new()))
+    } else {
+        ("strip_roles", strip_roles(ctx, guild_id, actor, &reason).await)
+    };
+    if let Err(e) = &result {
+        tracing::error!("Failed to {} anti-nuke actor {} in guild {}: {}", action_taken, actor_id, guild, e);
+        metrics::discord_failure(&format!("anti_nuke_{}", action_taken));
+    }
+
+    let guild_config = data.guild_configs.get(&data.pool, guild).await?;
+    let incident = queries::create_incident(
+        &data.pool,
+        guild,
+        actor_id,
+        "anti_nuke",
+        ThreatLevel::Critical.as_str(),
+        1.0,
+        json!({
+            "username": user.name,
+            "bot": user.bot,
+            "trigger": verdict.action.as_str(),
+            "count": verdict.count,
+            "threshold": verdict.threshold,
+            "window_secs": verdict.window_secs,
+            "activity": verdict.activity.iter()
+                .map(|(action, count)| (action.as_str().to_string(), json!(count)))
+                .collect::<serde_json::Map<_, _>>(),
+            "enforced": result.is_ok(),
+            "unremovable_roles": result.as_ref().map(|names| names.as_slice()).unwrap_or_default(),
+        }),
+        result.is_ok().then_some(action_taken)
+    ).await?;
+    webhooks::emit_incident(&data.pool, &incident).await;
+
+    let response = match (user.bot, &result) {
+        (true, Ok(_)) => "Bot banned".to_string(),
+        (false, Ok(unremovable)) if unremovable.is_empty() => "All roles stripped".to_string(),
+        (false, Ok(unremovable)) => format!(
+            "⚠️ Roles stripped, but these are above Kitsune and must be removed by hand: {}",
+            unremovable.join(", ")
+        ),
+        (_, Err(_)) => "⚠️ Kitsune could not act. Check its role position and permissions".to_string(),
+    };
+
+    alerts::dispatch_incident(&ctx.http, &guild_config, IncidentAlert {
+        incident: &incident,
+        threat_level: ThreatLevel::Critical,
+        breakdown: vec![
+            ("Actor", format!("<@{}>{}", actor_id, if user.bot { " (bot)" } else { "" })),
+            ("Window", format!("{}s", verdict.window_secs)),
+            ("Response", response),
+        ],
+        reasons: verdict.activity.iter()
+            .map(|(action, count)| format!("{}: {}", action.label(), count))
+            .collect(),
+        message_link: None,
+    }).await;
+
+    Ok(())
+}
+
+/// Removes every role Kitsune is allowed to: managed roles and roles at or
+/// above its own highest role stay, and their names are returned so the
+/// alert can tell moderators what is left to take away by hand.
+async fn strip_roles(
+    ctx: &serenity::Context,
+    guild_id: serenity::GuildId,
+    actor: serenity::UserId,
+    reason: &str,
+) -> serenity::Result<Vec<String>> {
+    let member = guild_id.member(ctx, actor).await?;
+    let bot_id = ctx.cache.current_user().id;
+    let me = guild_id.member(ctx, bot_id).await?;
+    let roles = guild_id.roles(&ctx.http).await?;
+
+    let top_position = me.roles.iter()
+        .filter_map(|id| roles.get(id).map(|role| role.position))
+        .max()
+        .unwrap_or(0);
+
+    let (keep, removable): (Vec<serenity::RoleId>, Vec<serenity::RoleId>) = member.roles.iter()
+        .partition(|id| roles.get(id).is_none_or(|role| role.managed || role.position >= top_position));
+    let unremovable: Vec<String> = keep.iter()
+        .filter_map(|id| roles.get(id))
+        .filter(|role| !role.managed)
+        .map(|role| role.name.clone())
+        .collect();
+
+    if !removable.is_empty() {
+        guild_id.edit_member(ctx, actor, serenity::EditMember::new().roles(keep).audit_log_reason(reason)).await?;
+    }
+    Ok(unremovable)
+}
@@ -0,0 +1,912 @@
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+use poise::serenity_prelude as serenity;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+use std::collections::{HashMap, HashSet};
+
+use crate::config::BackupConfig;
+use crate::database::{models::{GuildBackup, GuildSettingsBackup}, queries};
+
+/// Bumped whenever the snapshot layout changes in a way older readers can't
+/// handle.
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+pub const ARCHIVE_FORMAT: &str = "kitsune-backup";
+
+const AUDIT_REASON: &str = "Kitsune backup restore";
+
+/// Everything Kitsune needs to rebuild a guild's structure: roles, categories,
+/// channels with their permission overwrites, and Kitsune's own settings.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GuildSnapshot {
+    pub format_version: u32,
+    #[serde(with = "snowflake")]
+    pub guild_id: u64,
+    pub guild_name: String,
+    pub captured_at: DateTime<Utc>,
+    pub roles: Vec<RoleSnapshot>,
+    pub categories: Vec<ChannelSnapshot>,
+    pub channels: Vec<ChannelSnapshot>,
+    #[serde(default)]
+    pub settings: Option<GuildSettingsBackup>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RoleSnapshot {
+    #[serde(with = "snowflake")]
+    pub id: u64,
+    pub name: String,
+    pub color: u32,
+    pub permissions: u64,
+    pub position: u16,
+    pub hoist: bool,
+    pub mentionable: bool,
+    pub managed: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChannelSnapshot {
+    #[serde(with = "snowflake")]
+    pub id: u64,
+    pub name: String,
+    pub kind: String,
+    pub position: u16,
+    #[serde(with = "snowflake::option", default)]
+    pub parent_id: Option<u64>,
+    pub topic: Option<String>,
+    pub nsfw: bool,
+    pub rate_limit_per_user: Option<u16>,
+    pub bitrate: Option<u32>,
+    pub user_limit: Option<u32>,
+    pub overwrites: Vec<OverwriteSnapshot>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct OverwriteSnapshot {
+    /// `role` or `member`
+    pub target: String,
+    #[serde(with = "snowflake")]
+    pub id: u64,
+    pub allow: u64,
+    pub deny: u64,
+}
+
+/// What a restore would change, computed by comparing a backup with the
+/// guild as it is now. Objects are matched by ID first and by name second, so
+/// running the same restore twice is a no-op.
+#[derive(Debug, Default)]
+pub struct RestorePlan {
+    pub create_roles: Vec<RoleSnapshot>,
+    pub update_roles: Vec<(u64, RoleSnapshot)>,
+    pub create_channels: Vec<ChannelSnapshot>,
+    pub update_channels: Vec<(u64, ChannelSnapshot)>,
+    pub skipped_roles: Vec<String>,
+    pub restore_settings: bool,
+}
+
+#[derive(Debug, Default)]
+pub struct RestoreReport {
+    pub created: usize,
+    pub updated: usize,
+    pub failed: Vec<String>,
+}
+
+impl GuildSnapshot {
+    pub fn from_json(data: &serde_json::Value) -> Result<Self> {
+        let snapshot: GuildSnapshot = serde_json::from_value(data.clone())?;
+        if snapshot.format_version > SNAPSHOT_VERSION {
+            bail!(
+                "Backup format version {} is newer than this Kitsune supports ({})",
+                snapshot.format_version,
+                SNAPSHOT_VERSION
+            );
+        }
+        Ok(snapshot)
+    }
+}
+
+impl GuildSnapshot {
+    /// Whether two snapshots describe the same layout, ignoring when they were
+    /// taken.
+    pub fn same_layout(&self, other: &GuildSnapshot) -> bool {
+        self.guild_id == other.guild_id
+            && self.roles == other.roles
+            && self.categories == other.categories
+            && self.channels == other.channels
+            && self.settings == other.settings
+    }
+}
+
+impl GuildSnapshot {
+    /// The first object carrying an ID of 0, which Discord never issues.
+    fn zero_id(&self) -> Option<String> {
+        if self.guild_id == 0 {
+            return Some("the guild".to_string());
+        }
+        if let Some(role) = self.roles.iter().find(|r| r.id == 0) {
+            return Some(format!("role {}", role.name));
+        }
+
+        self.categories.iter().chain(&self.channels).find_map(|channel| {
+            if channel.id == 0 || channel.parent_id == Some(0) {
+                Some(format!("channel #{}", channel.name))
+            } else if channel.overwrites.iter().any(|o| o.id == 0) {
+                Some(format!("an overwrite of #{}", channel.name))
+            } else {
+                None
+            }
+        })
+    }
+}
+
+impl RestorePlan {
+    pub fn is_empty(&self) -> bool {
+        self.create_roles.is_empty()
+            && self.update_roles.is_empty()
+            && self.create_channels.is_empty()
+            && self.update_channels.is_empty()
+            && !self.restore_settings
+    }
+
+    /// Human-readable diff, one line per change.
+    pub fn describe(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+
+        for role in &self.create_roles {
+            lines.push(format!("➕ Create role **{}**", role.name));
+        }
+        for (_, role) in &self.update_roles {
+            lines.push(format!("✏️ Update role **{}**", role.name));
+        }
+        for channel in &self.create_channels {
+            lines.push(format!("➕ Create {} **{}**", channel.kind, channel.name));
+        }
+        for (_, channel) in &self.update_channels {
+            lines.push(format!("✏️ Update {} **{}**", channel.kind, channel.name));
+        }
+        for name in &self.skipped_roles {
+            lines.push(format!("⏭️ Skip integration role **{}**", name));
+        }
+        if self.restore_settings {
+            lines.push("⚙️ Restore Kitsune configuration".to_string());
+        }
+
+        lines
+    }
+}
+
+/// Captures the guild's current roles, channels, overwrites and Kitsune
+/// settings.
+pub async fn capture(http: &serenity::Http, pool: &PgPool, guild_id: i64) -> Result<GuildSnapshot> {
+    let guild = serenity::GuildId::new(guild_id as u64);
+    let partial = guild.to_partial_guild(http).await?;
+    let roles = guild.roles(http).await?;
+    let channels = guild.channels(http).await?;
+
+    let mut roles: Vec<RoleSnapshot> = roles.values()
+        .map(|role| RoleSnapshot {
+            id: role.id.get(),
+            name: role.name.clone(),
+            color: role.colour.0,
+            permissions: role.permissions.bits(),
+            position: role.position,
+            hoist: role.hoist,
+            mentionable: role.mentionable,
+            managed: role.managed,
+        })
+        .collect();
+    roles.sort_by_key(|role| (role.position, role.id));
+
+    let mut categories = Vec::new();
+    let mut others = Vec::new();
+    for channel in channels.values() {
+        let Some(kind) = kind_name(channel.kind) else {
+            continue;
+        };
+
+        let snapshot = ChannelSnapshot {
+            id: channel.id.get(),
+            name: channel.name.clone(),
+            kind: kind.to_string(),
+            position: channel.position,
+            parent_id: channel.parent_id.map(|id| id.get()),
+            topic: channel.topic.clone(),
+            nsfw: channel.nsfw,
+            rate_limit_per_user: channel.rate_limit_per_user,
+            bitrate: channel.bitrate,
+            user_limit: channel.user_limit,
+            overwrites: channel.permission_overwrites.iter().filter_map(overwrite_snapshot).collect(),
+        };
+
+        if channel.kind == serenity::ChannelType::Category {
+            categories.push(snapshot);
+        } else {
+            others.push(snapshot);
+        }
+    }
+    categories.sort_by_key(|c| (c.position, c.id));
+    others.sort_by_key(|c| (c.parent_id, c.position, c.id));
+
+    let settings = queries::get_guild(pool, guild_id).await?
+        .map(|g| GuildSettingsBackup::from(&g));
+
+    Ok(GuildSnapshot {
+        format_version: SNAPSHOT_VERSION,
+        guild_id: guild.get(),
+        guild_name: partial.name,
+        captured_at: Utc::now(),
+        roles,
+        categories,
+        channels: others,
+        settings,
+    })
+}
+
+/// Takes an automatic backup unless the layout is unchanged since the latest
+/// backup, then prunes automatic backups beyond the retention count.
+pub async fn create_automatic(
+    http: &serenity::Http,
+    pool: &PgPool,
+    guild_id: i64,
+    config: &BackupConfig,
+) -> Result<Option<GuildBackup>> {
+    let snapshot = capture(http, pool, guild_id).await?;
+
+    let previous = queries::get_backups(pool, guild_id, 1).await?;
+    let unchanged = previous.first()
+        .and_then(|backup| GuildSnapshot::from_json(&backup.data).ok())
+        .is_some_and(|latest| latest.same_layout(&snapshot));
+    if unchanged {
+        tracing::debug!("Skipping automatic backup of guild {}: layout unchanged", guild_id);
+        return Ok(None);
+    }
+
+    let backup = queries::create_backup(pool, guild_id, Some("automatic"), serde_json::to_value(&snapshot)?, None).await?;
+    let pruned = queries::prune_backups(pool, guild_id, config.retention.max(1) as i64).await?;
+    tracing::info!("Automatic backup #{} of guild {} created, {} old backups pruned", backup.version, guild_id, pruned);
+
+    Ok(Some(backup))
+}
+
+/// Compares a backup with the current state of the guild.
+pub fn plan(backup: &GuildSnapshot, current: &GuildSnapshot) -> RestorePlan {
+    let mut plan = RestorePlan::default();
+    let role_map = match_roles(backup, current);
+    let channel_map = match_channels(backup, current);
+
+    let current_roles: HashMap<u64, &RoleSnapshot> = current.roles.iter().map(|r| (r.id, r)).collect();
+    for role in &backup.roles {
+        match role_map.get(&role.id).and_then(|id| current_roles.get(id)) {
+            Some(existing) if !existing.managed && role_differs(role, existing, role.id == backup.guild_id) => {
+                plan.update_roles.push((existing.id, role.clone()));
+            }
+            Some(_) => {}
+            None if role.managed => plan.skipped_roles.push(role.name.clone()),
+            None => plan.create_roles.push(role.clone()),
+        }
+    }
+
+    let current_channels: HashMap<u64, &ChannelSnapshot> = current.categories.iter()
+        .chain(&current.channels)
+        .map(|c| (c.id, c))
+        .collect();
+    for channel in backup.categories.iter().chain(&backup.channels) {
+        match channel_map.get(&channel.id).and_then(|id| current_channels.get(id)) {
+            Some(existing) if channel_differs(channel, existing, &role_map, &channel_map) => {
+                plan.update_channels.push((existing.id, channel.clone()));
+            }
+            Some(_) => {}
+            None => plan.create_channels.push(channel.clone()),
+        }
+    }
+
+    plan.restore_settings = match (&backup.settings, &current.settings) {
+        (Some(settings), Some(current_settings)) => {
+            &remap_settings(settings, backup.guild_id == current.guild_id, &role_map, &channel_map) != current_settings
+        }
+        (Some(_), None) => true,
+        _ => false,
+    };
+
+    plan
+}
+
+/// Recreates missing roles and channels, reapplies changed settings and
+/// overwrites, and restores Kitsune's configuration. Objects that exist now
+/// but aren't in the backup are left alone.
+pub async fn restore(
+    http: &serenity::Http,
+    pool: &PgPool,
+    guild_id: i64,
+    backup: &GuildSnapshot,
+) -> Result<RestoreReport> {
+    let guild = serenity::GuildId::new(guild_id as u64);
+    let current = capture(http, pool, guild_id).await?;
+    let plan = plan(backup, &current);
+    let mut role_map = match_roles(backup, &current);
+    let mut channel_map = match_channels(backup, &current);
+    let mut report = RestoreReport::default();
+
+    for role in &plan.create_roles {
+        let builder = serenity::EditRole::new()
+            .name(&role.name)
+            .colour(role.color)
+            .permissions(serenity::Permissions::from_bits_truncate(role.permissions))
+            .hoist(role.hoist)
+            .mentionable(role.mentionable)
+            .position(role.position)
+            .audit_log_reason(AUDIT_REASON);
+
+        match guild.create_role(http, builder).await {
+            Ok(created) => {
+                role_map.insert(role.id, created.id.get());
+                report.created += 1;
+            }
+            Err(e) => report.failed.push(format!("role {}: {}", role.name, e)),
+        }
+    }
+
+    for (current_id, role) in &plan.update_roles {
+        let permissions = serenity::Permissions::from_bits_truncate(role.permissions);
+        let builder = if role.id == backup.guild_id {
+            serenity::EditRole::new().permissions(permissions)
+        } else {
+            serenity::EditRole::new()
+                .name(&role.name)
+                .colour(role.color)
+                .permissions(permissions)
+                .hoist(role.hoist)
+                .mentionable(role.mentionable)
+        };
+
+        match guild.edit_role(http, serenity::RoleId::new(*current_id), builder.audit_log_reason(AUDIT_REASON)).await {
+            Ok(_) => report.updated += 1,
+            Err(e) => report.failed.push(format!("role {}: {}", role.name, e)),
+        }
+    }
+
+    // Categories come first in the plan, so channels can be parented to
+    // categories recreated earlier in this loop.
+    for channel in &plan.create_channels {
+        let Some(kind) = kind_from_name(&channel.kind) else {
+            continue;
+        };
+
+        let mut builder = serenity::CreateChannel::new(&channel.name)
+            .kind(kind)
+            .position(channel.position)
+            .nsfw(channel.nsfw)
+            .permissions(map_overwrites(&channel.overwrites, &role_map))
+            .audit_log_reason(AUDIT_REASON);
+
+        if let Some(parent) = channel.parent_id.and_then(|id| channel_map.get(&id)) {
+            builder = builder.category(serenity::ChannelId::new(*parent));
+        }
+        if let Some(topic) = &channel.topic {
+            builder = builder.topic(topic);
+        }
+        if let Some(seconds) = channel.rate_limit_per_user.filter(|s| *s > 0) {
+            builder = builder.rate_limit_per_user(seconds);
+        }
+        if let Some(bitrate) = channel.bitrate {
+            builder = builder.bitrate(bitrate);
+        }
+        if let Some(limit) = channel.user_limit {
+            builder = builder.user_limit(limit);
+        }
+
+        match guild.create_channel(http, builder).await {
+            Ok(created) => {
+                channel_map.insert(channel.id, created.id.get());
+                report.created += 1;
+            }
+            Err(e) => report.failed.push(format!("channel #{}: {}", channel.name, e)),
+        }
+    }
+
+    for (current_id, channel) in &plan.update_channels {
+        let mut builder = serenity::EditChannel::new()
+            .name(&channel.name)
+            .position(channel.position)
+            .nsfw(channel.nsfw)
+            .permissions(map_overwrites(&channel.overwrites, &role_map))
+            .audit_log_reason(AUDIT_REASON);
+
+        if channel.kind != "category" {
+            let parent = channel.parent_id
+                .and_then(|id| channel_map.get(&id))
+                .map(|id| serenity::ChannelId::new(*id));
+            builder = builder.category(parent);
+        }
+        if let Some(topic) = &channel.topic {
+            builder = builder.topic(topic);
+        }
+
+        match serenity::ChannelId::new(*current_id).edit(http, builder).await {
+            Ok(_) => report.updated += 1,
+            Err(e) => report.failed.push(format!("channel #{}: {}", channel.name, e)),
+        }
+    }
+
+    if plan.restore_settings {
+        if let Some(settings) = &backup.settings {
+            let settings = remap_settings(settings, backup.guild_id == guild.get(), &role_map, &channel_map);
+            queries::restore_guild_settings(pool, guild_id, &settings).await?;
+            report.updated += 1;
+        }
+    }
+
+    tracing::warn!(
+        "Restored backup of guild {} into guild {}: {} created, {} updated, {} failed",
+        backup.guild_id, guild_id, report.created, report.updated, report.failed.len()
+    );
+
+    Ok(report)
+}
+
+/// Downloadable form of a snapshot. `checksum` is the SHA-256 of `snapshot`
+/// serialized as compact JSON with sorted keys, so edits or truncation are
+/// caught on upload.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BackupArchive {
+    pub format: String,
+    pub format_version: u32,
+    pub exported_at: DateTime<Utc>,
+    #[serde(default)]
+    pub label: Option<String>,
+    pub checksum: String,
+    pub snapshot: serde_json::Value,
+}
+
+pub fn export_archive(snapshot: &GuildSnapshot, label: Option<&str>) -> Result<Vec<u8>> {
+    let snapshot = serde_json::to_value(snapshot)?;
+    let archive = BackupArchive {
+        format: ARCHIVE_FORMAT.to_string(),
+        format_version: SNAPSHOT_VERSION,
+        exported_at: Utc::now(),
+        label: label.map(str::to_string),
+        checksum: checksum(&snapshot)?,
+        snapshot,
+    };
+
+    Ok(serde_json::to_vec_pretty(&archive)?)
+}
+
+/// Parses and verifies an uploaded archive.
+pub fn import_archive(data: &[u8]) -> Result<GuildSnapshot> {
+    let archive: BackupArchive = serde_json::from_slice(data).context("File is not a Kitsune backup archive")?;
+
+    if archive.format != ARCHIVE_FORMAT {
+        bail!("Unknown archive format: {}", archive.format);
+    }
+    if archive.format_version > SNAPSHOT_VERSION {
+        bail!(
+            "Archive format version {} is newer than this Kitsune supports ({})",
+            archive.format_version,
+            SNAPSHOT_VERSION
+        );
+    }
+
+    let expected = checksum(&archive.snapshot)?;
+    if !archive.checksum.eq_ignore_ascii_case(&expected) {
+        bail!("Checksum mismatch: the archive was modified or is incomplete");
+    }
+
+    let snapshot = GuildSnapshot::from_json(&archive.snapshot)?;
+    // The checksum only catches accidents; anyone can recompute it, and a
+    // zero ID would make serenity panic halfway through a restore.
+    if let Some(object) = snapshot.zero_id() {
+        bail!("Invalid archive: {} has ID 0", object);
+    }
+
+    Ok(snapshot)
+}
+
+fn checksum(snapshot: &serde_json::Value) -> Result<String> {
+    let digest = Sha256::digest(serde_json::to_vec(snapshot)?);
+    Ok(format!("sha256:{:x}", digest))
+}
+
+fn kind_name(kind: serenity::ChannelType) -> Option<&'static str> {
+    match kind {
+        serenity::ChannelType::Text => Some("text"),
+        serenity::ChannelType::Voice => Some("voice"),
+        serenity::ChannelType::Category => Some("category"),
+        serenity::ChannelType::News => Some("news"),
+        serenity::ChannelType::Stage => Some("stage"),
+        serenity::ChannelType::Forum => Some("forum"),
+        _ => None,
+    }
+}
+
+fn kind_from_name(kind: &str) -> Option<serenity::ChannelType> {
+    match kind {
+        "text" => Some(serenity::ChannelType::Text),
+        "voice" => Some(serenity::ChannelType::Voice),
+        "category" => Some(serenity::ChannelType::Category),
+        "news" => Some(serenity::ChannelType::News),
+        "stage" => Some(serenity::ChannelType::Stage),
+        "forum" => Some(serenity::ChannelType::Forum),
+        _ => None,
+    }
+}
+
+fn overwrite_snapshot(overwrite: &serenity::PermissionOverwrite) -> Option<OverwriteSnapshot> {
+    let (target, id) = match overwrite.kind {
+        serenity::PermissionOverwriteType::Role(id) => ("role", id.get()),
+        serenity::PermissionOverwriteType::Member(id) => ("member", id.get()),
+        _ => return None,
+    };
+
+    Some(OverwriteSnapshot {
+        target: target.to_string(),
+        id,
+        allow: overwrite.allow.bits(),
+        deny: overwrite.deny.bits(),
+    })
+}
+
+/// Translates backed-up overwrites to the guild's current role IDs. Role
+/// overwrites whose role no longer exists are dropped.
+fn map_overwrites(overwrites: &[OverwriteSnapshot], role_map: &HashMap<u64, u64>) -> Vec<serenity::PermissionOverwrite> {
+    overwrites.iter()
+        .filter_map(|o| {
+            let kind = match o.target.as_str() {
+                "role" => serenity::PermissionOverwriteType::Role(serenity::RoleId::new(*role_map.get(&o.id)?)),
+                "member" => serenity::PermissionOverwriteType::Member(serenity::UserId::new(o.id)),
+                _ => return None,
+            };
+
+            Some(serenity::PermissionOverwrite {
+                allow: serenity::Permissions::from_bits_truncate(o.allow),
+                deny: serenity::Permissions::from_bits_truncate(o.deny),
+                kind,
+            })
+        })
+        .collect()
+}
+
+/// Maps backed-up role IDs to current ones. @everyone always maps to the
+/// target guild's @everyone, so a backup can be restored into another guild.
+fn match_roles(backup: &GuildSnapshot, current: &GuildSnapshot) -> HashMap<u64, u64> {
+    let mut map = HashMap::from([(backup.guild_id, current.guild_id)]);
+    let mut claimed: HashSet<u64> = HashSet::from([current.guild_id]);
+    let current_ids: HashSet<u64> = current.roles.iter().map(|r| r.id).collect();
+
+    for role in &backup.roles {
+        if role.id != backup.guild_id && current_ids.contains(&role.id) {
+            map.insert(role.id, role.id);
+            claimed.insert(role.id);
+        }
+    }
+
+    for role in &backup.roles {
+        if map.contains_key(&role.id) {
+            continue;
+        }
+        if let Some(existing) = current.roles.iter().find(|r| r.name == role.name && !claimed.contains(&r.id)) {
+            map.insert(role.id, existing.id);
+            claimed.insert(existing.id);
+        }
+    }
+
+    map
+}
+
+fn match_channels(backup: &GuildSnapshot, current: &GuildSnapshot) -> HashMap<u64, u64> {
+    let mut map = HashMap::new();
+    let mut claimed = HashSet::new();
+    let current_all: Vec<&ChannelSnapshot> = current.categories.iter().chain(&current.channels).collect();
+    let backup_all: Vec<&ChannelSnapshot> = backup.categories.iter().chain(&backup.channels).collect();
+
+    for channel in &backup_all {
+        if current_all.iter().any(|c| c.id == channel.id) {
+            map.insert(channel.id, channel.id);
+            claimed.insert(channel.id);
+        }
+    }
+
+    for channel in &backup_all {
+        if map.contains_key(&channel.id) {
+            continue;
+        }
+        if let Some(existing) = current_all.iter()
+            .find(|c| c.name == channel.name && c.kind == channel.kind && !claimed.contains(&c.id))
+        {
+            map.insert(channel.id, existing.id);
+            claimed.insert(existing.id);
+        }
+    }
+
+    map
+}
+
+/// Points channel and role references in Kitsune's settings at their
+/// restored counterparts. References that can't be resolved are kept for the
+/// source guild and dropped when restoring into another guild.
+fn remap_settings(
+    settings: &GuildSettingsBackup,
+    same_guild: bool,
+    role_map: &HashMap<u64, u64>,
+    channel_map: &HashMap<u64, u64>,
+) -> GuildSettingsBackup {
+    let remap = |value: &serde_json::Value, map: &HashMap<u64, u64>| -> Option<serde_json::Value> {
+        let id = value.as_u64()?;
+        match map.get(&id) {
+            Some(mapped) => Some(serde_json::json!(mapped)),
+            None if same_guild => Some(value.clone()),
+            None => None,
+        }
+    };
+
+    let mut settings = settings.clone();
+    let Some(config) = settings.config.as_object_mut() else {
+        return settings;
+    };
+
+    for key in ["alert_channel_id", "log_channel_id", "report_channel_id"] {
+        if let Some(value) = config.get(key).filter(|v| !v.is_null()) {
+            let mapped = remap(value, channel_map).unwrap_or(serde_json::Value::Null);
+            config.insert(key.to_string(), mapped);
+        }
+    }
+    for (key, map) in [("lockdown_channels", channel_map), ("notify_roles", role_map)] {
+        if let Some(values) = config.get(key).and_then(|v| v.as_array()) {
+            let mapped: Vec<serde_json::Value> = values.iter().filter_map(|v| remap(v, map)).collect();
+            config.insert(key.to_string(), serde_json::Value::Array(mapped));
+        }
+    }
+
+    settings
+}
+
+fn role_differs(backup: &RoleSnapshot, current: &RoleSnapshot, everyone: bool) -> bool {
+    if everyone {
+        return backup.permissions != current.permissions;
+    }
+
+    backup.name != current.name
+        || backup.color != current.color
+        || backup.permissions != current.permissions
+        || backup.hoist != current.hoist
+        || backup.mentionable != current.mentionable
+}
+
+fn channel_differs(
+    backup: &ChannelSnapshot,
+    current: &ChannelSnapshot,
+    role_map: &HashMap<u64, u64>,
+    channel_map: &HashMap<u64, u64>,
+) -> bool {
+    let parent = backup.parent_id.and_then(|id| channel_map.get(&id).copied());
+    let parent_changed = backup.kind != "category" && parent != current.parent_id;
+    let topic_changed = backup.topic.is_some() && backup.topic != current.topic;
+
+    // Overwrites for roles that no longer exist can't be restored, so they are
+    // left out of the comparison the same way map_overwrites drops them.
+    let expected: HashSet<OverwriteSnapshot> = backup.overwrites.iter()
+        .filter_map(|o| match o.target.as_str() {
+            "role" => Some(OverwriteSnapshot { id: *role_map.get(&o.id)?, ..o.clone() }),
+            _ => Some(o.clone()),
+        })
+        .collect();
+    let actual: HashSet<OverwriteSnapshot> = current.overwrites.iter().cloned().collect();
+
+    backup.name != current.name
+        || backup.nsfw != current.nsfw
+        || backup.position != current.position
+        || parent_changed
+        || topic_changed
+        || expected != actual
+}
+
+/// Snowflakes are written as strings so JSON consumers don't lose precision.
+mod snowflake {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Id {
+        Number(u64),
+        Text(String),
+    }
+
+    impl Id {
+        fn parse<E: serde::de::Error>(self) -> Result<u64, E> {
+            match self {
+                Id::Number(id) => Ok(id),
+                Id::Text(id) => id.parse().map_err(E::custom),
+            }
+        }
+    }
+
+    pub fn serialize<S: Serializer>(id: &u64, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&id.to_string())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+        Id::deserialize(deserializer)?.parse()
+    }
+
+    pub mod option {
+        use super::Id;
+        use serde::{Deserialize, Deserializer, Serializer};
+
+        pub fn serialize<S: Serializer>(id: &Option<u64>, serializer: S) -> Result<S::Ok, S::Error> {
+            match id {
+                Some(id) => serializer.serialize_some(&id.to_string()),
+                None => serializer.serialize_none(),
+            }
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<u64>, D::Error> {
+            Option::<Id>::deserialize(deserializer)?.map(Id::parse).transpose()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GUILD: u64 = 1;
+
+    fn role(id: u64, name: &str) -> RoleSnapshot {
+        RoleSnapshot {
+            id,
+            name: name.to_string(),
+            color: 0,
+            permissions: 0,
+            position: 1,
+            hoist: false,
+            mentionable: false,
+            managed: false,
+        }
+    }
+
+    fn channel(id: u64, name: &str, kind: &str, parent_id: Option<u64>, overwrites: Vec<OverwriteSnapshot>) -> ChannelSnapshot {
+        ChannelSnapshot {
+            id,
+            name: name.to_string(),
+            kind: kind.to_string(),
+            position: 0,
+            parent_id,
+            topic: None,
+            nsfw: false,
+            rate_limit_per_user: None,
+            bitrate: None,
+            user_limit: None,
+            overwrites,
+        }
+    }
+
+    fn overwrite(target: &str, id: u64, allow: u64) -> OverwriteSnapshot {
+        OverwriteSnapshot { target: target.to_string(), id, allow, deny: 0 }
+    }
+
+    fn snapshot(guild_id: u64, roles: Vec<RoleSnapshot>, categories: Vec<ChannelSnapshot>, channels: Vec<ChannelSnapshot>) -> GuildSnapshot {
+        GuildSnapshot {
+            format_version: SNAPSHOT_VERSION,
+            guild_id,
+            guild_name: "Test".to_string(),
+            captured_at: Utc::now(),
+            roles,
+            categories,
+            channels,
+            settings: None,
+        }
+    }
+
+    fn backup() -> GuildSnapshot {
+        snapshot(
+            GUILD,
+            vec![role(GUILD, "@everyone"), role(10, "Moderators")],
+            vec![channel(100, "Community", "category", None, Vec::new())],
+            vec![channel(101, "general", "text", Some(100), vec![overwrite("role", 10, 1024), overwrite("member", 5, 2048)])],
+        )
+    }
+
+    #[test]
+    fn unchanged_guild_needs_no_restore() {
+        let backup = backup();
+        assert!(plan(&backup, &backup.clone()).is_empty());
+    }
+
+    #[test]
+    fn second_restore_is_a_no_op() {
+        let backup = backup();
+        let wiped = snapshot(GUILD, vec![role(GUILD, "@everyone")], Vec::new(), Vec::new());
+
+        let first = plan(&backup, &wiped);
+        assert_eq!(first.create_roles.len(), 1);
+        assert_eq!(first.create_channels.len(), 2);
+
+        // What the guild looks like after the first restore: the same objects
+        // under new IDs, with overwrites pointing at the recreated role.
+        let restored = snapshot(
+            GUILD,
+            vec![role(GUILD, "@everyone"), role(20, "Moderators")],
+            vec![channel(200, "Community", "category", None, Vec::new())],
+            vec![channel(201, "general", "text", Some(200), vec![overwrite("role", 20, 1024), overwrite("member", 5, 2048)])],
+        );
+
+        let second = plan(&backup, &restored);
+        assert!(second.is_empty(), "unexpected changes: {:?}", second.describe());
+    }
+
+    #[test]
+    fn overwrites_of_missing_roles_are_ignored() {
+        let mut integration = role(11, "Music Bot");
+        integration.managed = true;
+        let backup = snapshot(
+            GUILD,
+            vec![role(GUILD, "@everyone"), integration],
+            Vec::new(),
+            vec![channel(101, "general", "text", None, vec![overwrite("role", 11, 1024)])],
+        );
+        let current = snapshot(
+            GUILD,
+            vec![role(GUILD, "@everyone")],
+            Vec::new(),
+            vec![channel(101, "general", "text", None, Vec::new())],
+        );
+
+        let plan = plan(&backup, &current);
+        assert_eq!(plan.skipped_roles, vec!["Music Bot".to_string()]);
+        assert!(plan.create_roles.is_empty());
+        assert!(plan.update_channels.is_empty());
+    }
+
+    #[test]
+    fn everyone_maps_to_the_target_guild() {
+        let mut source_everyone = role(GUILD, "@everyone");
+        source_everyone.permissions = 0x400;
+        let backup = snapshot(GUILD, vec![source_everyone], Vec::new(), Vec::new());
+
+        let mut target_everyone = role(2, "@everyone");
+        target_everyone.permissions = 0x400;
+        let current = snapshot(2, vec![target_everyone.clone()], Vec::new(), Vec::new());
+        assert!(plan(&backup, &current).is_empty());
+
+        target_everyone.permissions = 0;
+        let current = snapshot(2, vec![target_everyone], Vec::new(), Vec::new());
+        let plan = plan(&backup, &current);
+        assert!(plan.create_roles.is_empty());
+        assert_eq!(plan.update_roles.iter().map(|(id, _)| *id).collect::<Vec<_>>(), vec![2]);
+    }
+
+    #[test]
+    fn moved_channel_is_updated_in_place() {
+        let backup = backup();
+        let mut current = backup.clone();
+        current.channels[0].position = 3;
+        current.channels[0].parent_id = None;
+
+        let plan = plan(&backup, &current);
+        assert!(plan.create_channels.is_empty());
+        assert_eq!(plan.update_channels.iter().map(|(id, _)| *id).collect::<Vec<_>>(), vec![101]);
+    }
+
+    #[test]
+    fn archive_round_trip() {
+        let backup = backup();
+        let data = export_archive(&backup, Some("test")).unwrap();
+        assert!(import_archive(&data).unwrap().same_layout(&backup));
+
+        let tampered = String::from_utf8(data).unwrap().replace("general", "hacked");
+        assert!(import_archive(tampered.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn archive_with_zero_ids_is_rejected() {
+        let mut zero_member = backup();
+        zero_member.channels[0].overwrites.push(overwrite("member", 0, 1024));
+        assert!(import_archive(&export_archive(&zero_member, None).unwrap()).is_err());
+
+        let mut zero_parent = backup();
+        zero_parent.channels[0].parent_id = Some(0);
+        assert!(import_archive(&export_archive(&zero_parent, None).unwrap()).is_err());
+    }
+}
@@ -0,0 +1,281 @@
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::{DateTime, Utc};
+use poise::serenity_prelude as serenity;
+use serde::{Deserialize, Serialize};
+
+use crate::database::models::BlacklistedUser;
+
+pub const MAX_IMPORT_ENTRIES: usize = 10_000;
+
+/// One blacklist entry as it appears in import/export files. User IDs are
+/// written as strings so JSON consumers don't lose precision on snowflakes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlacklistRecord {
+    #[serde(deserialize_with = "deserialize_user_id")]
+    pub user_id: String,
+    #[serde(default)]
+    pub reason: Option<String>,
+    #[serde(default = "default_action")]
+    pub action: String,
+    #[serde(default)]
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Json,
+    Csv,
+}
+
+impl Format {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "json" => Some(Format::Json),
+            "csv" => Some(Format::Csv),
+            _ => None,
+        }
+    }
+
+    pub fn from_filename(name: &str) -> Option<Self> {
+        let extension = name.rsplit('.').next()?;
+        Self::parse(extension)
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Format::Json => "json",
+            Format::Csv => "csv",
+        }
+    }
+}
+
+impl BlacklistRecord {
+    pub fn user_id(&self) -> Result<i64> {
+        let id = parse_user_id(&self.user_id)
+            .with_context(|| format!("Invalid user ID: {}", self.user_id))?;
+        Ok(id as i64)
+    }
+}
+
+/// Discord IDs are never 0, and serenity panics when building one from it.
+pub fn parse_user_id(value: &str) -> Option<u64> {
+    value.trim().parse::<u64>().ok().filter(|id| *id != 0)
+}
+
+impl From<&BlacklistedUser> for BlacklistRecord {
+    fn from(entry: &BlacklistedUser) -> Self {
+        Self {
+            user_id: entry.user_id.to_string(),
+            reason: entry.reason.clone(),
+            action: entry.action.clone(),
+            expires_at: entry.expires_at,
+        }
+    }
+}
+
+pub fn parse_action(action: &str) -> Option<&'static str> {
+    match action.trim().to_lowercase().as_str() {
+        "ban" => Some("ban"),
+        "kick" => Some("kick"),
+        _ => None,
+    }
+}
+
+/// Gives a user who is already in the server the treatment they would get on
+/// join. Returns `Ok(false)` when they aren't a member.
+pub async fn enforce_on_member(
+    cache_http: impl serenity::CacheHttp,
+    guild_id: serenity::GuildId,
+    user_id: serenity::UserId,
+    action: &str,
+    reason: Option<&str>,
+) -> serenity::Result<bool> {
+    if guild_id.member(&cache_http, user_id).await.is_err() {
+        return Ok(false);
+    }
+
+    let audit_reason = format!("Kitsune blacklist: {}", reason.unwrap_or("no reason provided"));
+    if action == "kick" {
+        guild_id.kick_with_reason(cache_http.http(), user_id, &audit_reason).await?;
+    } else {
+        guild_id.ban_with_reason(cache_http.http(), user_id, 0, &audit_reason).await?;
+    }
+
+    Ok(true)
+}
+
+fn default_action() -> String {
+    "ban".to_string()
+}
+
+fn deserialize_user_id<'de, D>(deserializer: D) -> std::result::Result<String, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Id {
+        Number(u64),
+        Text(String),
+    }
+
+    Ok(match Id::deserialize(deserializer)? {
+        Id::Number(id) => id.to_string(),
+        Id::Text(id) => id,
+    })
+}
+
+pub fn export(entries: &[BlacklistedUser], format: Format) -> Result<Vec<u8>> {
+    let records: Vec<BlacklistRecord> = entries.iter().map(BlacklistRecord::from).collect();
+
+    match format {
+        Format::Json => Ok(serde_json::to_vec_pretty(&records)?),
+        Format::Csv => {
+            let mut out = String::from("user_id,action,expires_at,reason\n");
+            for record in &records {
+                out.push_str(&format!(
+                    "{},{},{},{}\n",
+                    record.user_id,
+                    record.action,
+                    record.expires_at.map(|t| t.to_rfc3339()).unwrap_or_default(),
+                    csv_escape(record.reason.as_deref().unwrap_or(""))
+                ));
+            }
+            Ok(out.into_bytes())
+        }
+    }
+}
+
+pub fn import(data: &[u8], format: Format) -> Result<Vec<BlacklistRecord>> {
+    let text = std::str::from_utf8(data).context("File is not valid UTF-8")?;
+
+    let records = match format {
+        Format::Json => serde_json::from_str::<Vec<BlacklistRecord>>(text)
+            .context("Expected a JSON array of blacklist entries")?,
+        Format::Csv => parse_csv(text)?,
+    };
+
+    if records.len() > MAX_IMPORT_ENTRIES {
+        bail!("Too many entries ({}), the limit is {}", records.len(), MAX_IMPORT_ENTRIES);
+    }
+
+    Ok(records)
+}
+
+fn parse_csv(text: &str) -> Result<Vec<BlacklistRecord>> {
+    let mut lines = text.lines().filter(|line| !line.trim().is_empty());
+
+    let header = lines.next().ok_or_else(|| anyhow!("CSV file is empty"))?;
+    let columns: Vec<String> = split_csv_line(header).iter().map(|c| c.trim().to_lowercase()).collect();
+    let column = |name: &str| columns.iter().position(|c| c == name);
+
+    let user_col = column("user_id").ok_or_else(|| anyhow!("CSV header must contain a user_id column"))?;
+    let reason_col = column("reason");
+    let action_col = column("action");
+    let expires_col = column("expires_at");
+
+    let mut records = Vec::new();
+    for (line_no, line) in lines.enumerate() {
+        let fields = split_csv_line(line);
+        let field = |index: Option<usize>| {
+            index
+                .and_then(|i| fields.get(i))
+                .map(|f| f.trim().to_string())
+                .filter(|f| !f.is_empty())
+        };
+
+        let user_id = field(Some(user_col))
+            .ok_or_else(|| anyhow!("Line {}: missing user_id", line_no + 2))?;
+        let expires_at = field(expires_col)
+            .map(|t| DateTime::parse_from_rfc3339(&t).map(|t| t.with_timezone(&Utc)))
+            .transpose()
+            .with_context(|| format!("Line {}: expires_at must be an RFC 3339 timestamp", line_no + 2))?;
+
+        records.push(BlacklistRecord {
+            user_id,
+            reason: field(reason_col),
+            action: field(action_col).unwrap_or_else(default_action),
+            expires_at,
+        });
+    }
+
+    Ok(records)
+}
+
+fn split_csv_line(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                current.push('"');
+                chars.next();
+            }
+            '"' => in_quotes = !in_quotes,
+            ',' if !in_quotes => fields.push(std::mem::take(&mut current)),
+            _ => current.push(c),
+        }
+    }
+    fields.push(current);
+
+    fields
+}
+
+fn csv_escape(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\"").replace(['\n', '\r'], " "))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_quoted_fields() {
+        let fields = split_csv_line(r#"123,"Spam, scams and ""free nitro""",ban"#);
+        assert_eq!(fields, vec!["123", r#"Spam, scams and "free nitro""#, "ban"]);
+    }
+
+    #[test]
+    fn csv_columns_in_any_order() {
+        let csv = "reason,User_ID,action\n\"raider, alt account\",42,kick\n\n,7,\n";
+        let records = parse_csv(csv).unwrap();
+
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].user_id, "42");
+        assert_eq!(records[0].reason.as_deref(), Some("raider, alt account"));
+        assert_eq!(records[0].action, "kick");
+        assert_eq!(records[1].user_id, "7");
+        assert_eq!(records[1].reason, None);
+        assert_eq!(records[1].action, "ban");
+    }
+
+    #[test]
+    fn csv_requires_user_id() {
+        assert!(parse_csv("reason,action\nspam,ban\n").is_err());
+        assert!(parse_csv("user_id,reason\n,spam\n").is_err());
+        assert!(parse_csv("user_id,expires_at\n1,tomorrow\n").is_err());
+    }
+
+    #[test]
+    fn csv_escape_round_trip() {
+        for reason in ["plain", "a, b", r#"said "hi""#] {
+            let line = format!("1,{}", csv_escape(reason));
+            assert_eq!(split_csv_line(&line)[1], reason);
+        }
+    }
+
+    #[test]
+    fn user_ids() {
+        assert_eq!(parse_user_id(" 123 "), Some(123));
+        assert_eq!(parse_user_id("0"), None);
+        assert_eq!(parse_user_id("-5"), None);
+        assert_eq!(parse_user_id("abc"), None);
+    }
+}
@@ -5,6 +5,7 @@ pub mod honeypot;
 pub mod stats;
 pub mod reputation;
 pub mod lockdown_cmd;
+pub mod jobs;
 pub mod whitelist;
 pub mod testing;
 pub mod help;
@@ -18,7 +19,7 @@ use super::{Context, Error};
 
 use security::{status, scan, check, analyze, reputation_cmd};
 use moderation::{ban, kick, timeout, warn, unban, pardon};
-use config::{view, automod_toggle, channel, notify, raid, behavior, ml};
+use config::{view, automod_toggle, automod_threshold, channel, notify, raid, antinuke, behavior, ml};
 use honeypot::honeypot;
 use stats::{stats, leaderboard, report, forensics, export};
 use reputation::{reputation_query, reputation_report, reputation_trust, reputation_sync, reputation_servers, reputation_appeal};
@@ -37,7 +38,7 @@ use integration::{webhook, api};
     subcommands(
         "status", "scan", "check", "analyze",
         "ban", "kick", "timeout", "warn", "unban", "pardon",
-        "view", "automod_toggle", "channel", "notify", "raid", "behavior", "ml",
+        "view", "automod_toggle", "automod_threshold", "channel", "notify", "raid", "antinuke", "behavior", "ml",
         "honeypot",
         "stats", "leaderboard", "report", "forensics", "export",
         "lockdown", "verification"
@@ -1,5 +1,11 @@
 use poise::serenity_prelude as serenity;
-use crate::bot::{Context, Error};
+use serde_json::json;
+use crate::bot::{Context, Error, backup as snapshot, scheduler::{self, Job}};
+use crate::config::BackupFrequency;
+use crate::database::queries;
+
+const MAX_DIFF_LINES: usize = 25;
+const MAX_ARCHIVE_BYTES: u32 = 8 * 1024 * 1024;
 
 #[poise::command(
     slash_command,
@@ -13,11 +19,33 @@ pub async fn backup(ctx: Context<'_>) -> Result<(), Error> {
 }
 
 #[poise::command( slash_command, guild_only = true, required_permissions = "ADMINISTRATOR", rename = "create")]
-pub async fn backup_create(ctx: Context<'_>) -> Result<(), Error> {
+pub async fn backup_create(
+    ctx: Context<'_>,
+    #[description = "Optional label for this backup"] label: Option<String>,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or("Command must be used in a guild")?.get() as i64;
+    ctx.defer().await?;
+    
+    let snapshot = snapshot::capture(ctx.http(), &ctx.data().pool, guild_id).await?;
+    let backup = queries::create_backup(
+        &ctx.data().pool,
+        guild_id,
+        label.as_deref(),
+        serde_json::to_value(&snapshot)?,
+        Some(ctx.author().id.get() as i64)
+    ).await?;
+    
     ctx.send(poise::CreateReply::default().embed(
         serenity::CreateEmbed::new()
             .title("💾 Backup Created")
-            .description("Configuration backup created successfully\n\n**Backup ID:** backup_001\n**Timestamp:** Now")
+            .description(format!(
+                "Server backup created successfully\n\n**Version:** #{}\n**Roles:** {}\n**Categories:** {}\n**Channels:** {}\n**Timestamp:** <t:{}:F>",
+                backup.version,
+                snapshot.roles.len(),
+                snapshot.categories.len(),
+                snapshot.channels.len(),
+                backup.created_at.timestamp()
+            ))
             .color(0x2ecc71)
             .footer(serenity::CreateEmbedFooter::new("Kitsune Backup"))
     )).await?;
@@ -28,13 +56,126 @@ pub async fn backup_create(ctx: Context<'_>) -> Result<(), Error> {
 #[poise::command( slash_command, guild_only = true, required_permissions = "ADMINISTRATOR", rename = "backup_restore")]
 pub async fn backup_restore(
     ctx: Context<'_>,
-    #[description = "Backup ID to restore"] backup_id: String,
+    #[description = "Backup version to restore"] version: i32,
+    #[description = "Apply the changes (default: show a dry-run diff)"] apply: Option<bool>,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or("Command must be used in a guild")?.get() as i64;
+    ctx.defer().await?;
+    
+    let Some(backup) = queries::get_backup(&ctx.data().pool, guild_id, version).await? else {
+        return send_not_found(ctx, version).await;
+    };
+    
+    let backup_snapshot = snapshot::GuildSnapshot::from_json(&backup.data)?;
+    preview_or_restore(
+        ctx,
+        guild_id,
+        &backup_snapshot,
+        &format!("backup #{}", version),
+        &format!("/admin backup_restore version:{} apply:True", version),
+        apply.unwrap_or(false)
+    ).await
+}
+
+#[poise::command( slash_command, guild_only = true, required_permissions = "ADMINISTRATOR", rename = "backup_upload")]
+pub async fn backup_upload(
+    ctx: Context<'_>,
+    #[description = "Archive downloaded with /admin backup_download"] file: serenity::Attachment,
+    #[description = "Apply the changes (default: show a dry-run diff)"] apply: Option<bool>,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or("Command must be used in a guild")?.get() as i64;
+    
+    if file.size > MAX_ARCHIVE_BYTES {
+        return Err("File is too large (max 8 MB)".into());
+    }
+    
+    ctx.defer().await?;
+    
+    let data = file.download().await?;
+    let backup_snapshot = snapshot::import_archive(&data)?;
+    
+    preview_or_restore(
+        ctx,
+        guild_id,
+        &backup_snapshot,
+        &format!("archive of **{}**", backup_snapshot.guild_name),
+        "/admin backup_upload with apply:True",
+        apply.unwrap_or(false)
+    ).await
+}
+
+/// Shows the dry-run diff for a snapshot, or applies it when `apply` is set.
+async fn preview_or_restore(
+    ctx: Context<'_>,
+    guild_id: i64,
+    backup_snapshot: &snapshot::GuildSnapshot,
+    source: &str,
+    apply_hint: &str,
+    apply: bool,
 ) -> Result<(), Error> {
+    let current = snapshot::capture(ctx.http(), &ctx.data().pool, guild_id).await?;
+    let plan = snapshot::plan(backup_snapshot, &current);
+    
+    if plan.is_empty() {
+        ctx.send(poise::CreateReply::default().embed(
+            serenity::CreateEmbed::new()
+                .title("✅ Nothing to Restore")
+                .description(format!("The server already matches {}", source))
+                .color(0x2ecc71)
+                .footer(serenity::CreateEmbedFooter::new("Kitsune Backup"))
+        )).await?;
+        return Ok(());
+    }
+    
+    if !apply {
+        let lines = plan.describe();
+        let mut diff = lines.iter().take(MAX_DIFF_LINES).cloned().collect::<Vec<_>>().join("\n");
+        if lines.len() > MAX_DIFF_LINES {
+            diff.push_str(&format!("\n…and {} more changes", lines.len() - MAX_DIFF_LINES));
+        }
+        
+        ctx.send(poise::CreateReply::default().embed(
+            serenity::CreateEmbed::new()
+                .title("🔍 Restore Preview")
+                .description(format!(
+                    "Restoring {} would make these changes:\n\n{}\n\nObjects not in the backup are left untouched. Run `{}` to apply.",
+                    source, diff, apply_hint
+                ))
+                .color(0xf39c12)
+                .footer(serenity::CreateEmbedFooter::new("Kitsune Backup"))
+        )).await?;
+        return Ok(());
+    }
+    
+    let report = snapshot::restore(ctx.http(), &ctx.data().pool, guild_id, backup_snapshot).await?;
+    ctx.data().guild_configs.reload(&ctx.data().pool, guild_id).await?;
+    
+    let mut description = format!(
+        "Restored from {}\n\n**Created:** {}\n**Updated:** {}\n**Failed:** {}",
+        source, report.created, report.updated, report.failed.len()
+    );
+    if !report.failed.is_empty() {
+        description.push_str("\n\n");
+        description.push_str(&report.failed.iter().take(10).map(|f| format!("• {}", f)).collect::<Vec<_>>().join("\n"));
+    }
+    
     ctx.send(poise::CreateReply::default().embed(
         serenity::CreateEmbed::new()
             .title("♻️ Backup Restored")
-            .description(format!("Restored from backup: {}", backup_id))
-            .color(0x2ecc71)
+            .description(description)
+            .color(if report.failed.is_empty() { 0x2ecc71 } else { 0xf39c12 })
+            .footer(serenity::CreateEmbedFooter::new("Kitsune Backup"))
+    )).await?;
+    
+    Ok(())
+}
+
+async fn send_not_found(ctx: Context<'_>, version: i32) -> Result<(), Error> {
+    ctx.send(poise::CreateReply::default().embed(
+        serenity::CreateEmbed::new()
+            .title("❌ Backup Not Found")
+            .description(format!("No backup #{} exists for this server. Use `/admin backup_list` to see available backups.", version))
+            .color(0xe74c3c)
             .footer(serenity::CreateEmbedFooter::new("Kitsune Backup"))
     )).await?;
     
@@ -43,10 +184,32 @@ pub async fn backup_restore(
 
 #[poise::command( slash_command, guild_only = true, required_permissions = "ADMINISTRATOR", rename = "backup_list")]
 pub async fn backup_list(ctx: Context<'_>) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or("Command must be used in a guild")?.get() as i64;
+    let backups = queries::get_backups(&ctx.data().pool, guild_id, 15).await?;
+    
+    let description = if backups.is_empty() {
+        "No backups found".to_string()
+    } else {
+        backups.iter()
+            .map(|backup| {
+                let count = |key: &str| backup.data.get(key).and_then(|v| v.as_array()).map(|a| a.len()).unwrap_or(0);
+                format!(
+                    "**#{}** <t:{}:R>{} — {} roles, {} channels",
+                    backup.version,
+                    backup.created_at.timestamp(),
+                    backup.label.as_deref().map(|l| format!(" `{}`", l)).unwrap_or_default(),
+                    count("roles"),
+                    count("categories") + count("channels")
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+    
     ctx.send(poise::CreateReply::default().embed(
         serenity::CreateEmbed::new()
             .title("📋 Available Backups")
-            .description("No backups found")
+            .description(description)
             .color(0x3498db)
             .footer(serenity::CreateEmbedFooter::new("Kitsune Backup"))
     )).await?;
@@ -57,15 +220,33 @@ pub async fn backup_list(ctx: Context<'_>) -> Result<(), Error> {
 #[poise::command( slash_command, guild_only = true, required_permissions = "ADMINISTRATOR", rename = "backup_download")]
 pub async fn backup_download(
     ctx: Context<'_>,
-    #[description = "Backup ID to download"] backup_id: String,
+    #[description = "Backup version to download"] version: i32,
 ) -> Result<(), Error> {
-    ctx.send(poise::CreateReply::default().embed(
-        serenity::CreateEmbed::new()
-            .title("📥 Backup Download")
-            .description(format!("Preparing download for backup: {}", backup_id))
-            .color(0x3498db)
-            .footer(serenity::CreateEmbedFooter::new("Kitsune Backup"))
-    )).await?;
+    let guild_id = ctx.guild_id().ok_or("Command must be used in a guild")?.get() as i64;
+    
+    let Some(backup) = queries::get_backup(&ctx.data().pool, guild_id, version).await? else {
+        return send_not_found(ctx, version).await;
+    };
+    
+    let backup_snapshot = snapshot::GuildSnapshot::from_json(&backup.data)?;
+    let data = snapshot::export_archive(&backup_snapshot, backup.label.as_deref())?;
+    let filename = format!("kitsune-backup-{}-v{}.json", guild_id, backup.version);
+    
+    ctx.send(poise::CreateReply::default()
+        .embed(
+            serenity::CreateEmbed::new()
+                .title("📥 Backup Download")
+                .description(format!(
+                    "Backup #{} from <t:{}:F>\n\nRestore it on any server with `/admin backup_upload`.",
+                    backup.version,
+                    backup.created_at.timestamp()
+                ))
+                .color(0x3498db)
+                .footer(serenity::CreateEmbedFooter::new("Kitsune Backup"))
+        )
+        .attachment(serenity::CreateAttachment::bytes(data, filename))
+        .ephemeral(true)
+    ).await?;
     
     Ok(())
 }
@@ -73,13 +254,47 @@ pub async fn backup_download(
 #[poise::command( slash_command, guild_only = true, required_permissions = "ADMINISTRATOR", rename = "backup_schedule")]
 pub async fn backup_schedule(
     ctx: Context<'_>,
-    #[description = "Backup frequency"]
+    #[description = "Backup frequency: hourly, daily, weekly or off"]
     frequency: String,
+    #[description = "Automatic backups to keep"]
+    #[min = 1]
+    #[max = 1000]
+    retention: Option<u32>,
 ) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or("Command must be used in a guild")?.get() as i64;
+    
+    let parsed = BackupFrequency::parse(&frequency);
+    if parsed.is_none() && !frequency.trim().eq_ignore_ascii_case("off") {
+        return Err("Frequency must be one of: hourly, daily, weekly, off".into());
+    }
+    
+    let pool = &ctx.data().pool;
+    queries::set_guild_setting(pool, guild_id, "backup_frequency", json!(parsed.map(|f| f.as_str()).unwrap_or("off"))).await?;
+    if let Some(retention) = retention {
+        queries::set_guild_setting(pool, guild_id, "backup_retention", json!(retention)).await?;
+    }
+    let guild_config = ctx.data().guild_configs.reload(pool, guild_id).await?;
+    
+    let description = match parsed {
+        Some(frequency) => {
+            let job = scheduler::schedule_backup(pool, guild_id, frequency).await?;
+            format!(
+                "Automatic backups scheduled: **{}**\n**Next backup:** <t:{}:R>\n**Retention:** {} automatic backups\n\nUnchanged layouts are skipped. Manual backups are never pruned.",
+                frequency.as_str(),
+                job.run_at.timestamp(),
+                guild_config.backup.retention
+            )
+        }
+        None => {
+            queries::cancel_jobs_of_type(pool, guild_id, Job::CREATE_BACKUP).await?;
+            "Automatic backups disabled".to_string()
+        }
+    };
+    
     ctx.send(poise::CreateReply::default().embed(
         serenity::CreateEmbed::new()
-            .title("⏰ Backup Scheduled")
-            .description(format!("Automatic backups scheduled: **{}**", frequency))
+            .title("⏰ Backup Schedule Updated")
+            .description(description)
             .color(0x2ecc71)
             .footer(serenity::CreateEmbedFooter::new("Kitsune Backup"))
     )).await?;
@@ -1,27 +1,74 @@
 use poise::serenity_prelude as serenity;
-use crate::database::queries;
+use crate::database::{queries, models::ThreatLevel};
 use crate::bot::{Context, Error};
+use crate::security::anti_nuke::NukeAction;
+use serde_json::json;
 
 #[poise::command( slash_command, guild_only = true, required_permissions = "ADMINISTRATOR")]
 pub async fn view(ctx: Context<'_>) -> Result<(), Error> {
     let guild_id = ctx.guild_id().ok_or("Command must be used in a guild")?.get() as i64;
     let guild = queries::get_guild(&ctx.data().pool, guild_id).await?;
+    let effective = ctx.data().guild_configs.reload(&ctx.data().pool, guild_id).await?;
     
-    let automod_status = ctx.data().config.auto_mod.enabled;
     let lockdown_status = guild.as_ref().map(|g| g.lockdown_active).unwrap_or(false);
     
     let description = format!(
-        "**Auto-Moderation:** {}\n**Lockdown:** {}\n\n**Raid Detection Thresholds:**\n- 5s: {}\n- 30s: {}\n- 1m: {}\n- 5m: {}\n\n**Auto-Mod Thresholds:**\n- Low: {:.2}\n- Medium: {:.2}\n- High: {:.2}\n- Critical: {:.2}",
-        if automod_status { "✅ Enabled" } else { "❌ Disabled" },
+        "**Auto-Moderation:** {}\n**Lockdown:** {}\n\n**Raid Detection:** {}\n- 5s: {}\n- 30s: {}\n- 1m: {}\n- 5m: {}\n- New account: < {} days\n- Username similarity: {:.2}\n- Raid mode cooldown: {} min (auto-lockdown {})\n\n**Behavior Analysis:** {}\n- Message burst: {} messages\n- Spam similarity: {:.2}\n- Link spam: {} links\n- Mention spam: {} mentions\n\n**Machine Learning:** {}\n- Model: {}\n- Inference timeout: {}ms\n- Confidence: {:.2}\n\n**Auto-Mod Thresholds:**\n- Low: {:.2}\n- Medium: {:.2}\n- High: {:.2}\n- Critical: {:.2}\n\n**Channels:**\n- Alerts: {}\n- Logs: {}\n- Reports: {}\n- Lockdown: {}\n\n**Notifications:** {} (level {}+)\n**Automatic Backups:** {} (keep {})\n**Forensic Retention:** {} (detailed logging {})\n\n**Anti-Nuke:** {} (per {}s, {} trusted)\n{}",
+        if effective.auto_mod.enabled { "✅ Enabled" } else { "❌ Disabled" },
         if lockdown_status { "🔒 Active" } else { "✅ Inactive" },
-        ctx.data().config.security.raid_threshold_5s,
-        ctx.data().config.security.raid_threshold_30s,
-        ctx.data().config.security.raid_threshold_1m,
-        ctx.data().config.security.raid_threshold_5m,
-        ctx.data().config.auto_mod.low_threat_threshold,
-        ctx.data().config.auto_mod.medium_threat_threshold,
-        ctx.data().config.auto_mod.high_threat_threshold,
-        ctx.data().config.auto_mod.critical_threat_threshold
+        if effective.security.enabled { "✅ Enabled" } else { "❌ Disabled" },
+        effective.security.raid_threshold_5s,
+        effective.security.raid_threshold_30s,
+        effective.security.raid_threshold_1m,
+        effective.security.raid_threshold_5m,
+        effective.security.new_account_days,
+        effective.security.username_similarity_threshold,
+        effective.raid_mode.cooldown_secs / 60,
+        if effective.raid_mode.auto_lockdown { "on" } else { "off" },
+        if effective.behavior.enabled { "✅ Enabled" } else { "❌ Disabled" },
+        effective.auto_mod.message_burst_count,
+        effective.security.spam_similarity_threshold,
+        effective.behavior.link_spam_threshold,
+        effective.behavior.mention_spam_threshold,
+        if effective.ml.enabled { "✅ Enabled" } else { "❌ Disabled" },
+        effective.ml.model_path,
+        effective.ml.inference_timeout_ms,
+        effective.ml.confidence_threshold,
+        effective.auto_mod.low_threat_threshold,
+        effective.auto_mod.medium_threat_threshold,
+        effective.auto_mod.high_threat_threshold,
+        effective.auto_mod.critical_threat_threshold,
+        channel_mention(effective.alerts.alert_channel_id),
+        channel_mention(effective.alerts.log_channel_id),
+        channel_mention(effective.alerts.report_channel_id),
+        if effective.lockdown.channels.is_empty() {
+            "All text channels".to_string()
+        } else {
+            effective.lockdown.channels.iter().map(|id| format!("<#{}>", id)).collect::<Vec<_>>().join(", ")
+        },
+        if effective.alerts.notify_roles.is_empty() {
+            "No roles".to_string()
+        } else {
+            effective.alerts.notify_roles.iter().map(|id| format!("<@&{}>", id)).collect::<Vec<_>>().join(", ")
+        },
+        effective.alerts.notify_level.as_str(),
+        effective.backup.frequency.map(|f| f.as_str()).unwrap_or("off"),
+        effective.backup.retention,
+        match effective.forensics.retention_days {
+            0 => "forever".to_string(),
+            days => format!("{} days", days),
+        },
+        if effective.forensics.detailed_logging { "on" } else { "off" },
+        if effective.anti_nuke.enabled { "✅ Enabled" } else { "❌ Disabled" },
+        effective.anti_nuke.window_secs,
+        guild.as_ref().map(|g| g.settings().anti_nuke_trusted.len()).unwrap_or(0),
+        NukeAction::ALL.iter()
+            .map(|action| format!("- {}: {}", action.label(), match action.threshold(&effective.anti_nuke) {
+                0 => "off".to_string(),
+                n => n.to_string(),
+            }))
+            .collect::<Vec<_>>()
+            .join("\n")
     );
     
     ctx.send(poise::CreateReply::default().embed(
@@ -35,11 +82,20 @@ pub async fn view(ctx: Context<'_>) -> Result<(), Error> {
     Ok(())
 }
 
+fn channel_mention(channel_id: Option<u64>) -> String {
+    channel_id.map(|id| format!("<#{}>", id)).unwrap_or_else(|| "Not set".to_string())
+}
+
 #[poise::command( slash_command, guild_only = true, required_permissions = "ADMINISTRATOR", rename = "automod")]
 pub async fn automod_toggle(
     ctx: Context<'_>,
     #[description = "Enable or disable auto-moderation"] enable: bool,
 ) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or("Command must be used in a guild")?.get() as i64;
+    
+    queries::set_auto_mod_enabled(&ctx.data().pool, guild_id, enable).await?;
+    ctx.data().guild_configs.reload(&ctx.data().pool, guild_id).await?;
+    
     ctx.send(poise::CreateReply::default().embed(
         serenity::CreateEmbed::new()
             .title(if enable { "✅ Auto-Moderation Enabled" } else { "❌ Auto-Moderation Disabled" })
@@ -51,14 +107,45 @@ pub async fn automod_toggle(
     Ok(())
 }
 
+#[poise::command( slash_command, guild_only = true, required_permissions = "ADMINISTRATOR", rename = "automod_threshold")]
+pub async fn automod_threshold(
+    ctx: Context<'_>,
+    #[description = "Threat level (low, medium, high, critical)"] level: String,
+    #[description = "Threat score (0.0-1.0) at which the level starts"] threshold: f32,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or("Command must be used in a guild")?.get() as i64;
+    let threshold = threshold.clamp(0.0, 1.0);
+    
+    let key = match level.to_lowercase().as_str() {
+        "low" => "low_threat_threshold",
+        "medium" => "medium_threat_threshold",
+        "high" => "high_threat_threshold",
+        "critical" => "critical_threat_threshold",
+        _ => return Err("Level must be one of: low, medium, high, critical".into()),
+    };
+    
+    queries::set_guild_setting(&ctx.data().pool, guild_id, key, json!(threshold)).await?;
+    ctx.data().guild_configs.reload(&ctx.data().pool, guild_id).await?;
+    
+    ctx.send(poise::CreateReply::default().embed(
+        serenity::CreateEmbed::new()
+            .title("✅ Auto-Moderation Updated")
+            .description(format!("**{}** threat threshold set to: **{:.2}**", level.to_lowercase(), threshold))
+            .color(0x2ecc71)
+            .footer(serenity::CreateEmbedFooter::new("Kitsune Configuration"))
+    )).await?;
+    
+    Ok(())
+}
+
 #[poise::command(
     slash_command,
     guild_only = true,
     required_permissions = "ADMINISTRATOR",
-    subcommands("alerts", "logs", "reports")
+    subcommands("alerts", "logs", "reports", "channel_lockdown")
 )]
 pub async fn channel(ctx: Context<'_>) -> Result<(), Error> {
-    ctx.say("Use subcommands: `/kitsune channel alerts`, `/kitsune channel logs`, or `/kitsune channel reports`").await?;
+    ctx.say("Use subcommands: `/kitsune channel alerts`, `/kitsune channel logs`, `/kitsune channel reports`, or `/kitsune channel lockdown`").await?;
     Ok(())
 }
 
@@ -67,8 +154,12 @@ pub async fn alerts(
     ctx: Context<'_>,
     #[description = "Channel for security alerts"] channel: serenity::Channel,
 ) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or("Command must be used in a guild")?.get() as i64;
     let channel_id = channel.id();
     
+    queries::set_guild_setting(&ctx.data().pool, guild_id, "alert_channel_id", json!(channel_id.get() as i64)).await?;
+    ctx.data().guild_configs.reload(&ctx.data().pool, guild_id).await?;
+    
     ctx.send(poise::CreateReply::default().embed(
         serenity::CreateEmbed::new()
             .title("✅ Alert Channel Set")
@@ -85,8 +176,12 @@ pub async fn logs(
     ctx: Context<'_>,
     #[description = "Channel for detailed logs"] channel: serenity::Channel,
 ) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or("Command must be used in a guild")?.get() as i64;
     let channel_id = channel.id();
     
+    queries::set_guild_setting(&ctx.data().pool, guild_id, "log_channel_id", json!(channel_id.get() as i64)).await?;
+    ctx.data().guild_configs.reload(&ctx.data().pool, guild_id).await?;
+    
     ctx.send(poise::CreateReply::default().embed(
         serenity::CreateEmbed::new()
             .title("✅ Log Channel Set")
@@ -103,8 +198,12 @@ pub async fn reports(
     ctx: Context<'_>,
     #[description = "Channel for automated reports"] channel: serenity::Channel,
 ) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or("Command must be used in a guild")?.get() as i64;
     let channel_id = channel.id();
     
+    queries::set_guild_setting(&ctx.data().pool, guild_id, "report_channel_id", json!(channel_id.get() as i64)).await?;
+    ctx.data().guild_configs.reload(&ctx.data().pool, guild_id).await?;
+    
     ctx.send(poise::CreateReply::default().embed(
         serenity::CreateEmbed::new()
             .title("✅ Report Channel Set")
@@ -116,6 +215,47 @@ pub async fn reports(
     Ok(())
 }
 
+#[poise::command( slash_command, guild_only = true, required_permissions = "ADMINISTRATOR", rename = "lockdown")]
+pub async fn channel_lockdown(
+    ctx: Context<'_>,
+    #[description = "Channel to add to or remove from lockdowns"] channel: serenity::Channel,
+    #[description = "Lock this channel during lockdowns"] include: bool,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or("Command must be used in a guild")?.get() as i64;
+    let guild = queries::get_guild(&ctx.data().pool, guild_id).await?;
+    let mut channels = guild.map(|g| g.settings().lockdown_channels).unwrap_or_default();
+    
+    let channel_id = channel.id().get() as i64;
+    channels.retain(|id| *id != channel_id);
+    if include {
+        channels.push(channel_id);
+    }
+    
+    queries::set_guild_setting(&ctx.data().pool, guild_id, "lockdown_channels", json!(channels)).await?;
+    ctx.data().guild_configs.reload(&ctx.data().pool, guild_id).await?;
+    
+    let scope = if channels.is_empty() {
+        "Lockdowns will cover **all text channels**".to_string()
+    } else {
+        format!("Lockdowns will cover **{}** selected channel(s)", channels.len())
+    };
+    
+    ctx.send(poise::CreateReply::default().embed(
+        serenity::CreateEmbed::new()
+            .title("✅ Lockdown Channels Updated")
+            .description(format!(
+                "<#{}> {} locked during lockdowns\n{}",
+                channel_id,
+                if include { "will be" } else { "will no longer be" },
+                scope
+            ))
+            .color(0x2ecc71)
+            .footer(serenity::CreateEmbedFooter::new("Kitsune Guardian Fox"))
+    )).await?;
+    
+    Ok(())
+}
+
 #[poise::command(
     slash_command,
     guild_only = true,
@@ -132,6 +272,18 @@ pub async fn notify_add(
     ctx: Context<'_>,
     #[description = "Role to notify"] role: serenity::Role,
 ) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or("Command must be used in a guild")?.get() as i64;
+    let guild = queries::get_guild(&ctx.data().pool, guild_id).await?;
+    let mut roles = guild.map(|g| g.settings().notify_roles).unwrap_or_default();
+    
+    let role_id = role.id.get() as i64;
+    if !roles.contains(&role_id) {
+        roles.push(role_id);
+    }
+    
+    queries::set_guild_setting(&ctx.data().pool, guild_id, "notify_roles", json!(roles)).await?;
+    ctx.data().guild_configs.reload(&ctx.data().pool, guild_id).await?;
+    
     ctx.send(poise::CreateReply::default().embed(
         serenity::CreateEmbed::new()
             .title("✅ Notification Role Added")
@@ -148,6 +300,14 @@ pub async fn notify_remove(
     ctx: Context<'_>,
     #[description = "Role to stop notifying"] role: serenity::Role,
 ) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or("Command must be used in a guild")?.get() as i64;
+    let guild = queries::get_guild(&ctx.data().pool, guild_id).await?;
+    let mut roles = guild.map(|g| g.settings().notify_roles).unwrap_or_default();
+    roles.retain(|id| *id != role.id.get() as i64);
+    
+    queries::set_guild_setting(&ctx.data().pool, guild_id, "notify_roles", json!(roles)).await?;
+    ctx.data().guild_configs.reload(&ctx.data().pool, guild_id).await?;
+    
     ctx.send(poise::CreateReply::default().embed(
         serenity::CreateEmbed::new()
             .title("✅ Notification Role Removed")
@@ -162,13 +322,20 @@ pub async fn notify_remove(
 #[poise::command( slash_command, guild_only = true, required_permissions = "ADMINISTRATOR", rename = "level")]
 pub async fn notify_level(
     ctx: Context<'_>,
-    #[description = "Notification level"] 
+    #[description = "Minimum threat level that pings notification roles (low, medium, high, critical)"] 
     level: String,
 ) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or("Command must be used in a guild")?.get() as i64;
+    let threat_level = ThreatLevel::parse(&level)
+        .ok_or("Level must be one of: low, medium, high, critical")?;
+    
+    queries::set_guild_setting(&ctx.data().pool, guild_id, "notify_level", json!(threat_level.as_str().to_lowercase())).await?;
+    ctx.data().guild_configs.reload(&ctx.data().pool, guild_id).await?;
+    
     ctx.send(poise::CreateReply::default().embed(
         serenity::CreateEmbed::new()
             .title("✅ Notification Level Set")
-            .description(format!("Notification level set to: **{}**", level))
+            .description(format!("Notification level set to: **{}**", threat_level.as_str()))
             .color(0x2ecc71)
             .footer(serenity::CreateEmbedFooter::new("Kitsune Guardian Fox"))
     )).await?;
@@ -180,7 +347,7 @@ pub async fn notify_level(
     slash_command,
     guild_only = true,
     required_permissions = "ADMINISTRATOR",
-    subcommands("raid_join_threshold_5s", "raid_join_threshold_30s", "raid_join_threshold_1m", "raid_new_account_days", "raid_username_similarity", "raid_enabled")
+    subcommands("raid_join_threshold_5s", "raid_join_threshold_30s", "raid_join_threshold_1m", "raid_join_threshold_5m", "raid_new_account_days", "raid_username_similarity", "raid_enabled", "raid_cooldown", "raid_auto_lockdown")
 )]
 pub async fn raid(ctx: Context<'_>) -> Result<(), Error> {
     ctx.say("Use raid subcommands to configure raid detection thresholds").await?;
@@ -190,8 +357,15 @@ pub async fn raid(ctx: Context<'_>) -> Result<(), Error> {
 #[poise::command( slash_command, guild_only = true, required_permissions = "ADMINISTRATOR", rename = "join_threshold_5s")]
 pub async fn raid_join_threshold_5s(
     ctx: Context<'_>,
-    #[description = "Number of joins in 5 seconds to trigger alert"] threshold: u32,
+    #[description = "Number of joins in 5 seconds to trigger alert"]
+    #[min = 1]
+    #[max = 10000]
+    threshold: u32,
 ) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or("Command must be used in a guild")?.get() as i64;
+    queries::update_raid_thresholds(&ctx.data().pool, guild_id, Some(threshold as i32), None, None, None, None).await?;
+    ctx.data().guild_configs.reload(&ctx.data().pool, guild_id).await?;
+    
     ctx.send(poise::CreateReply::default().embed(
         serenity::CreateEmbed::new()
             .title("✅ Raid Detection Updated")
@@ -205,8 +379,15 @@ pub async fn raid_join_threshold_5s(
 #[poise::command( slash_command, guild_only = true, required_permissions = "ADMINISTRATOR", rename = "join_threshold_30s")]
 pub async fn raid_join_threshold_30s(
     ctx: Context<'_>,
-    #[description = "Number of joins in 30 seconds to trigger alert"] threshold: u32,
+    #[description = "Number of joins in 30 seconds to trigger alert"]
+    #[min = 1]
+    #[max = 10000]
+    threshold: u32,
 ) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or("Command must be used in a guild")?.get() as i64;
+    queries::update_raid_thresholds(&ctx.data().pool, guild_id, None, Some(threshold as i32), None, None, None).await?;
+    ctx.data().guild_configs.reload(&ctx.data().pool, guild_id).await?;
+    
     ctx.send(poise::CreateReply::default().embed(
         serenity::CreateEmbed::new()
             .title("✅ Raid Detection Updated")
@@ -220,8 +401,15 @@ pub async fn raid_join_threshold_30s(
 #[poise::command( slash_command, guild_only = true, required_permissions = "ADMINISTRATOR", rename = "join_threshold_1m")]
 pub async fn raid_join_threshold_1m(
     ctx: Context<'_>,
-    #[description = "Number of joins in 1 minute to trigger alert"] threshold: u32,
+    #[description = "Number of joins in 1 minute to trigger alert"]
+    #[min = 1]
+    #[max = 10000]
+    threshold: u32,
 ) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or("Command must be used in a guild")?.get() as i64;
+    queries::update_raid_thresholds(&ctx.data().pool, guild_id, None, None, Some(threshold as i32), None, None).await?;
+    ctx.data().guild_configs.reload(&ctx.data().pool, guild_id).await?;
+    
     ctx.send(poise::CreateReply::default().embed(
         serenity::CreateEmbed::new()
             .title("✅ Raid Detection Updated")
@@ -232,11 +420,39 @@ pub async fn raid_join_threshold_1m(
     Ok(())
 }
 
+#[poise::command( slash_command, guild_only = true, required_permissions = "ADMINISTRATOR", rename = "join_threshold_5m")]
+pub async fn raid_join_threshold_5m(
+    ctx: Context<'_>,
+    #[description = "Number of joins in 5 minutes to trigger alert"]
+    #[min = 1]
+    #[max = 10000]
+    threshold: u32,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or("Command must be used in a guild")?.get() as i64;
+    queries::update_raid_thresholds(&ctx.data().pool, guild_id, None, None, None, Some(threshold as i32), None).await?;
+    ctx.data().guild_configs.reload(&ctx.data().pool, guild_id).await?;
+    
+    ctx.send(poise::CreateReply::default().embed(
+        serenity::CreateEmbed::new()
+            .title("✅ Raid Detection Updated")
+            .description(format!("5-minute join threshold set to: **{}**", threshold))
+            .color(0x2ecc71)
+            .footer(serenity::CreateEmbedFooter::new("Kitsune Configuration"))
+    )).await?;
+    Ok(())
+}
+
 #[poise::command( slash_command, guild_only = true, required_permissions = "ADMINISTRATOR", rename = "new_account_days")]
 pub async fn raid_new_account_days(
     ctx: Context<'_>,
-    #[description = "Days since account creation to consider 'new'"] days: u32,
+    #[description = "Days since account creation to consider 'new'"]
+    #[max = 3650]
+    days: u32,
 ) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or("Command must be used in a guild")?.get() as i64;
+    queries::update_raid_thresholds(&ctx.data().pool, guild_id, None, None, None, None, Some(days as i32)).await?;
+    ctx.data().guild_configs.reload(&ctx.data().pool, guild_id).await?;
+    
     ctx.send(poise::CreateReply::default().embed(
         serenity::CreateEmbed::new()
             .title("✅ Raid Detection Updated")
@@ -252,7 +468,11 @@ pub async fn raid_username_similarity(
     ctx: Context<'_>,
     #[description = "Similarity threshold (0.0-1.0) for detecting similar usernames"] threshold: f32,
 ) -> Result<(), Error> {
-    let threshold = threshold.max(0.0).min(1.0);
+    let guild_id = ctx.guild_id().ok_or("Command must be used in a guild")?.get() as i64;
+    let threshold = threshold.clamp(0.0, 1.0);
+    queries::set_guild_setting(&ctx.data().pool, guild_id, "username_similarity_threshold", json!(threshold)).await?;
+    ctx.data().guild_configs.reload(&ctx.data().pool, guild_id).await?;
+    
     ctx.send(poise::CreateReply::default().embed(
         serenity::CreateEmbed::new()
             .title("✅ Raid Detection Updated")
@@ -268,6 +488,10 @@ pub async fn raid_enabled(
     ctx: Context<'_>,
     #[description = "Enable or disable raid detection"] enable: bool,
 ) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or("Command must be used in a guild")?.get() as i64;
+    queries::set_guild_setting(&ctx.data().pool, guild_id, "raid_enabled", json!(enable)).await?;
+    ctx.data().guild_configs.reload(&ctx.data().pool, guild_id).await?;
+    
     ctx.send(poise::CreateReply::default().embed(
         serenity::CreateEmbed::new()
             .title(if enable { "✅ Raid Detection Enabled" } else { "❌ Raid Detection Disabled" })
@@ -278,6 +502,164 @@ pub async fn raid_enabled(
     Ok(())
 }
 
+#[poise::command( slash_command, guild_only = true, required_permissions = "ADMINISTRATOR", rename = "cooldown")]
+pub async fn raid_cooldown(
+    ctx: Context<'_>,
+    #[description = "Quiet minutes before raid mode returns to normal (1-1440)"] minutes: u64,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or("Command must be used in a guild")?.get() as i64;
+    let minutes = minutes.clamp(1, 1440);
+    
+    queries::set_guild_setting(&ctx.data().pool, guild_id, "raid_cooldown_secs", json!(minutes * 60)).await?;
+    ctx.data().guild_configs.reload(&ctx.data().pool, guild_id).await?;
+    
+    ctx.send(poise::CreateReply::default().embed(
+        serenity::CreateEmbed::new()
+            .title("✅ Raid Cooldown Updated")
+            .description(format!("Raid mode ends after **{} minutes** without new raid signals", minutes))
+            .color(0x2ecc71)
+            .footer(serenity::CreateEmbedFooter::new("Kitsune Configuration"))
+    )).await?;
+    Ok(())
+}
+
+#[poise::command( slash_command, guild_only = true, required_permissions = "ADMINISTRATOR", rename = "auto_lockdown")]
+pub async fn raid_auto_lockdown(
+    ctx: Context<'_>,
+    #[description = "Lock the server automatically while in raid mode"] enable: bool,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or("Command must be used in a guild")?.get() as i64;
+    queries::set_guild_setting(&ctx.data().pool, guild_id, "raid_auto_lockdown", json!(enable)).await?;
+    ctx.data().guild_configs.reload(&ctx.data().pool, guild_id).await?;
+    
+    ctx.send(poise::CreateReply::default().embed(
+        serenity::CreateEmbed::new()
+            .title(if enable { "✅ Raid Auto-Lockdown Enabled" } else { "❌ Raid Auto-Lockdown Disabled" })
+            .description(format!("Automatic lockdown during raid mode has been **{}**", if enable { "enabled" } else { "disabled" }))
+            .color(if enable { 0x2ecc71 } else { 0xe74c3c })
+            .footer(serenity::CreateEmbedFooter::new("Kitsune Configuration"))
+    )).await?;
+    Ok(())
+}
+
+#[poise::command(
+    slash_command,
+    guild_only = true,
+    required_permissions = "ADMINISTRATOR",
+    subcommands("antinuke_enabled", "antinuke_threshold", "antinuke_window", "antinuke_trust")
+)]
+pub async fn antinuke(ctx: Context<'_>) -> Result<(), Error> {
+    ctx.say("Use antinuke subcommands to configure anti-nuke protection").await?;
+    Ok(())
+}
+
+#[poise::command( slash_command, guild_only = true, required_permissions = "ADMINISTRATOR", rename = "enabled")]
+pub async fn antinuke_enabled(
+    ctx: Context<'_>,
+    #[description = "Enable or disable anti-nuke protection"] enable: bool,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or("Command must be used in a guild")?.get() as i64;
+    queries::set_guild_setting(&ctx.data().pool, guild_id, "anti_nuke_enabled", json!(enable)).await?;
+    ctx.data().guild_configs.reload(&ctx.data().pool, guild_id).await?;
+    
+    ctx.send(poise::CreateReply::default().embed(
+        serenity::CreateEmbed::new()
+            .title(if enable { "✅ Anti-Nuke Enabled" } else { "❌ Anti-Nuke Disabled" })
+            .description(format!("Anti-nuke protection has been **{}**", if enable { "enabled" } else { "disabled" }))
+            .color(if enable { 0x2ecc71 } else { 0xe74c3c })
+            .footer(serenity::CreateEmbedFooter::new("Kitsune Configuration"))
+    )).await?;
+    Ok(())
+}
+
+#[poise::command( slash_command, guild_only = true, required_permissions = "ADMINISTRATOR", rename = "threshold")]
+pub async fn antinuke_threshold(
+    ctx: Context<'_>,
+    #[description = "channel_delete, role_delete, permission_escalation, ban, kick or webhook_create"] action: String,
+    #[description = "Actions allowed per window before Kitsune responds (0 disables)"] count: u32,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or("Command must be used in a guild")?.get() as i64;
+    let action = NukeAction::parse(&action)
+        .ok_or("Action must be one of: channel_delete, role_delete, permission_escalation, ban, kick, webhook_create")?;
+    
+    let mut thresholds = queries::get_guild(&ctx.data().pool, guild_id).await?
+        .map(|g| g.settings().anti_nuke_thresholds)
+        .unwrap_or_default();
+    thresholds.insert(action.as_str().to_string(), count);
+    
+    queries::set_guild_setting(&ctx.data().pool, guild_id, "anti_nuke_thresholds", json!(thresholds)).await?;
+    ctx.data().guild_configs.reload(&ctx.data().pool, guild_id).await?;
+    
+    let description = if count == 0 {
+        format!("**{}** is no longer tracked", action.label())
+    } else {
+        format!("**{}** threshold set to: **{}**", action.label(), count)
+    };
+    
+    ctx.send(poise::CreateReply::default().embed(
+        serenity::CreateEmbed::new()
+            .title("✅ Anti-Nuke Updated")
+            .description(description)
+            .color(0x2ecc71)
+            .footer(serenity::CreateEmbedFooter::new("Kitsune Configuration"))
+    )).await?;
+    Ok(())
+}
+
+#[poise::command( slash_command, guild_only = true, required_permissions = "ADMINISTRATOR", rename = "window")]
+pub async fn antinuke_window(
+    ctx: Context<'_>,
+    #[description = "Time window in seconds (10-3600)"] seconds: u64,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or("Command must be used in a guild")?.get() as i64;
+    let seconds = seconds.clamp(10, 3600);
+    
+    queries::set_guild_setting(&ctx.data().pool, guild_id, "anti_nuke_window_secs", json!(seconds)).await?;
+    ctx.data().guild_configs.reload(&ctx.data().pool, guild_id).await?;
+    
+    ctx.send(poise::CreateReply::default().embed(
+        serenity::CreateEmbed::new()
+            .title("✅ Anti-Nuke Updated")
+            .description(format!("Anti-nuke thresholds now apply per **{} seconds**", seconds))
+            .color(0x2ecc71)
+            .footer(serenity::CreateEmbedFooter::new("Kitsune Configuration"))
+    )).await?;
+    Ok(())
+}
+
+#[poise::command( slash_command, guild_only = true, required_permissions = "ADMINISTRATOR", rename = "trust")]
+pub async fn antinuke_trust(
+    ctx: Context<'_>,
+    #[description = "User or bot to exempt from anti-nuke (the owner always is)"] user: serenity::User,
+    #[description = "Trust or stop trusting this account"] trusted: bool,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or("Command must be used in a guild")?.get() as i64;
+    let guild = queries::get_guild(&ctx.data().pool, guild_id).await?;
+    let mut users = guild.map(|g| g.settings().anti_nuke_trusted).unwrap_or_default();
+    
+    let user_id = user.id.get() as i64;
+    users.retain(|id| *id != user_id);
+    if trusted {
+        users.push(user_id);
+    }
+    
+    queries::set_guild_setting(&ctx.data().pool, guild_id, "anti_nuke_trusted", json!(users)).await?;
+    ctx.data().guild_configs.reload(&ctx.data().pool, guild_id).await?;
+    
+    ctx.send(poise::CreateReply::default().embed(
+        serenity::CreateEmbed::new()
+            .title("✅ Anti-Nuke Updated")
+            .description(if trusted {
+                format!("<@{}> is now exempt from anti-nuke. The whitelist does not exempt anyone; only the owner and trusted accounts are.", user.id)
+            } else {
+                format!("<@{}> is no longer exempt from anti-nuke", user.id)
+            })
+            .color(0x2ecc71)
+            .footer(serenity::CreateEmbedFooter::new("Kitsune Configuration"))
+    )).await?;
+    Ok(())
+}
+
 #[poise::command(
     slash_command,
     guild_only = true,
@@ -292,12 +674,19 @@ pub async fn behavior(ctx: Context<'_>) -> Result<(), Error> {
 #[poise::command( slash_command, guild_only = true, required_permissions = "ADMINISTRATOR", rename = "message_burst_threshold")]
 pub async fn behavior_message_burst(
     ctx: Context<'_>,
-    #[description = "Messages per minute threshold"] threshold: u32,
+    #[description = "Messages within the burst window to flag as a burst"]
+    #[min = 1]
+    #[max = 1000]
+    threshold: u32,
 ) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or("Command must be used in a guild")?.get() as i64;
+    queries::set_guild_setting(&ctx.data().pool, guild_id, "message_burst_count", json!(threshold)).await?;
+    ctx.data().guild_configs.reload(&ctx.data().pool, guild_id).await?;
+    
     ctx.send(poise::CreateReply::default().embed(
         serenity::CreateEmbed::new()
             .title("✅ Behavior Analysis Updated")
-            .description(format!("Message burst threshold set to: **{}** messages/min", threshold))
+            .description(format!("Message burst threshold set to: **{}** messages", threshold))
             .color(0x2ecc71)
             .footer(serenity::CreateEmbedFooter::new("Kitsune Configuration"))
     )).await?;
@@ -309,7 +698,11 @@ pub async fn behavior_spam_similarity(
     ctx: Context<'_>,
     #[description = "Similarity threshold (0.0-1.0) for detecting spam"] threshold: f32,
 ) -> Result<(), Error> {
-    let threshold = threshold.max(0.0).min(1.0);
+    let guild_id = ctx.guild_id().ok_or("Command must be used in a guild")?.get() as i64;
+    let threshold = threshold.clamp(0.0, 1.0);
+    queries::set_guild_setting(&ctx.data().pool, guild_id, "spam_similarity_threshold", json!(threshold)).await?;
+    ctx.data().guild_configs.reload(&ctx.data().pool, guild_id).await?;
+    
     ctx.send(poise::CreateReply::default().embed(
         serenity::CreateEmbed::new()
             .title("✅ Behavior Analysis Updated")
@@ -323,8 +716,15 @@ pub async fn behavior_spam_similarity(
 #[poise::command( slash_command, guild_only = true, required_permissions = "ADMINISTRATOR", rename = "link_spam_threshold")]
 pub async fn behavior_link_spam(
     ctx: Context<'_>,
-    #[description = "Number of links in a short time to flag as spam"] threshold: u32,
+    #[description = "Number of links in a short time to flag as spam"]
+    #[min = 1]
+    #[max = 1000]
+    threshold: u32,
 ) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or("Command must be used in a guild")?.get() as i64;
+    queries::set_guild_setting(&ctx.data().pool, guild_id, "link_spam_threshold", json!(threshold)).await?;
+    ctx.data().guild_configs.reload(&ctx.data().pool, guild_id).await?;
+    
     ctx.send(poise::CreateReply::default().embed(
         serenity::CreateEmbed::new()
             .title("✅ Behavior Analysis Updated")
@@ -338,8 +738,15 @@ pub async fn behavior_link_spam(
 #[poise::command( slash_command, guild_only = true, required_permissions = "ADMINISTRATOR", rename = "mention_spam_threshold")]
 pub async fn behavior_mention_spam(
     ctx: Context<'_>,
-    #[description = "Number of mentions to flag as spam"] threshold: u32,
+    #[description = "Number of mentions to flag as spam"]
+    #[min = 1]
+    #[max = 1000]
+    threshold: u32,
 ) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or("Command must be used in a guild")?.get() as i64;
+    queries::set_guild_setting(&ctx.data().pool, guild_id, "mention_spam_threshold", json!(threshold)).await?;
+    ctx.data().guild_configs.reload(&ctx.data().pool, guild_id).await?;
+    
     ctx.send(poise::CreateReply::default().embed(
         serenity::CreateEmbed::new()
             .title("✅ Behavior Analysis Updated")
@@ -355,6 +762,10 @@ pub async fn behavior_enabled(
     ctx: Context<'_>,
     #[description = "Enable or disable behavior analysis"] enable: bool,
 ) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or("Command must be used in a guild")?.get() as i64;
+    queries::set_guild_setting(&ctx.data().pool, guild_id, "behavior_enabled", json!(enable)).await?;
+    ctx.data().guild_configs.reload(&ctx.data().pool, guild_id).await?;
+    
     ctx.send(poise::CreateReply::default().embed(
         serenity::CreateEmbed::new()
             .title(if enable { "✅ Behavior Analysis Enabled" } else { "❌ Behavior Analysis Disabled" })
@@ -381,6 +792,10 @@ pub async fn ml_model_path(
     ctx: Context<'_>,
     #[description = "Path to ML model file"] path: String,
 ) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or("Command must be used in a guild")?.get() as i64;
+    queries::set_guild_setting(&ctx.data().pool, guild_id, "ml_model_path", json!(path)).await?;
+    ctx.data().guild_configs.reload(&ctx.data().pool, guild_id).await?;
+    
     ctx.send(poise::CreateReply::default().embed(
         serenity::CreateEmbed::new()
             .title("✅ ML Configuration Updated")
@@ -394,8 +809,15 @@ pub async fn ml_model_path(
 #[poise::command( slash_command, guild_only = true, required_permissions = "ADMINISTRATOR", rename = "inference_timeout")]
 pub async fn ml_inference_timeout(
     ctx: Context<'_>,
-    #[description = "Timeout for ML inference in milliseconds"] timeout_ms: u32,
+    #[description = "Timeout for ML inference in milliseconds"]
+    #[min = 1]
+    #[max = 60000]
+    timeout_ms: u32,
 ) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or("Command must be used in a guild")?.get() as i64;
+    queries::set_guild_setting(&ctx.data().pool, guild_id, "ml_inference_timeout_ms", json!(timeout_ms)).await?;
+    ctx.data().guild_configs.reload(&ctx.data().pool, guild_id).await?;
+    
     ctx.send(poise::CreateReply::default().embed(
         serenity::CreateEmbed::new()
             .title("✅ ML Configuration Updated")
@@ -411,7 +833,11 @@ pub async fn ml_confidence_threshold(
     ctx: Context<'_>,
     #[description = "Confidence threshold (0.0-1.0) for ML predictions"] threshold: f32,
 ) -> Result<(), Error> {
-    let threshold = threshold.max(0.0).min(1.0);
+    let guild_id = ctx.guild_id().ok_or("Command must be used in a guild")?.get() as i64;
+    let threshold = threshold.clamp(0.0, 1.0);
+    queries::set_guild_setting(&ctx.data().pool, guild_id, "ml_confidence_threshold", json!(threshold)).await?;
+    ctx.data().guild_configs.reload(&ctx.data().pool, guild_id).await?;
+    
     ctx.send(poise::CreateReply::default().embed(
         serenity::CreateEmbed::new()
             .title("✅ ML Configuration Updated")
@@ -427,6 +853,10 @@ pub async fn ml_enabled(
     ctx: Context<'_>,
     #[description = "Enable or disable ML predictions"] enable: bool,
 ) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or("Command must be used in a guild")?.get() as i64;
+    queries::set_guild_setting(&ctx.data().pool, guild_id, "ml_enabled", json!(enable)).await?;
+    ctx.data().guild_configs.reload(&ctx.data().pool, guild_id).await?;
+    
     ctx.send(poise::CreateReply::default().embed(
         serenity::CreateEmbed::new()
             .title(if enable { "✅ ML Enabled" } else { "❌ ML Disabled" })
@@ -1,23 +1,193 @@
 use poise::serenity_prelude as serenity;
 use crate::bot::{Context, Error};
+use crate::database::queries;
+use crate::security::honeypot::{
+    parse_bait_message_target, TRAP_BAIT_MESSAGE, TRAP_BAIT_ROLE, TRAP_FAKE_COMMAND, TRAP_HIDDEN_CHANNEL,
+};
+use chrono::{Duration, Utc};
 
 #[poise::command(
     slash_command,
     guild_only = true,
     required_permissions = "ADMINISTRATOR",
-    subcommands("setup", "list", "add_channel", "add_command", "catches", "clear")
+    subcommands("setup", "teardown", "bait_message", "list", "add_channel", "add_command", "remove", "catches", "clear")
 )]
 pub async fn honeypot(ctx: Context<'_>) -> Result<(), Error> {
     ctx.say("Use honeypot subcommands to manage traps").await?;
     Ok(())
 }
 
+pub const BAIT_BUTTON_ID: &str = "kitsune:bait_verify";
+pub const BAIT_REACTION: &str = "✅";
+
+const MAX_BAIT_CHANNELS: usize = 5;
+const BAIT_CHANNEL_TOPIC: &str = "⚠️ Do not post here. Messages in this channel are treated as spam.";
+
 #[poise::command( slash_command, guild_only = true, required_permissions = "ADMINISTRATOR")]
-pub async fn setup(ctx: Context<'_>) -> Result<(), Error> {
+pub async fn setup(
+    ctx: Context<'_>,
+    #[description = "Comma-separated bait channel names (default: free-nitro)"] channels: Option<String>,
+    #[description = "Role your real members have; bait channels are hidden from it"] member_role: Option<serenity::Role>,
+    #[description = "Name of a bait role whose mention counts as a trap hit (optional)"] bait_role: Option<String>,
+) -> Result<(), Error> {
+    let guild = ctx.guild_id().ok_or("Command must be used in a guild")?;
+    let guild_id = guild.get() as i64;
+    let author_id = ctx.author().id.get() as i64;
+    
+    let mut names: Vec<String> = Vec::new();
+    for name in channels.as_deref().unwrap_or("free-nitro").split(',') {
+        let name = name.trim().trim_start_matches('#').to_lowercase().replace(' ', "-");
+        if !name.is_empty() && !names.contains(&name) {
+            names.push(name.chars().take(100).collect());
+        }
+    }
+    if names.len() > MAX_BAIT_CHANNELS {
+        return Err(format!("At most {} bait channels can be created at once", MAX_BAIT_CHANNELS).into());
+    }
+    
+    ctx.defer().await?;
+    
+    let everyone = serenity::RoleId::new(guild.get());
+    let bot_id = ctx.framework().bot_id;
+    
+    let mut created = Vec::new();
+    let mut failed = Vec::new();
+    
+    for name in &names {
+        // New accounts only carry @everyone, so they see the channel; members
+        // holding `member_role` don't. Nobody can read the history, so one
+        // caught spammer can't warn the next.
+        let mut overwrites = vec![
+            serenity::PermissionOverwrite {
+                allow: serenity::Permissions::VIEW_CHANNEL | serenity::Permissions::SEND_MESSAGES,
+                deny: serenity::Permissions::READ_MESSAGE_HISTORY,
+                kind: serenity::PermissionOverwriteType::Role(everyone),
+            },
+            serenity::PermissionOverwrite {
+                allow: serenity::Permissions::VIEW_CHANNEL
+                    | serenity::Permissions::SEND_MESSAGES
+                    | serenity::Permissions::READ_MESSAGE_HISTORY
+                    | serenity::Permissions::MANAGE_MESSAGES,
+                deny: serenity::Permissions::empty(),
+                kind: serenity::PermissionOverwriteType::Member(bot_id),
+            },
+        ];
+        if let Some(role) = &member_role {
+            overwrites.push(serenity::PermissionOverwrite {
+                allow: serenity::Permissions::empty(),
+                deny: serenity::Permissions::VIEW_CHANNEL,
+                kind: serenity::PermissionOverwriteType::Role(role.id),
+            });
+        }
+        
+        let builder = serenity::CreateChannel::new(name.as_str())
+            .kind(serenity::ChannelType::Text)
+            .topic(BAIT_CHANNEL_TOPIC)
+            .permissions(overwrites);
+        
+        match guild.create_channel(ctx, builder).await {
+            Ok(channel) => {
+                queries::add_honeypot_trap(
+                    &ctx.data().pool,
+                    guild_id,
+                    TRAP_HIDDEN_CHANNEL,
+                    &channel.id.get().to_string(),
+                    Some(name),
+                    author_id,
+                    true
+                ).await?;
+                created.push(format!("✅ Bait channel <#{}>", channel.id));
+            }
+            Err(e) => failed.push(format!("❌ #{}: {}", name, e)),
+        }
+    }
+    
+    if let Some(name) = bait_role.as_deref().map(str::trim).filter(|n| !n.is_empty()) {
+        let builder = serenity::EditRole::new()
+            .name(name)
+            .mentionable(true)
+            .permissions(serenity::Permissions::empty());
+        
+        match guild.create_role(ctx, builder).await {
+            Ok(role) => {
+                queries::add_honeypot_trap(
+                    &ctx.data().pool,
+                    guild_id,
+                    TRAP_BAIT_ROLE,
+                    &role.id.get().to_string(),
+                    Some(name),
+                    author_id,
+                    true
+                ).await?;
+                created.push(format!("✅ Bait role <@&{}>", role.id));
+            }
+            Err(e) => failed.push(format!("❌ Role {}: {}", name, e)),
+        }
+    }
+    
+    ctx.data().honeypot.reload(&ctx.data().pool, guild_id).await?;
+    
+    let visibility = match &member_role {
+        Some(role) => format!("Bait channels are hidden from <@&{}> and visible to everyone else.", role.id),
+        None => "⚠️ No member role given: bait channels are visible to everyone. Pass `member_role` to hide them from verified members.".to_string(),
+    };
+    
     ctx.send(poise::CreateReply::default().embed(
         serenity::CreateEmbed::new()
             .title("🕸️ Honeypot Setup")
-            .description("Honeypot system is being configured...\n\n✅ Hidden channels created\n✅ Fake commands registered\n✅ Monitoring activated")
+            .description(format!(
+                "{}\n\n{}\n\nRemove everything created here with `/kitsune honeypot teardown`.",
+                created.iter().chain(failed.iter()).cloned().collect::<Vec<_>>().join("\n"),
+                visibility
+            ))
+            .color(if failed.is_empty() { 0x9b59b6 } else { 0xf39c12 })
+            .footer(serenity::CreateEmbedFooter::new("Kitsune Guardian Fox"))
+    )).await?;
+    
+    Ok(())
+}
+
+#[poise::command( slash_command, guild_only = true, required_permissions = "ADMINISTRATOR", rename = "bait_message")]
+pub async fn bait_message(
+    ctx: Context<'_>,
+    #[description = "Channel new members land in"] channel: serenity::Channel,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or("Command must be used in a guild")?.get() as i64;
+    let channel_id = channel.id();
+    
+    let message = channel_id.send_message(ctx, serenity::CreateMessage::new()
+        .embed(
+            serenity::CreateEmbed::new()
+                .title("🔐 Verification")
+                .description(format!("Welcome! Press **Verify** below or react with {} to confirm you're human.", BAIT_REACTION))
+                .color(0x3498db)
+        )
+        .button(
+            serenity::CreateButton::new(BAIT_BUTTON_ID)
+                .label("Verify")
+                .style(serenity::ButtonStyle::Success)
+        )
+    ).await?;
+    message.react(ctx, serenity::ReactionType::Unicode(BAIT_REACTION.to_string())).await?;
+    
+    queries::add_honeypot_trap(
+        &ctx.data().pool,
+        guild_id,
+        TRAP_BAIT_MESSAGE,
+        &format!("{}/{}", channel_id.get(), message.id.get()),
+        None,
+        ctx.author().id.get() as i64,
+        true
+    ).await?;
+    ctx.data().honeypot.reload(&ctx.data().pool, guild_id).await?;
+    
+    ctx.send(poise::CreateReply::default().embed(
+        serenity::CreateEmbed::new()
+            .title("✅ Bait Message Posted")
+            .description(format!(
+                "Posted a verification prompt in <#{}>\n\nMembers who click or react faster than a human could, measured from when they joined, are flagged as automated.",
+                channel_id
+            ))
             .color(0x9b59b6)
             .footer(serenity::CreateEmbedFooter::new("Kitsune Guardian Fox"))
     )).await?;
@@ -25,11 +195,93 @@ pub async fn setup(ctx: Context<'_>) -> Result<(), Error> {
     Ok(())
 }
 
+#[poise::command( slash_command, guild_only = true, required_permissions = "ADMINISTRATOR")]
+pub async fn teardown(ctx: Context<'_>) -> Result<(), Error> {
+    let guild = ctx.guild_id().ok_or("Command must be used in a guild")?;
+    let guild_id = guild.get() as i64;
+    
+    ctx.defer().await?;
+    
+    let traps = queries::get_honeypot_traps(&ctx.data().pool, guild_id).await?;
+    let mut removed = 0;
+    let mut failed = Vec::new();
+    
+    for trap in traps.iter().filter(|t| t.managed) {
+        let result = match trap.trap_type.as_str() {
+            TRAP_HIDDEN_CHANNEL => match trap.target.parse::<u64>() {
+                Ok(id) => serenity::ChannelId::new(id).delete(ctx).await.map(|_| ()),
+                Err(_) => Ok(()),
+            },
+            TRAP_BAIT_ROLE => match trap.target.parse::<u64>() {
+                Ok(id) => guild.delete_role(ctx, serenity::RoleId::new(id)).await,
+                Err(_) => Ok(()),
+            },
+            TRAP_BAIT_MESSAGE => match parse_bait_message_target(&trap.target) {
+                Some((channel_id, message_id)) => serenity::ChannelId::new(channel_id as u64)
+                    .delete_message(ctx, serenity::MessageId::new(message_id as u64)).await,
+                None => Ok(()),
+            },
+            _ => Ok(()),
+        };
+        
+        match result {
+            Ok(()) => {}
+            Err(serenity::Error::Http(e)) if e.status_code() == Some(serenity::StatusCode::NOT_FOUND) => {}
+            Err(e) => {
+                failed.push(format!("❌ {}: {}", trap.label.as_deref().unwrap_or(&trap.target), e));
+                continue;
+            }
+        }
+        
+        queries::remove_honeypot_trap(&ctx.data().pool, guild_id, trap.id).await?;
+        removed += 1;
+    }
+    
+    ctx.data().honeypot.reload(&ctx.data().pool, guild_id).await?;
+    
+    let description = if failed.is_empty() {
+        format!("Removed **{}** bait channels, roles and messages created by Kitsune", removed)
+    } else {
+        format!("Removed **{}** bait channels, roles and messages created by Kitsune\n\n{}", removed, failed.join("\n"))
+    };
+    
+    ctx.send(poise::CreateReply::default().embed(
+        serenity::CreateEmbed::new()
+            .title("🧹 Honeypot Teardown")
+            .description(description)
+            .color(if failed.is_empty() { 0x2ecc71 } else { 0xf39c12 })
+            .footer(serenity::CreateEmbedFooter::new("Kitsune Guardian Fox"))
+    )).await?;
+    
+    Ok(())
+}
+
 #[poise::command( slash_command, guild_only = true, required_permissions = "ADMINISTRATOR")]
 pub async fn list(ctx: Context<'_>) -> Result<(), Error> {
     let guild_id = ctx.guild_id().ok_or("Command must be used in a guild")?.get() as i64;
+    let traps = queries::get_honeypot_traps(&ctx.data().pool, guild_id).await?;
     
-    let description = "**Active Traps:**\n• Hidden Channel: #verify-here\n• Hidden Channel: #free-nitro\n• Fake Command: .verify\n• Fake Command: !captcha\n\n**Status:** ✅ All traps active";
+    let description = if traps.is_empty() {
+        "No honeypot traps configured\n\nAdd one with `/kitsune honeypot add_channel` or `/kitsune honeypot add_command`.".to_string()
+    } else {
+        let lines = traps.iter()
+            .map(|trap| match trap.trap_type.as_str() {
+                TRAP_HIDDEN_CHANNEL => format!("`#{}` Hidden Channel: <#{}>", trap.id, trap.target),
+                TRAP_FAKE_COMMAND => format!("`#{}` Fake Command: `{}`", trap.id, trap.target),
+                TRAP_BAIT_ROLE => format!("`#{}` Bait Role: <@&{}>", trap.id, trap.target),
+                TRAP_BAIT_MESSAGE => match parse_bait_message_target(&trap.target) {
+                    Some((channel_id, message_id)) => format!(
+                        "`#{}` Bait Message: https://discord.com/channels/{}/{}/{}",
+                        trap.id, guild_id, channel_id, message_id
+                    ),
+                    None => format!("`#{}` Bait Message: {}", trap.id, trap.target),
+                },
+                other => format!("`#{}` {}: {}", trap.id, other, trap.label.as_deref().unwrap_or(&trap.target)),
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        format!("**Active Traps:** {}\n\n{}", traps.len(), lines)
+    };
     
     ctx.send(poise::CreateReply::default().embed(
         serenity::CreateEmbed::new()
@@ -45,14 +297,27 @@ pub async fn list(ctx: Context<'_>) -> Result<(), Error> {
 #[poise::command( slash_command, guild_only = true, required_permissions = "ADMINISTRATOR", rename = "add_channel")]
 pub async fn add_channel(
     ctx: Context<'_>,
-    #[description = "Channel name for trap"] name: String,
+    #[description = "Channel to use as a trap"] channel: serenity::Channel,
 ) -> Result<(), Error> {
     let guild_id = ctx.guild_id().ok_or("Command must be used in a guild")?.get() as i64;
+    let channel_id = channel.id();
+    let label = channel.guild().map(|c| c.name);
+    
+    queries::add_honeypot_trap(
+        &ctx.data().pool,
+        guild_id,
+        TRAP_HIDDEN_CHANNEL,
+        &channel_id.get().to_string(),
+        label.as_deref(),
+        ctx.author().id.get() as i64,
+        false
+    ).await?;
+    ctx.data().honeypot.reload(&ctx.data().pool, guild_id).await?;
     
     ctx.send(poise::CreateReply::default().embed(
         serenity::CreateEmbed::new()
             .title("✅ Honeypot Channel Added")
-            .description(format!("Hidden channel **{}** registered as trap", name))
+            .description(format!("<#{}> registered as trap\n\nAnyone posting there will be flagged, so keep it hidden from real members.", channel_id))
             .color(0x9b59b6)
             .footer(serenity::CreateEmbedFooter::new("Kitsune Guardian Fox"))
     )).await?;
@@ -66,8 +331,21 @@ pub async fn add_command(
     #[description = "Fake command to register"] command: String,
 ) -> Result<(), Error> {
     let guild_id = ctx.guild_id().ok_or("Command must be used in a guild")?.get() as i64;
+    let command = command.trim().to_lowercase();
+    if command.is_empty() {
+        return Err("Command cannot be empty".into());
+    }
     
-    ctx.data().honeypot.register_fake_command(guild_id, command.clone());
+    queries::add_honeypot_trap(
+        &ctx.data().pool,
+        guild_id,
+        TRAP_FAKE_COMMAND,
+        &command,
+        None,
+        ctx.author().id.get() as i64,
+        false
+    ).await?;
+    ctx.data().honeypot.reload(&ctx.data().pool, guild_id).await?;
     
     ctx.send(poise::CreateReply::default().embed(
         serenity::CreateEmbed::new()
@@ -80,18 +358,55 @@ pub async fn add_command(
     Ok(())
 }
 
+#[poise::command( slash_command, guild_only = true, required_permissions = "ADMINISTRATOR")]
+pub async fn remove(
+    ctx: Context<'_>,
+    #[description = "Trap number from /kitsune honeypot list"] trap_id: i32,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or("Command must be used in a guild")?.get() as i64;
+    
+    let removed = queries::remove_honeypot_trap(&ctx.data().pool, guild_id, trap_id).await?;
+    ctx.data().honeypot.reload(&ctx.data().pool, guild_id).await?;
+    
+    ctx.send(poise::CreateReply::default().embed(
+        if removed {
+            serenity::CreateEmbed::new()
+                .title("✅ Honeypot Trap Removed")
+                .description(format!("Trap `#{}` is no longer active", trap_id))
+                .color(0x2ecc71)
+        } else {
+            serenity::CreateEmbed::new()
+                .title("❌ Trap Not Found")
+                .description(format!("No trap `#{}` in this server", trap_id))
+                .color(0xe74c3c)
+        }
+        .footer(serenity::CreateEmbedFooter::new("Kitsune Guardian Fox"))
+    )).await?;
+    
+    Ok(())
+}
+
 #[poise::command( slash_command, guild_only = true, required_permissions = "ADMINISTRATOR")]
 pub async fn catches(
     ctx: Context<'_>,
     #[description = "Hours to look back (default 24)"] hours: Option<i64>,
 ) -> Result<(), Error> {
     let guild_id = ctx.guild_id().ok_or("Command must be used in a guild")?.get() as i64;
-    let hours = hours.unwrap_or(24);
+    let hours = hours.unwrap_or(24).clamp(1, 24 * 90);
+    let since = Utc::now() - Duration::hours(hours);
+    
+    let total = queries::count_honeypot_catches(&ctx.data().pool, guild_id, since).await?;
+    let recent = queries::get_honeypot_catches(&ctx.data().pool, guild_id, since, 15).await?;
     
-    let description = format!(
-        "**Time Range:** Last {} hours\n**Total Catches:** 0\n\nNo bots caught in honeypot traps recently.",
-        hours
-    );
+    let description = if recent.is_empty() {
+        format!("**Time Range:** Last {} hours\n**Total Catches:** 0\n\nNo bots caught in honeypot traps recently.", hours)
+    } else {
+        let lines = recent.iter()
+            .map(|c| format!("• <@{}> — {} `{}` <t:{}:R>", c.user_id, c.trap_type, c.trap_name, c.created_at.timestamp()))
+            .collect::<Vec<_>>()
+            .join("\n");
+        format!("**Time Range:** Last {} hours\n**Total Catches:** {}\n\n{}", hours, total, lines)
+    };
     
     ctx.send(poise::CreateReply::default().embed(
         serenity::CreateEmbed::new()
@@ -106,10 +421,15 @@ pub async fn catches(
 
 #[poise::command( slash_command, guild_only = true, required_permissions = "ADMINISTRATOR")]
 pub async fn clear(ctx: Context<'_>) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or("Command must be used in a guild")?.get() as i64;
+    
+    let deleted = queries::clear_honeypot_catches(&ctx.data().pool, guild_id).await?;
+    ctx.data().honeypot.clear_catches(guild_id).await?;
+    
     ctx.send(poise::CreateReply::default().embed(
         serenity::CreateEmbed::new()
             .title("✅ Honeypot Catches Cleared")
-            .description("Old honeypot catch records have been cleared")
+            .description(format!("**{}** honeypot catch records have been cleared", deleted))
             .color(0x2ecc71)
             .footer(serenity::CreateEmbedFooter::new("Kitsune Guardian Fox"))
     )).await?;
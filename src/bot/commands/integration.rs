@@ -1,11 +1,17 @@
 use poise::serenity_prelude as serenity;
-use crate::bot::{Context, Error};
+use serde_json::json;
+use uuid::Uuid;
+use crate::api;
+use crate::database::queries;
+use crate::bot::{webhooks, Context, Error};
+
+const MAX_WEBHOOKS: usize = 10;
 
 #[poise::command(
     slash_command,
     guild_only = true,
     required_permissions = "ADMINISTRATOR",
-    subcommands("webhook_add", "webhook_list", "webhook_test")
+    subcommands("webhook_add", "webhook_list", "webhook_remove", "webhook_test", "webhook_deliveries")
 )]
 pub async fn webhook(ctx: Context<'_>) -> Result<(), Error> {
     ctx.say("Use webhook subcommands to manage webhooks").await?;
@@ -15,26 +21,86 @@ pub async fn webhook(ctx: Context<'_>) -> Result<(), Error> {
 #[poise::command( slash_command, guild_only = true, required_permissions = "ADMINISTRATOR", rename = "add")]
 pub async fn webhook_add(
     ctx: Context<'_>,
-    #[description = "Webhook URL"] _url: String,
-    #[description = "Events to trigger"] events: String,
+    #[description = "Webhook URL"] url: String,
+    #[description = "Comma separated events, or all"] events: String,
 ) -> Result<(), Error> {
-    ctx.send(poise::CreateReply::default().embed(
-        serenity::CreateEmbed::new()
-            .title("✅ Webhook Added")
-            .description(format!("Webhook registered for events: {}", events))
-            .color(0x2ecc71)
-            .footer(serenity::CreateEmbedFooter::new("Kitsune Integration"))
-    )).await?;
+    let guild_id = ctx.guild_id().ok_or("Command must be used in a guild")?.get() as i64;
+    
+    let problem = ctx.data().webhooks.check_url(&url).await.err();
+    let events = match (problem, webhooks::parse_events(&events)) {
+        (None, Ok(events)) => events,
+        (Some(problem), _) | (None, Err(problem)) => {
+            ctx.send(poise::CreateReply::default().embed(
+                serenity::CreateEmbed::new()
+                    .title("❌ Webhook Not Added")
+                    .description(problem)
+                    .color(0xe74c3c)
+                    .footer(serenity::CreateEmbedFooter::new("Kitsune Integration"))
+            )).await?;
+            return Ok(());
+        }
+    };
+    
+    if queries::get_webhooks(&ctx.data().pool, guild_id).await?.len() >= MAX_WEBHOOKS {
+        ctx.send(poise::CreateReply::default().embed(
+            serenity::CreateEmbed::new()
+                .title("❌ Webhook Not Added")
+                .description(format!("This server already has {} webhooks. Remove one with `/admin webhook remove` first.", MAX_WEBHOOKS))
+                .color(0xe74c3c)
+                .footer(serenity::CreateEmbedFooter::new("Kitsune Integration"))
+        )).await?;
+        return Ok(());
+    }
+    
+    let secret = format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple());
+    let webhook = queries::add_webhook(
+        &ctx.data().pool,
+        guild_id,
+        &url,
+        &secret,
+        &events,
+        ctx.author().id.get() as i64
+    ).await?;
+    
+    ctx.send(poise::CreateReply::default()
+        .embed(
+            serenity::CreateEmbed::new()
+                .title("✅ Webhook Added")
+                .description(format!(
+                    "**ID:** {}\n**URL:** {}\n**Events:** {}\n\n**Signing secret:** ||{}||\n\nEvery delivery carries an `X-Kitsune-Signature` header with the HMAC-SHA256 of `{{timestamp}}.{{body}}`. Store the secret now, it is not shown again.",
+                    webhook.id, webhook.url, webhook.events.join(", "), secret
+                ))
+                .color(0x2ecc71)
+                .footer(serenity::CreateEmbedFooter::new("Kitsune Integration"))
+        )
+        .ephemeral(true)
+    ).await?;
     
     Ok(())
 }
 
 #[poise::command( slash_command, guild_only = true, required_permissions = "ADMINISTRATOR", rename = "list")]
 pub async fn webhook_list(ctx: Context<'_>) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or("Command must be used in a guild")?.get() as i64;
+    let configured = queries::get_webhooks(&ctx.data().pool, guild_id).await?;
+    
+    let mut lines = Vec::new();
+    for webhook in &configured {
+        let recent = queries::get_webhook_deliveries(&ctx.data().pool, guild_id, webhook.id, 20).await?;
+        let delivered = recent.iter().filter(|d| d.status == "delivered").count();
+        let failed = recent.iter().filter(|d| d.status == "failed").count();
+        let pending = recent.len() - delivered - failed;
+        
+        lines.push(format!(
+            "**#{}** {}\nEvents: {}\nLast {} deliveries: {} delivered, {} pending, {} failed",
+            webhook.id, webhooks::display_url(&webhook.url), webhook.events.join(", "), recent.len(), delivered, pending, failed
+        ));
+    }
+    
     ctx.send(poise::CreateReply::default().embed(
         serenity::CreateEmbed::new()
             .title("📋 Configured Webhooks")
-            .description("No webhooks configured")
+            .description(if lines.is_empty() { "No webhooks configured".to_string() } else { lines.join("\n\n") })
             .color(0x3498db)
             .footer(serenity::CreateEmbedFooter::new("Kitsune Integration"))
     )).await?;
@@ -42,15 +108,116 @@ pub async fn webhook_list(ctx: Context<'_>) -> Result<(), Error> {
     Ok(())
 }
 
+#[poise::command( slash_command, guild_only = true, required_permissions = "ADMINISTRATOR", rename = "remove")]
+pub async fn webhook_remove(
+    ctx: Context<'_>,
+    #[description = "Webhook ID"] webhook_id: i32,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or("Command must be used in a guild")?.get() as i64;
+    
+    if !queries::remove_webhook(&ctx.data().pool, guild_id, webhook_id).await? {
+        return send_not_found(ctx, webhook_id).await;
+    }
+    
+    ctx.send(poise::CreateReply::default().embed(
+        serenity::CreateEmbed::new()
+            .title("🗑️ Webhook Removed")
+            .description(format!("Webhook #{} and its pending deliveries were removed", webhook_id))
+            .color(0x2ecc71)
+            .footer(serenity::CreateEmbedFooter::new("Kitsune Integration"))
+    )).await?;
+    
+    Ok(())
+}
+
 #[poise::command( slash_command, guild_only = true, required_permissions = "ADMINISTRATOR", rename = "test")]
 pub async fn webhook_test(
     ctx: Context<'_>,
-    #[description = "Webhook ID"] webhook_id: String,
+    #[description = "Webhook ID"] webhook_id: i32,
 ) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or("Command must be used in a guild")?.get() as i64;
+    
+    let Some(webhook) = queries::get_webhook(&ctx.data().pool, guild_id, webhook_id).await? else {
+        return send_not_found(ctx, webhook_id).await;
+    };
+    
+    ctx.defer().await?;
+    
+    let delivery_id = Uuid::new_v4();
+    let payload = webhooks::envelope(delivery_id, webhooks::TEST, guild_id, &json!({
+        "message": "Test delivery from Kitsune",
+        "requested_by": ctx.author().id.to_string(),
+        "subscribed_events": webhook.events,
+    }));
+    let delivery = queries::create_webhook_delivery(&ctx.data().pool, delivery_id, &webhook, webhooks::TEST, payload).await?;
+    let attempt = ctx.data().webhooks.attempt(&ctx.data().pool, &webhook, &delivery).await?;
+    
+    let (title, color) = if attempt.succeeded() {
+        ("🧪 Webhook Test Delivered", 0x2ecc71)
+    } else {
+        ("🧪 Webhook Test Failed", 0xe74c3c)
+    };
+    
     ctx.send(poise::CreateReply::default().embed(
         serenity::CreateEmbed::new()
-            .title("🧪 Webhook Test")
-            .description(format!("Testing webhook: {}", webhook_id))
+            .title(title)
+            .description(format!(
+                "**Webhook:** #{} {}\n**Delivery:** `{}`\n**Result:** {}",
+                webhook.id, webhooks::display_url(&webhook.url), delivery.id, attempt.describe()
+            ))
+            .color(color)
+            .footer(serenity::CreateEmbedFooter::new("Kitsune Integration"))
+    )).await?;
+    
+    Ok(())
+}
+
+#[poise::command( slash_command, guild_only = true, required_permissions = "ADMINISTRATOR", rename = "deliveries")]
+pub async fn webhook_deliveries(
+    ctx: Context<'_>,
+    #[description = "Webhook ID"] webhook_id: i32,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or("Command must be used in a guild")?.get() as i64;
+    
+    let Some(webhook) = queries::get_webhook(&ctx.data().pool, guild_id, webhook_id).await? else {
+        return send_not_found(ctx, webhook_id).await;
+    };
+    
+    let deliveries = queries::get_webhook_deliveries(&ctx.data().pool, guild_id, webhook.id, 15).await?;
+    
+    let description = if deliveries.is_empty() {
+        "No deliveries yet".to_string()
+    } else {
+        deliveries.iter()
+            .map(|delivery| {
+                let icon = match delivery.status.as_str() {
+                    "delivered" => "✅",
+                    "failed" => "❌",
+                    _ => "⏳",
+                };
+                let detail = match (&delivery.last_error, delivery.last_status_code) {
+                    (Some(error), _) if delivery.status != "delivered" => format!(" — {}", error),
+                    (_, Some(code)) => format!(" — HTTP {}", code),
+                    _ => String::new(),
+                };
+                let retry = if delivery.status == "pending" && delivery.attempts > 0 {
+                    format!(", retry <t:{}:R>", delivery.next_attempt_at.timestamp())
+                } else {
+                    String::new()
+                };
+                format!(
+                    "{} `{}` <t:{}:R> ({} attempts{}){}",
+                    icon, delivery.event, delivery.created_at.timestamp(), delivery.attempts, retry, detail
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+    
+    ctx.send(poise::CreateReply::default().embed(
+        serenity::CreateEmbed::new()
+            .title(format!("📬 Deliveries for Webhook #{}", webhook.id))
+            .description(description)
             .color(0x3498db)
             .footer(serenity::CreateEmbedFooter::new("Kitsune Integration"))
     )).await?;
@@ -58,15 +225,138 @@ pub async fn webhook_test(
     Ok(())
 }
 
-#[poise::command( slash_command, guild_only = true, required_permissions = "ADMINISTRATOR")]
+async fn send_not_found(ctx: Context<'_>, webhook_id: i32) -> Result<(), Error> {
+    ctx.send(poise::CreateReply::default().embed(
+        serenity::CreateEmbed::new()
+            .title("❌ Webhook Not Found")
+            .description(format!("No webhook #{} exists for this server. Use `/admin webhook list` to see configured webhooks.", webhook_id))
+            .color(0xe74c3c)
+            .footer(serenity::CreateEmbedFooter::new("Kitsune Integration"))
+    )).await?;
+    
+    Ok(())
+}
+
+#[poise::command(
+    slash_command,
+    guild_only = true,
+    required_permissions = "ADMINISTRATOR",
+    subcommands("api_info", "api_token_create", "api_token_list", "api_token_revoke")
+)]
 pub async fn api(ctx: Context<'_>) -> Result<(), Error> {
+    ctx.say("Use api subcommands to manage REST API access").await?;
+    Ok(())
+}
+
+#[poise::command( slash_command, guild_only = true, required_permissions = "ADMINISTRATOR", rename = "info")]
+pub async fn api_info(ctx: Context<'_>) -> Result<(), Error> {
+    let config = &ctx.data().guild_configs.file_config().api;
+    
+    let description = if config.enabled {
+        format!(
+            "**Status:** ✅ Enabled\n**Base path:** `http://{}/api/v1`\n\nAuthenticate with `Authorization: Bearer <token>` using a token from `/admin api token_create`.\n\n**Endpoints:** `incidents`, `forensics`, `config`, `whitelist`, `blacklist`, `lockdown`",
+            config.bind_address
+        )
+    } else {
+        "**Status:** ❌ Disabled\n\nThe bot operator can enable it in the `[api]` section of the configuration file.".to_string()
+    };
+    
     ctx.send(poise::CreateReply::default().embed(
         serenity::CreateEmbed::new()
             .title("🔌 API Information")
-            .description("**Kitsune API**\n\nEndpoint: https://api.kitsune.bot\nVersion: v1\n\nDocumentation available at: https://docs.kitsune.bot/api")
+            .description(description)
+            .color(0x3498db)
+            .footer(serenity::CreateEmbedFooter::new("Kitsune Integration"))
+    )).await?;
+    
+    Ok(())
+}
+
+#[poise::command( slash_command, guild_only = true, required_permissions = "ADMINISTRATOR", rename = "token_create")]
+pub async fn api_token_create(
+    ctx: Context<'_>,
+    #[description = "Name to recognise the token by"] name: String,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or("Command must be used in a guild")?.get() as i64;
+    
+    let token = api::generate_token();
+    let created = queries::create_api_token(
+        &ctx.data().pool,
+        guild_id,
+        name.trim(),
+        &api::hash_token(&token),
+        ctx.author().id.get() as i64
+    ).await?;
+    
+    ctx.send(poise::CreateReply::default()
+        .embed(
+            serenity::CreateEmbed::new()
+                .title("🔑 API Token Created")
+                .description(format!(
+                    "**ID:** {}\n**Name:** {}\n\n**Token:** ||{}||\n\nThe token grants full API access to this server. Store it now, it is not shown again.",
+                    created.id, created.name, token
+                ))
+                .color(0x2ecc71)
+                .footer(serenity::CreateEmbedFooter::new("Kitsune Integration"))
+        )
+        .ephemeral(true)
+    ).await?;
+    
+    Ok(())
+}
+
+#[poise::command( slash_command, guild_only = true, required_permissions = "ADMINISTRATOR", rename = "token_list")]
+pub async fn api_token_list(ctx: Context<'_>) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or("Command must be used in a guild")?.get() as i64;
+    let tokens = queries::get_api_tokens(&ctx.data().pool, guild_id).await?;
+    
+    let description = if tokens.is_empty() {
+        "No API tokens".to_string()
+    } else {
+        tokens.iter()
+            .map(|token| format!(
+                "**#{}** {} — created by <@{}> <t:{}:R>, {}",
+                token.id,
+                token.name,
+                token.created_by,
+                token.created_at.timestamp(),
+                token.last_used_at.map(|t| format!("last used <t:{}:R>", t.timestamp())).unwrap_or_else(|| "never used".to_string())
+            ))
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+    
+    ctx.send(poise::CreateReply::default().embed(
+        serenity::CreateEmbed::new()
+            .title("🔑 API Tokens")
+            .description(description)
             .color(0x3498db)
             .footer(serenity::CreateEmbedFooter::new("Kitsune Integration"))
     )).await?;
     
     Ok(())
 }
+
+#[poise::command( slash_command, guild_only = true, required_permissions = "ADMINISTRATOR", rename = "token_revoke")]
+pub async fn api_token_revoke(
+    ctx: Context<'_>,
+    #[description = "Token ID"] token_id: i32,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or("Command must be used in a guild")?.get() as i64;
+    
+    let (title, description, color) = if queries::revoke_api_token(&ctx.data().pool, guild_id, token_id).await? {
+        ("🗑️ API Token Revoked", format!("Token #{} no longer has access", token_id), 0x2ecc71)
+    } else {
+        ("❌ API Token Not Found", format!("No token #{} exists for this server. Use `/admin api token_list` to see tokens.", token_id), 0xe74c3c)
+    };
+    
+    ctx.send(poise::CreateReply::default().embed(
+        serenity::CreateEmbed::new()
+            .title(title)
+            .description(description)
+            .color(color)
+            .footer(serenity::CreateEmbedFooter::new("Kitsune Integration"))
+    )).await?;
+    
+    Ok(())
+}
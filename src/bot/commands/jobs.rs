@@ -0,0 +1,63 @@
+use poise::serenity_prelude as serenity;
+use crate::database::queries;
+use crate::bot::{Context, Error, scheduler::Job};
+
+#[poise::command( slash_command, guild_only = true, required_permissions = "ADMINISTRATOR", rename = "jobs")]
+pub async fn jobs_list(ctx: Context<'_>) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or("Command must be used in a guild")?.get() as i64;
+    let jobs = queries::get_pending_jobs(&ctx.data().pool, guild_id).await?;
+    
+    let description = if jobs.is_empty() {
+        "No scheduled jobs".to_string()
+    } else {
+        jobs.iter()
+            .map(|job| {
+                let name = Job::from_row(job).map(|kind| kind.describe()).unwrap_or_else(|_| job.job_type.clone());
+                let status = if job.status == "running" { " (running)" } else { "" };
+                let error = job.last_error.as_deref()
+                    .map(|e| format!("\n  Last error: {}", e))
+                    .unwrap_or_default();
+                format!("`{}`\n• {} <t:{}:R>{}{}", job.id, name, job.run_at.timestamp(), status, error)
+            })
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    };
+    
+    ctx.send(poise::CreateReply::default().embed(
+        serenity::CreateEmbed::new()
+            .title("⏰ Scheduled Jobs")
+            .description(description)
+            .color(0x3498db)
+            .footer(serenity::CreateEmbedFooter::new("Kitsune Guardian Fox"))
+    )).await?;
+    
+    Ok(())
+}
+
+#[poise::command( slash_command, guild_only = true, required_permissions = "ADMINISTRATOR", rename = "job_cancel")]
+pub async fn jobs_cancel(
+    ctx: Context<'_>,
+    #[description = "Job ID from /admin jobs"] job_id: String,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or("Command must be used in a guild")?.get() as i64;
+    let job_id = uuid::Uuid::parse_str(job_id.trim()).map_err(|_| "Invalid job ID")?;
+    
+    let cancelled = queries::cancel_job(&ctx.data().pool, guild_id, job_id).await?;
+    
+    ctx.send(poise::CreateReply::default().embed(
+        if cancelled {
+            serenity::CreateEmbed::new()
+                .title("✅ Job Cancelled")
+                .description(format!("Job `{}` will not run", job_id))
+                .color(0x2ecc71)
+        } else {
+            serenity::CreateEmbed::new()
+                .title("❌ Job Not Found")
+                .description(format!("No pending job `{}` in this server", job_id))
+                .color(0xe74c3c)
+        }
+        .footer(serenity::CreateEmbedFooter::new("Kitsune Guardian Fox"))
+    )).await?;
+    
+    Ok(())
+}
@@ -1,30 +1,63 @@
 use poise::serenity_prelude as serenity;
 use crate::database::queries;
-use crate::bot::{Context, Error};
+use crate::bot::{Context, Error, lockdown, raid_mode, scheduler::{self, Job}};
 
 #[poise::command( slash_command, guild_only = true, required_permissions = "ADMINISTRATOR")]
 pub async fn lockdown(
     ctx: Context<'_>,
     #[description = "Enable lockdown"] enable: bool,
-    #[description = "Duration in minutes (optional)"] duration: Option<u64>,
+    #[description = "Duration in minutes (optional)"]
+    #[max = 525600]
+    duration: Option<u64>,
 ) -> Result<(), Error> {
     let guild_id = ctx.guild_id().ok_or("Command must be used in a guild")?.get() as i64;
     
-    queries::set_lockdown(&ctx.data().pool, guild_id, enable).await?;
+    // Checked before engaging so a bad duration can't leave a lockdown with no lift.
+    if let Some(minutes) = duration {
+        scheduler::minutes_from_now(minutes)?;
+    }
+    
+    let data = ctx.data();
+    
+    ctx.defer().await?;
+    
+    // A moderator taking over means raid mode must not lift this lockdown on its own.
+    data.raid_modes.set_auto_locked(guild_id, false);
+    raid_mode::persist(&data.pool, &data.raid_modes, guild_id).await?;
+    
+    let report = if enable {
+        let guild_config = data.guild_configs.get(&data.pool, guild_id).await?;
+        lockdown::engage(ctx.http(), &data.pool, guild_id, &guild_config).await?
+    } else {
+        lockdown::lift(ctx.http(), &data.pool, guild_id).await?
+    };
     
     let status = if enable { "enabled" } else { "disabled" };
     let emoji = if enable { "🔒" } else { "✅" };
     
-    let duration_text = if let Some(d) = duration {
-        format!("\n**Duration:** {} minutes", d)
-    } else {
-        String::new()
+    let duration_text = match duration.filter(|_| enable) {
+        Some(minutes) => {
+            let author_id = ctx.author().id.get() as i64;
+            let lift = scheduler::schedule_lift(&data.pool, guild_id, minutes.max(1), Some(author_id)).await?;
+            format!("\n**Duration:** {} minutes (lifts <t:{}:R>)", minutes.max(1), lift.run_at.timestamp())
+        }
+        None => {
+            queries::cancel_jobs_of_type(&data.pool, guild_id, Job::LIFT_LOCKDOWN).await?;
+            String::new()
+        }
     };
     
     ctx.send(poise::CreateReply::default().embed(
         serenity::CreateEmbed::new()
             .title(format!("{} Lockdown {}", emoji, if enable { "Enabled" } else { "Disabled" }))
-            .description(format!("Server lockdown has been **{}**{}", status, duration_text))
+            .description(format!(
+                "Server lockdown has been **{}**\n**Channels {}:** {}{}{}",
+                status,
+                if enable { "locked" } else { "restored" },
+                report.channels,
+                if report.failed > 0 { format!(" ({} failed, check Kitsune's Manage Channels permission)", report.failed) } else { String::new() },
+                duration_text
+            ))
             .color(if enable { 0xe74c3c } else { 0x2ecc71 })
             .footer(serenity::CreateEmbedFooter::new("Kitsune Guardian Fox"))
     )).await?;
@@ -39,10 +72,20 @@ pub async fn lockdown_status(ctx: Context<'_>) -> Result<(), Error> {
     
     let is_locked = guild.as_ref().map(|g| g.lockdown_active).unwrap_or(false);
     
+    let scheduled = queries::get_pending_jobs(&ctx.data().pool, guild_id).await?
+        .into_iter()
+        .filter_map(|job| Job::from_row(&job).ok().map(|kind| (kind, job.run_at)))
+        .map(|(kind, run_at)| format!("\n• {} <t:{}:R>", kind.describe(), run_at.timestamp()))
+        .collect::<String>();
+    
     ctx.send(poise::CreateReply::default().embed(
         serenity::CreateEmbed::new()
             .title("🔒 Lockdown Status")
-            .description(format!("**Status:** {}", if is_locked { "🔒 Active" } else { "✅ Inactive" }))
+            .description(format!(
+                "**Status:** {}{}",
+                if is_locked { "🔒 Active" } else { "✅ Inactive" },
+                if scheduled.is_empty() { String::new() } else { format!("\n\n**Scheduled:**{}", scheduled) }
+            ))
             .color(if is_locked { 0xe74c3c } else { 0x2ecc71 })
             .footer(serenity::CreateEmbedFooter::new("Kitsune Guardian Fox"))
     )).await?;
@@ -53,12 +96,42 @@ pub async fn lockdown_status(ctx: Context<'_>) -> Result<(), Error> {
 #[poise::command( slash_command, guild_only = true, required_permissions = "ADMINISTRATOR", rename = "lockdown_schedule")]
 pub async fn lockdown_schedule(
     ctx: Context<'_>,
-    #[description = "Minutes from now to enable lockdown"] minutes: u64,
+    #[description = "Minutes from now to enable lockdown"]
+    #[max = 525600]
+    minutes: u64,
+    #[description = "How long the lockdown lasts in minutes (optional)"]
+    #[max = 525600]
+    duration: Option<u64>,
 ) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or("Command must be used in a guild")?.get() as i64;
+    let author_id = ctx.author().id.get() as i64;
+    
+    let run_at = scheduler::minutes_from_now(minutes)?;
+    if let Some(minutes) = duration {
+        scheduler::minutes_from_now(minutes)?;
+    }
+    let job = scheduler::schedule(
+        &ctx.data().pool,
+        guild_id,
+        Job::StartLockdown { duration_minutes: duration.map(|d| d.max(1)) },
+        run_at,
+        Some(author_id)
+    ).await?;
+    
+    let duration_text = match duration {
+        Some(d) => format!(" for {} minutes", d.max(1)),
+        None => String::new(),
+    };
+    
     ctx.send(poise::CreateReply::default().embed(
         serenity::CreateEmbed::new()
             .title("⏰ Lockdown Scheduled")
-            .description(format!("Lockdown will activate in {} minutes", minutes))
+            .description(format!(
+                "Lockdown will activate <t:{}:R>{}\n**Job ID:** `{}`\n\nCancel it with `/admin job_cancel`.",
+                run_at.timestamp(),
+                duration_text,
+                job.id
+            ))
             .color(0xf39c12)
             .footer(serenity::CreateEmbedFooter::new("Kitsune Guardian Fox"))
     )).await?;
@@ -1,6 +1,6 @@
 use poise::serenity_prelude as serenity;
 use crate::database::queries;
-use crate::bot::{Context, Error};
+use crate::bot::{webhooks, Context, Error};
 use serde_json::json;
 
 #[poise::command( slash_command, guild_only = true, required_permissions = "BAN_MEMBERS")]
@@ -19,7 +19,7 @@ pub async fn ban(
     let member = guild_id.member(&ctx, user_id).await?;
     member.ban_with_reason(&ctx, delete_days, &reason_str).await?;
     
-    queries::create_incident(
+    let incident = queries::create_incident(
         &ctx.data().pool,
         guild_id.get() as i64,
         user_id.get() as i64,
@@ -29,6 +29,7 @@ pub async fn ban(
         json!({"reason": reason_str, "moderator": moderator_id}),
         Some("ban")
     ).await?;
+    webhooks::emit_incident(&ctx.data().pool, &incident).await;
     
     queries::update_user_reputation(&ctx.data().pool, user_id.get() as i64, -20).await?;
     
@@ -57,7 +58,7 @@ pub async fn kick(
     let member = guild_id.member(&ctx, user_id).await?;
     member.kick_with_reason(&ctx, &reason_str).await?;
     
-    queries::create_incident(
+    let incident = queries::create_incident(
         &ctx.data().pool,
         guild_id.get() as i64,
         user_id.get() as i64,
@@ -67,6 +68,7 @@ pub async fn kick(
         json!({"reason": reason_str, "moderator": moderator_id}),
         Some("kick")
     ).await?;
+    webhooks::emit_incident(&ctx.data().pool, &incident).await;
     
     queries::update_user_reputation(&ctx.data().pool, user_id.get() as i64, -10).await?;
     
@@ -99,7 +101,7 @@ pub async fn timeout(
     
     member.disable_communication_until_datetime(&ctx, timeout_until).await?;
     
-    queries::create_incident(
+    let incident = queries::create_incident(
         &ctx.data().pool,
         guild_id.get() as i64,
         user_id.get() as i64,
@@ -109,6 +111,7 @@ pub async fn timeout(
         json!({"reason": reason_str, "duration_minutes": duration}),
         Some("timeout")
     ).await?;
+    webhooks::emit_incident(&ctx.data().pool, &incident).await;
     
     ctx.send(poise::CreateReply::default().embed(
         serenity::CreateEmbed::new()
@@ -131,7 +134,7 @@ pub async fn warn(
     let user_id = user.id;
     let moderator_id = ctx.author().id.get() as i64;
     
-    queries::create_incident(
+    let incident = queries::create_incident(
         &ctx.data().pool,
         guild_id.get() as i64,
         user_id.get() as i64,
@@ -141,6 +144,7 @@ pub async fn warn(
         json!({"reason": reason, "moderator": moderator_id}),
         Some("warning")
     ).await?;
+    webhooks::emit_incident(&ctx.data().pool, &incident).await;
     
     let user_incidents = queries::get_user_incidents(&ctx.data().pool, user_id.get() as i64, 10).await?;
     let warnings = user_incidents.iter().filter(|i| i.incident_type == "warning").count();
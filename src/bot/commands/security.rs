@@ -1,5 +1,5 @@
 use poise::serenity_prelude as serenity;
-use crate::database::{queries, models::ThreatLevel};
+use crate::database::queries;
 use crate::bot::{Context, Error};
 use chrono::{Utc, Duration};
 
@@ -10,15 +10,21 @@ pub async fn status(ctx: Context<'_>) -> Result<(), Error> {
     let guild = queries::get_guild(&ctx.data().pool, guild_id).await?;
     let recent_incidents = queries::get_recent_incidents(&ctx.data().pool, guild_id, 10).await?;
     
-    let raid_analysis = ctx.data().raid_detector.analyze_raid_risk(guild_id);
+    let guild_config = ctx.data().guild_configs.get(&ctx.data().pool, guild_id).await?;
+    let raid_analysis = ctx.data().raid_detector.analyze_raid_risk(guild_id, &guild_config).await?;
     
-    let threat_level = ThreatLevel::from_score(raid_analysis.threat_score);
+    let threat_level = ctx.data().auto_mod.threat_level(&guild_config, raid_analysis.threat_score);
     let lockdown = guild.map(|g| g.lockdown_active).unwrap_or(false);
+    let raid_mode = match ctx.data().raid_modes.state(guild_id) {
+        Some(state) => format!("{} (since <t:{}:R>)", state.mode.as_str(), state.since.timestamp()),
+        None => "Normal".to_string(),
+    };
     
     let description = format!(
-        "**Threat Level:** {} ({:.2})\n**Lockdown:** {}\n**Recent Incidents:** {}\n\n**Raid Detection:**\n- 5s: {} joins\n- 30s: {} joins\n- 1m: {} joins\n- 5m: {} joins\n\n**Analysis:**\n{}",
+        "**Threat Level:** {} ({:.2})\n**Raid Mode:** {}\n**Lockdown:** {}\n**Recent Incidents:** {}\n\n**Raid Detection:**\n- 5s: {} joins\n- 30s: {} joins\n- 1m: {} joins\n- 5m: {} joins\n\n**Analysis:**\n{}",
         threat_level.as_str(),
         raid_analysis.threat_score,
+        raid_mode,
         if lockdown { "🔒 Active" } else { "✅ Inactive" },
         recent_incidents.len(),
         raid_analysis.join_rate_5s,
@@ -51,14 +57,15 @@ pub async fn scan(ctx: Context<'_>) -> Result<(), Error> {
     
     let guild = ctx.guild_id().unwrap().to_partial_guild(&ctx).await?;
     let members = guild.members(&ctx, None, None).await?;
+    let guild_config = ctx.data().guild_configs.get(&ctx.data().pool, guild_id).await?;
     
     let mut suspicious_count = 0;
     let mut high_threat_users = Vec::new();
     
     for member in members.iter().take(100) {
         let user_id = member.user.id.get() as i64;
-        let metrics = ctx.data().behavior_analyzer.get_behavioral_metrics(guild_id, user_id);
-        let honeypot_catches = ctx.data().honeypot.get_user_catches(guild_id, user_id);
+        let metrics = ctx.data().behavior_analyzer.get_behavioral_metrics(guild_id, user_id, &guild_config).await?;
+        let honeypot_catches = ctx.data().honeypot.get_user_catches(guild_id, user_id).await?;
         
         let threat_score = metrics.threat_score + (honeypot_catches.len() as f32 * 0.2);
         
@@ -112,10 +119,11 @@ pub async fn check(
     let db_user = queries::get_user(&ctx.data().pool, user_id).await?;
     let incidents = queries::get_user_incidents(&ctx.data().pool, user_id, 5).await?;
     
-    let behavioral_metrics = ctx.data().behavior_analyzer.get_behavioral_metrics(guild_id, user_id);
-    let honeypot_catches = ctx.data().honeypot.get_user_catches(guild_id, user_id);
+    let guild_config = ctx.data().guild_configs.get(&ctx.data().pool, guild_id).await?;
+    let behavioral_metrics = ctx.data().behavior_analyzer.get_behavioral_metrics(guild_id, user_id, &guild_config).await?;
+    let honeypot_catches = ctx.data().honeypot.get_user_catches(guild_id, user_id).await?;
     
-    let threat_level = ThreatLevel::from_score(behavioral_metrics.threat_score);
+    let threat_level = ctx.data().auto_mod.threat_level(&guild_config, behavioral_metrics.threat_score);
     
     let description = format!(
         "**User:** {}\n**ID:** {}\n**Global Reputation:** {}\n**Total Incidents:** {}\n\n**Threat Level:** {} ({:.2})\n\n**Behavioral Metrics:**\n- Spam Score: {:.2}\n- Link Density: {:.2}\n- Mention Ratio: {:.2}\n- Caps Ratio: {:.2}\n\n**Honeypot Catches:** {}\n**Recent Incidents:** {}",
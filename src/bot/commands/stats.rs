@@ -1,7 +1,20 @@
 use poise::serenity_prelude as serenity;
 use crate::database::queries;
+use crate::database::models::{ForensicEvent, ForensicFilter, IncidentFilter};
 use crate::bot::{Context, Error};
-use chrono::{Utc, Duration};
+use crate::bot::{alerts, export};
+use crate::utils::time;
+use chrono::{DateTime, Duration, Utc};
+use serde_json::json;
+use std::collections::BTreeMap;
+use uuid::Uuid;
+
+const SEARCH_PAGE_SIZE: i64 = 10;
+const TIMELINE_FETCH_LIMIT: i64 = 100;
+const TIMELINE_ENTRIES: usize = 25;
+const INCIDENT_CONTEXT_MINUTES: i64 = 10;
+/// Events shown on each side of an incident.
+const INCIDENT_CONTEXT_EVENTS: i64 = 7;
 
 #[poise::command(
     slash_command,
@@ -19,7 +32,8 @@ pub async fn stats(ctx: Context<'_>) -> Result<(), Error> {
     
     let incidents_7d = queries::get_recent_incidents(&ctx.data().pool, guild_id, 100).await?.len();
     
-    let raid_analysis = ctx.data().raid_detector.analyze_raid_risk(guild_id);
+    let guild_config = ctx.data().guild_configs.get(&ctx.data().pool, guild_id).await?;
+    let raid_analysis = ctx.data().raid_detector.analyze_raid_risk(guild_id, &guild_config).await?;
     
     let description = format!(
         "**Security Overview**\n\n**Incidents:**\n- Last 24h: {}\n- Last 7d: {}\n\n**Current Threat Level:** {:.2}\n**Raid Risk:** {}\n\n**Auto-Mod Status:** ✅ Active",
@@ -72,7 +86,8 @@ pub async fn stats_user(
     let user_id = user.id.get() as i64;
     
     let incidents = queries::get_user_incidents(&ctx.data().pool, user_id, 100).await?;
-    let metrics = ctx.data().behavior_analyzer.get_behavioral_metrics(guild_id, user_id);
+    let guild_config = ctx.data().guild_configs.get(&ctx.data().pool, guild_id).await?;
+    let metrics = ctx.data().behavior_analyzer.get_behavioral_metrics(guild_id, user_id, &guild_config).await?;
     
     let description = format!(
         "**User:** {}\n\n**Activity:**\n- Total Incidents: {}\n- Threat Score: {:.2}\n- Spam Score: {:.2}\n\n**Status:** {}",
@@ -148,17 +163,47 @@ pub async fn report_generate(
     ctx: Context<'_>,
     #[description = "Hours to include in report"] hours: Option<i64>,
 ) -> Result<(), Error> {
-    let hours = hours.unwrap_or(24);
+    let guild_id = ctx.guild_id().ok_or("Command must be used in a guild")?.get() as i64;
+    let hours = hours.unwrap_or(24).clamp(1, 720);
     
     ctx.defer().await?;
     
-    ctx.send(poise::CreateReply::default().embed(
-        serenity::CreateEmbed::new()
-            .title("📄 Security Report Generated")
-            .description(format!("Report for last {} hours has been generated.\n\nDownload link will be available for 24 hours.", hours))
-            .color(0x3498db)
-            .footer(serenity::CreateEmbedFooter::new("Kitsune Guardian Fox"))
-    )).await?;
+    let cutoff = Utc::now() - Duration::hours(hours);
+    let incidents: Vec<_> = queries::get_recent_incidents(&ctx.data().pool, guild_id, 1000).await?
+        .into_iter()
+        .filter(|i| i.created_at >= cutoff)
+        .collect();
+    
+    let mut by_type: BTreeMap<&str, usize> = BTreeMap::new();
+    let mut by_severity: BTreeMap<&str, usize> = BTreeMap::new();
+    let mut by_action: BTreeMap<&str, usize> = BTreeMap::new();
+    for incident in &incidents {
+        *by_type.entry(incident.incident_type.as_str()).or_default() += 1;
+        *by_severity.entry(incident.severity.as_str()).or_default() += 1;
+        *by_action.entry(incident.action_taken.as_deref().unwrap_or("none")).or_default() += 1;
+    }
+    
+    let summarize = |counts: &BTreeMap<&str, usize>| {
+        if counts.is_empty() {
+            "None".to_string()
+        } else {
+            counts.iter().map(|(k, v)| format!("• {}: {}", k, v)).collect::<Vec<_>>().join("\n")
+        }
+    };
+    
+    let embed = serenity::CreateEmbed::new()
+        .title("📄 Security Report")
+        .description(format!("**Time Range:** Last {} hours\n**Total Incidents:** {}", hours, incidents.len()))
+        .field("By Type", summarize(&by_type), true)
+        .field("By Severity", summarize(&by_severity), true)
+        .field("By Action", summarize(&by_action), true)
+        .color(0x3498db)
+        .footer(serenity::CreateEmbedFooter::new("Kitsune Guardian Fox"));
+    
+    let guild_config = ctx.data().guild_configs.get(&ctx.data().pool, guild_id).await?;
+    alerts::dispatch_report(&ctx.serenity_context().http, &guild_config, embed.clone()).await;
+    
+    ctx.send(poise::CreateReply::default().embed(embed)).await?;
     
     Ok(())
 }
@@ -167,7 +212,7 @@ pub async fn report_generate(
     slash_command,
     guild_only = true,
     required_permissions = "ADMINISTRATOR",
-    subcommands("forensics_incident", "forensics_user", "forensics_search")
+    subcommands("forensics_incident", "forensics_user", "forensics_search", "forensics_retention")
 )]
 pub async fn forensics(ctx: Context<'_>) -> Result<(), Error> {
     ctx.say("Use forensics subcommands for detailed analysis").await?;
@@ -179,10 +224,74 @@ pub async fn forensics_incident(
     ctx: Context<'_>,
     #[description = "Incident ID"] incident_id: String,
 ) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or("Command must be used in a guild")?.get() as i64;
+    let incident_id = Uuid::parse_str(incident_id.trim()).map_err(|_| "Incident ID must be a UUID")?;
+    
+    let Some(incident) = queries::get_incident(&ctx.data().pool, guild_id, incident_id).await? else {
+        ctx.send(poise::CreateReply::default().embed(
+            serenity::CreateEmbed::new()
+                .title("❌ Incident Not Found")
+                .description(format!("No incident `{}` in this server", incident_id))
+                .color(0xe74c3c)
+                .footer(serenity::CreateEmbedFooter::new("Kitsune Guardian Fox"))
+        )).await?;
+        return Ok(());
+    };
+    
+    // Each side is fetched separately so a burst after the incident can't
+    // push out everything that led up to it.
+    let window = Duration::minutes(INCIDENT_CONTEXT_MINUTES);
+    let mut surrounding = queries::search_forensic_events(&ctx.data().pool, guild_id, &ForensicFilter {
+        since: Some(incident.created_at - window),
+        before: Some(incident.created_at),
+        limit: INCIDENT_CONTEXT_EVENTS,
+        ..Default::default()
+    }).await?;
+    surrounding.reverse();
+    surrounding.extend(queries::export_forensic_events(
+        &ctx.data().pool,
+        guild_id,
+        incident.created_at,
+        incident.created_at + window,
+        None,
+        INCIDENT_CONTEXT_EVENTS
+    ).await?);
+    
+    let evidence = serde_json::to_string_pretty(&incident.evidence)?;
+    let evidence = format!("```json\n{}\n```", truncate(&evidence, 1000));
+    
+    let context = if surrounding.is_empty() {
+        "No forensic events recorded around this incident".to_string()
+    } else {
+        surrounding.iter()
+            .map(|event| {
+                let marker = if event.user_id == Some(incident.user_id) { "▶" } else { "•" };
+                format!("{} {}", marker, describe_event(event))
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+    
     ctx.send(poise::CreateReply::default().embed(
         serenity::CreateEmbed::new()
             .title("🔍 Incident Forensics")
-            .description(format!("Detailed analysis for incident: {}", incident_id))
+            .description(format!(
+                "**ID:** `{}`\n**User:** <@{}>\n**Type:** {}\n**Severity:** {}\n**Threat Score:** {:.2}\n**Action:** {}\n**Status:** {}\n**Time:** <t:{}:f>",
+                incident.id,
+                incident.user_id,
+                incident.incident_type,
+                incident.severity,
+                incident.threat_score,
+                incident.action_taken.as_deref().unwrap_or("none"),
+                if incident.resolved { "Resolved" } else { "Open" },
+                incident.created_at.timestamp()
+            ))
+            .field("Evidence", evidence, false)
+            .field(
+                format!("Events within {} minutes (▶ = this user)", INCIDENT_CONTEXT_MINUTES),
+                truncate(&context, 1024),
+                false
+            )
             .color(0x3498db)
             .footer(serenity::CreateEmbedFooter::new("Kitsune Guardian Fox"))
     )).await?;
@@ -196,13 +305,88 @@ pub async fn forensics_user(
     #[description = "User to analyze"] user: serenity::User,
     #[description = "Hours to look back"] hours: Option<i64>,
 ) -> Result<(), Error> {
-    let hours = hours.unwrap_or(24);
+    let guild_id = ctx.guild_id().ok_or("Command must be used in a guild")?.get() as i64;
+    let user_id = user.id.get() as i64;
+    let hours = hours.unwrap_or(24).clamp(1, 24 * 90);
+    let since = Utc::now() - Duration::hours(hours);
+    let pool = &ctx.data().pool;
+    
+    let events = queries::search_forensic_events(pool, guild_id, &ForensicFilter {
+        user_id: Some(user_id),
+        since: Some(since),
+        limit: TIMELINE_FETCH_LIMIT,
+        ..Default::default()
+    }).await?;
+    let incidents = queries::search_incidents(pool, guild_id, &IncidentFilter {
+        user_id: Some(user_id),
+        since: Some(since),
+        limit: TIMELINE_FETCH_LIMIT,
+        ..Default::default()
+    }).await?;
+    let catches = queries::get_user_honeypot_catches(pool, guild_id, user_id, since, TIMELINE_FETCH_LIMIT).await?;
+    // The timeline only holds the latest entries of each kind, so the totals
+    // are counted separately.
+    let activity = queries::get_user_activity(pool, guild_id, user_id, since).await?;
+    
+    let mut timeline: Vec<(DateTime<Utc>, String)> = Vec::new();
+    for event in &events {
+        let icon = match event.event_type.as_str() {
+            "member_join" => "📥",
+            "message" => "💬",
+            _ => "📝",
+        };
+        let mut line = format!("{} <t:{}:f> `{}` score {:.2}", icon, event.created_at.timestamp(), event.event_type, event.threat_score);
+        if let Some(content) = event.content.as_deref() {
+            line.push_str(&format!(" — {}", snippet(content, 80)));
+        }
+        timeline.push((event.created_at, line));
+    }
+    for incident in &incidents {
+        timeline.push((incident.created_at, format!(
+            "🚨 <t:{}:f> **{}** ({}) → {} `{}`",
+            incident.created_at.timestamp(),
+            incident.incident_type,
+            incident.severity,
+            incident.action_taken.as_deref().unwrap_or("no action"),
+            incident.id
+        )));
+    }
+    for catch in &catches {
+        timeline.push((catch.created_at, format!(
+            "🍯 <t:{}:f> {} — {}",
+            catch.created_at.timestamp(),
+            catch.trap_type,
+            snippet(&catch.trap_name, 60)
+        )));
+    }
+    timeline.sort_by_key(|(at, _)| *at);
+    
+    let hidden = timeline.len().saturating_sub(TIMELINE_ENTRIES);
+    let mut lines: Vec<String> = timeline.into_iter().skip(hidden).map(|(_, line)| line).collect();
+    if hidden > 0 {
+        lines.insert(0, format!("*… {} earlier entries not shown*", hidden));
+    }
+    let timeline = if lines.is_empty() {
+        "No recorded activity in this period".to_string()
+    } else {
+        truncate(&lines.join("\n"), 3500)
+    };
     
     ctx.send(poise::CreateReply::default().embed(
         serenity::CreateEmbed::new()
             .title("🔍 User Forensics")
-            .description(format!("Complete activity history for {} (last {} hours)", user.tag(), hours))
-            .color(0x3498db)
+            .description(format!(
+                "Timeline for {} (last {} hours)\n\n{}",
+                user.tag(),
+                hours,
+                timeline
+            ))
+            .field("Joins", activity.joins.to_string(), true)
+            .field("Flagged Messages", activity.messages.to_string(), true)
+            .field("Incidents", activity.incidents.to_string(), true)
+            .field("Honeypot Catches", activity.catches.to_string(), true)
+            .field("Peak Threat Score", format!("{:.2}", activity.peak_score), true)
+            .color(if activity.incidents == 0 && activity.catches == 0 { 0x3498db } else { 0xf39c12 })
             .thumbnail(user.face())
             .footer(serenity::CreateEmbedFooter::new("Kitsune Guardian Fox"))
     )).await?;
@@ -211,21 +395,171 @@ pub async fn forensics_user(
 }
 
 #[poise::command( slash_command, guild_only = true, required_permissions = "ADMINISTRATOR", rename = "search")]
+#[allow(clippy::too_many_arguments)]
 pub async fn forensics_search(
     ctx: Context<'_>,
-    #[description = "Search query"] query: String,
+    #[description = "Words to search for in message content and tags (supports \"phrases\", or, -word)"] query: Option<String>,
+    #[description = "Only events from this user"] user: Option<serenity::User>,
+    #[description = "Event type, e.g. message or member_join"] event_type: Option<String>,
+    #[description = "Only events with this tag"] tag: Option<String>,
+    #[description = "Minimum threat score (0.0-1.0)"] min_score: Option<f32>,
+    #[description = "Maximum threat score (0.0-1.0)"] max_score: Option<f32>,
+    #[description = "Start of the range: YYYY-MM-DD or RFC 3339"] since: Option<String>,
+    #[description = "End of the range: YYYY-MM-DD or RFC 3339"] until: Option<String>,
+    #[description = "Results page (default 1)"] page: Option<u32>,
 ) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or("Command must be used in a guild")?.get() as i64;
+    let page = page.unwrap_or(1).max(1);
+    let since = since.as_deref()
+        .map(|since| time::parse_bound(since, false).ok_or("`since` must be a date (YYYY-MM-DD) or an RFC 3339 timestamp"))
+        .transpose()?;
+    let until = until.as_deref()
+        .map(|until| time::parse_bound(until, true).ok_or("`until` must be a date (YYYY-MM-DD) or an RFC 3339 timestamp"))
+        .transpose()?;
+    
+    let filter = ForensicFilter {
+        text: query.clone().filter(|q| !q.trim().is_empty()),
+        event_type: event_type.clone(),
+        user_id: user.as_ref().map(|u| u.id.get() as i64),
+        tag: tag.clone(),
+        min_score,
+        max_score,
+        since,
+        before: until,
+        before_id: None,
+        limit: SEARCH_PAGE_SIZE + 1,
+        offset: (page as i64 - 1) * SEARCH_PAGE_SIZE,
+    };
+    let mut events = queries::search_forensic_events(&ctx.data().pool, guild_id, &filter).await?;
+    let has_more = events.len() as i64 > SEARCH_PAGE_SIZE;
+    events.truncate(SEARCH_PAGE_SIZE as usize);
+    
+    let mut criteria = Vec::new();
+    if let Some(query) = &filter.text {
+        criteria.push(format!("**Query:** {}", query));
+    }
+    if let Some(user) = &user {
+        criteria.push(format!("**User:** {}", user.tag()));
+    }
+    if let Some(event_type) = &event_type {
+        criteria.push(format!("**Type:** {}", event_type));
+    }
+    if let Some(tag) = &tag {
+        criteria.push(format!("**Tag:** {}", tag));
+    }
+    if min_score.is_some() || max_score.is_some() {
+        criteria.push(format!("**Score:** {:.2} – {:.2}", min_score.unwrap_or(0.0), max_score.unwrap_or(1.0)));
+    }
+    if let Some(since) = since {
+        criteria.push(format!("**Since:** <t:{}:f>", since.timestamp()));
+    }
+    if let Some(until) = until {
+        criteria.push(format!("**Until:** <t:{}:f>", until.timestamp()));
+    }
+    if criteria.is_empty() {
+        criteria.push("Latest events".to_string());
+    }
+    
+    let results = if events.is_empty() {
+        "No matching events".to_string()
+    } else {
+        events.iter().map(describe_event).collect::<Vec<_>>().join("\n")
+    };
+    
+    let footer = if has_more {
+        format!("Page {} · more results with page:{}", page, page + 1)
+    } else {
+        format!("Page {}", page)
+    };
+    
     ctx.send(poise::CreateReply::default().embed(
         serenity::CreateEmbed::new()
             .title("🔍 Forensic Search")
-            .description(format!("Search results for: **{}**", query))
+            .description(truncate(&format!("{}\n\n{}", criteria.join("\n"), results), 4000))
             .color(0x3498db)
-            .footer(serenity::CreateEmbedFooter::new("Kitsune Guardian Fox"))
+            .footer(serenity::CreateEmbedFooter::new(format!("Kitsune Guardian Fox · {}", footer)))
     )).await?;
     
     Ok(())
 }
 
+#[poise::command( slash_command, guild_only = true, required_permissions = "ADMINISTRATOR", rename = "retention")]
+pub async fn forensics_retention(
+    ctx: Context<'_>,
+    #[description = "Days to keep forensic data (0 keeps it forever)"] days: Option<i64>,
+    #[description = "Store message content and full metadata"] detailed_logging: Option<bool>,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or("Command must be used in a guild")?.get() as i64;
+    let pool = &ctx.data().pool;
+    
+    if let Some(days) = days {
+        queries::set_guild_setting(pool, guild_id, "forensics_retention_days", json!(days.clamp(0, 3650))).await?;
+    }
+    if let Some(enabled) = detailed_logging {
+        queries::set_guild_setting(pool, guild_id, "detailed_logging", json!(enabled)).await?;
+    }
+    let effective = ctx.data().guild_configs.reload(pool, guild_id).await?;
+    let forensics = &effective.forensics;
+    
+    let retention = match forensics.retention_days {
+        0 => "Forever".to_string(),
+        days => format!("{} days", days),
+    };
+    let expired = if ctx.data().guild_configs.file_config().forensics.archive {
+        "moved to the archive tables"
+    } else {
+        "deleted"
+    };
+    
+    ctx.send(poise::CreateReply::default().embed(
+        serenity::CreateEmbed::new()
+            .title(if days.is_some() || detailed_logging.is_some() { "✅ Forensic Retention Updated" } else { "🗄️ Forensic Retention" })
+            .description(format!(
+                "**Retention:** {}\n**Detailed logging:** {}\n\nForensic events, resolved incidents and honeypot catches older than the retention period are {}. Open incidents are always kept.",
+                retention,
+                if forensics.detailed_logging { "✅ Message content stored" } else { "❌ Reduced metadata only" },
+                expired
+            ))
+            .color(0x2ecc71)
+            .footer(serenity::CreateEmbedFooter::new("Kitsune Configuration"))
+    )).await?;
+    
+    Ok(())
+}
+
+fn describe_event(event: &ForensicEvent) -> String {
+    let mut line = format!("<t:{}:f> `{}`", event.created_at.timestamp(), event.event_type);
+    if let Some(user_id) = event.user_id {
+        line.push_str(&format!(" <@{}>", user_id));
+    }
+    line.push_str(&format!(" score {:.2}", event.threat_score));
+    if let Some(tags) = event.tags.as_ref().filter(|tags| !tags.is_empty()) {
+        line.push_str(&format!(" [{}]", tags.join(", ")));
+    }
+    if let Some(content) = event.content.as_deref() {
+        line.push_str(&format!("\n> {}", snippet(content, 120)));
+    }
+    line
+}
+
+/// One line of message content, safe to put inside an embed.
+fn snippet(content: &str, max_chars: usize) -> String {
+    let flat = content.replace(['\n', '\r'], " ").replace('`', "'");
+    if flat.chars().count() > max_chars {
+        format!("{}…", flat.chars().take(max_chars).collect::<String>())
+    } else {
+        flat
+    }
+}
+
+fn truncate(text: &str, max_chars: usize) -> String {
+    if text.chars().count() > max_chars {
+        format!("{}…", text.chars().take(max_chars - 1).collect::<String>())
+    } else {
+        text.to_string()
+    }
+}
+
 #[poise::command(
     slash_command,
     guild_only = true,
@@ -240,16 +574,106 @@ pub async fn export(ctx: Context<'_>) -> Result<(), Error> {
 #[poise::command( slash_command, guild_only = true, required_permissions = "ADMINISTRATOR", rename = "data")]
 pub async fn export_data(
     ctx: Context<'_>,
-    #[description = "Export format"]
-    format: String,
+    #[description = "Data to export: incidents, forensics, honeypot or profiles"] dataset: String,
+    #[description = "File format: json, csv or ndjson (default json)"] format: Option<String>,
+    #[description = "Start of the range: YYYY-MM-DD or RFC 3339 (default 30 days ago)"] since: Option<String>,
+    #[description = "End of the range: YYYY-MM-DD or RFC 3339 (default now)"] until: Option<String>,
+    #[description = "Replace message content with a placeholder"] redact: Option<bool>,
 ) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or("Command must be used in a guild")?.get() as i64;
+    let dataset = export::Dataset::parse(&dataset)
+        .ok_or("Dataset must be `incidents`, `forensics`, `honeypot` or `profiles`")?;
+    let format = export::Format::parse(format.as_deref().unwrap_or("json"))
+        .ok_or("Format must be `json`, `csv` or `ndjson`")?;
+    let since = match since {
+        Some(since) => time::parse_bound(&since, false).ok_or("`since` must be a date (YYYY-MM-DD) or an RFC 3339 timestamp")?,
+        None => Utc::now() - Duration::days(30),
+    };
+    let until = match until {
+        Some(until) => time::parse_bound(&until, true).ok_or("`until` must be a date (YYYY-MM-DD) or an RFC 3339 timestamp")?,
+        None => Utc::now(),
+    };
+    if since >= until {
+        return Err("`since` must be before `until`".into());
+    }
+    
+    let guild_config = ctx.data().guild_configs.get(&ctx.data().pool, guild_id).await?;
+    let enforced = guild_config.forensics.redact_exported_content;
+    let redact = enforced || redact.unwrap_or(false);
+    
+    ctx.defer_ephemeral().await?;
+    
+    let mut export = export::Export::new(&ctx.data().pool, export::ExportRequest {
+        guild_id,
+        dataset,
+        format,
+        since,
+        until,
+        redact,
+    });
+    
+    while let Some(part) = export.next_part().await? {
+        ctx.send(poise::CreateReply::default()
+            .content(format!("📎 Part {} — {} records", part.index, part.records))
+            .attachment(serenity::CreateAttachment::bytes(part.data, part.filename))
+            .ephemeral(true)
+        ).await?;
+        
+        if export.parts() >= export::MAX_PARTS {
+            break;
+        }
+    }
+    
+    queries::log_forensic_event(
+        &ctx.data().pool,
+        guild_id,
+        Some(ctx.author().id.get() as i64),
+        "data_export",
+        None,
+        json!({
+            "dataset": dataset.name(),
+            "format": format.extension(),
+            "since": since,
+            "until": until,
+            "records": export.records(),
+            "redacted": redact,
+            "complete": export.is_finished(),
+        }),
+        0.0,
+        vec!["export".to_string()]
+    ).await?;
+    
+    let mut description = format!(
+        "**Dataset:** {}\n**Format:** {}\n**Range:** <t:{}:f> → <t:{}:f>\n**Records:** {}\n**Files:** {}\n**Message content:** {}",
+        dataset.name(),
+        format.extension(),
+        since.timestamp(),
+        until.timestamp(),
+        export.records(),
+        export.parts(),
+        match (redact, enforced) {
+            (true, true) => "redacted (server policy)",
+            (true, false) => "redacted",
+            (false, _) => "included",
+        }
+    );
+    if !export.is_finished() {
+        if let Some(last) = export.last_written() {
+            description.push_str(&format!(
+                "\n\n⚠️ Stopped after {} files. Run the export again with `since:{}` to get the rest.",
+                export::MAX_PARTS,
+                last.to_rfc3339()
+            ));
+        }
+    }
+    
     ctx.send(poise::CreateReply::default().embed(
         serenity::CreateEmbed::new()
             .title("📦 Data Export")
-            .description(format!("Exporting data in {} format...", format))
-            .color(0x3498db)
+            .description(description)
+            .color(if export.is_finished() { 0x2ecc71 } else { 0xf39c12 })
             .footer(serenity::CreateEmbedFooter::new("Kitsune Guardian Fox"))
-    )).await?;
+    ).ephemeral(true)).await?;
     
     Ok(())
 }
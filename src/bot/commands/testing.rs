@@ -1,5 +1,10 @@
 use poise::serenity_prelude as serenity;
 use crate::bot::{Context, Error};
+use crate::bot::health::{self, DependencyCheck};
+use crate::bot::sharding;
+
+/// Keeps the Gateway field under Discord's 1024 character limit.
+const MAX_LISTED_SHARDS: usize = 12;
 
 #[poise::command(
     slash_command,
@@ -47,33 +52,121 @@ pub async fn test_ml(
     Ok(())
 }
 
+/// Dumps the raw health report, as served on `/readyz`.
 #[poise::command( slash_command, guild_only = true, required_permissions = "ADMINISTRATOR")]
 pub async fn debug(ctx: Context<'_>) -> Result<(), Error> {
+    ctx.defer_ephemeral().await?;
+    
+    let report = ctx.data().health.report().await;
+    let mut dump = serde_json::to_string_pretty(&report)?;
+    if dump.len() > 4000 {
+        let mut end = 4000;
+        while !dump.is_char_boundary(end) {
+            end -= 1;
+        }
+        dump.truncate(end);
+        dump.push_str("\n…");
+    }
+    
     ctx.send(poise::CreateReply::default().embed(
         serenity::CreateEmbed::new()
             .title("🔧 Debug Information")
-            .description("**Bot Status:** ✅ Online\n**Database:** ✅ Connected\n**Redis:** ✅ Connected\n**ML Model:** ⚠️ Not Loaded")
+            .description(format!("```json\n{}\n```", dump))
             .color(0x3498db)
             .footer(serenity::CreateEmbedFooter::new("Kitsune Debug"))
-    )).await?;
+    ).ephemeral(true)).await?;
     
     Ok(())
 }
 
 #[poise::command( slash_command, guild_only = true)]
 pub async fn health(ctx: Context<'_>) -> Result<(), Error> {
-    let uptime = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .unwrap()
-        .as_secs();
+    ctx.defer().await?;
+    
+    let health = &ctx.data().health;
+    let report = health.report().await;
+    
+    let (status, color) = if report.ready {
+        ("✅ Healthy", 0x2ecc71)
+    } else if report.database.ok && report.redis.ok {
+        ("⚠️ Degraded", 0xf39c12)
+    } else {
+        ("❌ Unhealthy", 0xe74c3c)
+    };
+    
+    let guild_id = ctx.guild_id().ok_or("Command must be used in a guild")?.get() as i64;
+    let guild_shard = sharding::shard_for(guild_id, report.shard_plan.total);
+    let shards = if report.shards.is_empty() {
+        "❌ No shards running".to_string()
+    } else {
+        // Disconnected shards first, so they survive the cut on large clusters.
+        let mut listed: Vec<_> = report.shards.iter().collect();
+        listed.sort_by_key(|shard| (shard.connected, shard.id));
+        
+        let mut lines: Vec<String> = listed.iter()
+            .take(MAX_LISTED_SHARDS)
+            .map(|shard| format!(
+                "{} Shard {}: {} · {} · {} guilds{}",
+                if shard.connected { "✅" } else { "⚠️" },
+                shard.id,
+                shard.stage,
+                shard.latency_ms.map(|ms| format!("{} ms", ms)).unwrap_or_else(|| "no heartbeat yet".to_string()),
+                shard.guilds,
+                if shard.id == guild_shard { " (this server)" } else { "" }
+            ))
+            .collect();
+        if listed.len() > MAX_LISTED_SHARDS {
+            let hidden = &listed[MAX_LISTED_SHARDS..];
+            let down = hidden.iter().filter(|shard| !shard.connected).count();
+            lines.push(match down {
+                0 => format!("…and {} more, all connected", hidden.len()),
+                _ => format!("…and {} more, {} not connected", hidden.len(), down),
+            });
+        }
+        
+        format!("**Running:** {}\n{}", report.shard_plan.describe(), lines.join("\n"))
+    };
+    
+    let state = match &report.memory {
+        Some(usage) => format!(
+            "**Backend:** {}\n**Message histories:** {}\n**Join event buffers:** {}\n**Honeypot catches:** {}",
+            report.state_backend,
+            usage.message_history,
+            usage.join_events,
+            usage.honeypot_catches
+        ),
+        None => format!("**Backend:** {} (shared, see Redis)", report.state_backend),
+    };
+    
+    let last_error = match &report.last_error {
+        Some(error) => format!("<t:{}:R>\n`{}`", error.at.timestamp(), error.message.replace('`', "'")),
+        None => "None since startup".to_string(),
+    };
     
     ctx.send(poise::CreateReply::default().embed(
         serenity::CreateEmbed::new()
             .title("💚 Health Status")
-            .description(format!("**Status:** ✅ Healthy\n**Uptime:** {} seconds\n**Database:** ✅ Connected\n**Memory:** Normal", uptime))
-            .color(0x2ecc71)
+            .description(format!(
+                "**Status:** {}\n**Uptime:** {} (since <t:{}:f>)",
+                status,
+                health::format_uptime(health.uptime()),
+                report.started_at.timestamp()
+            ))
+            .field("Database", describe_check(&report.database), true)
+            .field("Redis", describe_check(&report.redis), true)
+            .field("Gateway", shards, false)
+            .field("Detector State", state, false)
+            .field("Last Error", last_error, false)
+            .color(color)
             .footer(serenity::CreateEmbedFooter::new("Kitsune Guardian Fox"))
     )).await?;
     
     Ok(())
 }
+
+fn describe_check(check: &DependencyCheck) -> String {
+    match &check.error {
+        None => format!("✅ {} ms", check.latency_ms),
+        Some(error) => format!("❌ {}", error.chars().take(200).collect::<String>()),
+    }
+}
@@ -1,6 +1,10 @@
 use poise::serenity_prelude as serenity;
 use crate::database::queries;
-use crate::bot::{Context, Error};
+use crate::bot::{Context, Error, blacklist};
+use chrono::{Duration, Utc};
+
+const MAX_IMPORT_BYTES: u32 = 2 * 1024 * 1024;
+const MAX_EXPIRY_DAYS: u32 = 3650;
 
 #[poise::command( slash_command, guild_only = true, required_permissions = "ADMINISTRATOR")]
 pub async fn whitelist(
@@ -82,12 +86,52 @@ pub async fn whitelist_list(ctx: Context<'_>) -> Result<(), Error> {
 pub async fn blacklist(
     ctx: Context<'_>,
     #[description = "User ID to blacklist"] user_id: String,
-    #[description = "Reason"] _reason: Option<String>,
+    #[description = "Reason"] reason: Option<String>,
+    #[description = "Action on join: ban or kick (default ban)"] action: Option<String>,
+    #[description = "Days until the entry expires (optional)"]
+    #[max = 3650]
+    days: Option<u32>,
 ) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or("Command must be used in a guild")?;
+    let target = blacklist::parse_user_id(&user_id).ok_or("Invalid user ID")?;
+    let action = blacklist::parse_action(action.as_deref().unwrap_or("ban"))
+        .ok_or("Action must be `ban` or `kick`")?;
+    let expires_at = match days {
+        Some(d) if d > MAX_EXPIRY_DAYS => return Err(format!("Expiry is limited to {} days", MAX_EXPIRY_DAYS).into()),
+        Some(d) => Some(Utc::now().checked_add_signed(Duration::days(d.max(1) as i64)).ok_or("Expiry is out of range")?),
+        None => None,
+    };
+    let moderator_id = ctx.author().id.get() as i64;
+    
+    queries::add_to_blacklist(
+        &ctx.data().pool,
+        guild_id.get() as i64,
+        target as i64,
+        reason.as_deref(),
+        action,
+        moderator_id,
+        expires_at
+    ).await?;
+    
+    // Someone already in the server gets the same treatment they would get on join.
+    let enforced = match blacklist::enforce_on_member(ctx, guild_id, serenity::UserId::new(target), action, reason.as_deref()).await {
+        Ok(true) => format!("\n\nThe user was in the server and has been **{}**", if action == "kick" { "kicked" } else { "banned" }),
+        Ok(false) => String::new(),
+        Err(e) => format!("\n\n⚠️ The user is in the server but could not be removed: {}", e),
+    };
+    
     ctx.send(poise::CreateReply::default().embed(
         serenity::CreateEmbed::new()
             .title("🚫 User Blacklisted")
-            .description(format!("User ID {} has been blacklisted", user_id))
+            .description(format!(
+                "**User:** <@{}> ({})\n**Action on join:** {}\n**Expires:** {}\n**Reason:** {}{}",
+                target,
+                target,
+                action,
+                expires_at.map(|t| format!("<t:{}:R>", t.timestamp())).unwrap_or_else(|| "Never".to_string()),
+                reason.as_deref().unwrap_or("No reason provided"),
+                enforced
+            ))
             .color(0xe74c3c)
             .footer(serenity::CreateEmbedFooter::new("Kitsune Guardian Fox"))
     )).await?;
@@ -100,12 +144,24 @@ pub async fn blacklist_remove(
     ctx: Context<'_>,
     #[description = "User ID to remove"] user_id: String,
 ) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or("Command must be used in a guild")?.get() as i64;
+    let target: u64 = user_id.trim().parse().map_err(|_| "Invalid user ID")?;
+    
+    let removed = queries::remove_from_blacklist(&ctx.data().pool, guild_id, target as i64).await?;
+    
     ctx.send(poise::CreateReply::default().embed(
-        serenity::CreateEmbed::new()
-            .title("✅ User Removed from Blacklist")
-            .description(format!("User ID {} removed from blacklist", user_id))
-            .color(0x2ecc71)
-            .footer(serenity::CreateEmbedFooter::new("Kitsune Guardian Fox"))
+        if removed {
+            serenity::CreateEmbed::new()
+                .title("✅ User Removed from Blacklist")
+                .description(format!("User ID {} removed from blacklist", target))
+                .color(0x2ecc71)
+        } else {
+            serenity::CreateEmbed::new()
+                .title("❌ Not Blacklisted")
+                .description(format!("User ID {} is not on the blacklist", target))
+                .color(0xe74c3c)
+        }
+        .footer(serenity::CreateEmbedFooter::new("Kitsune Guardian Fox"))
     )).await?;
     
     Ok(())
@@ -113,10 +169,32 @@ pub async fn blacklist_remove(
 
 #[poise::command( slash_command, guild_only = true, required_permissions = "ADMINISTRATOR", rename = "blacklist_list")]
 pub async fn blacklist_list(ctx: Context<'_>) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or("Command must be used in a guild")?.get() as i64;
+    let entries = queries::get_blacklisted_users(&ctx.data().pool, guild_id).await?;
+    
+    let description = if entries.is_empty() {
+        "No blacklisted users".to_string()
+    } else {
+        let mut lines: Vec<String> = entries.iter()
+            .take(20)
+            .map(|entry| format!(
+                "• `{}` — {}{}{}",
+                entry.user_id,
+                entry.action,
+                entry.expires_at.map(|t| format!(", expires <t:{}:R>", t.timestamp())).unwrap_or_default(),
+                entry.reason.as_deref().map(|r| format!(": {}", r)).unwrap_or_default()
+            ))
+            .collect();
+        if entries.len() > 20 {
+            lines.push(format!("\n…and {} more. Use `/access blacklist_export` for the full list.", entries.len() - 20));
+        }
+        format!("**Blacklisted Users:** {}\n\n{}", entries.len(), lines.join("\n"))
+    };
+    
     ctx.send(poise::CreateReply::default().embed(
         serenity::CreateEmbed::new()
             .title("📋 Blacklisted Users")
-            .description("No blacklisted users")
+            .description(description)
             .color(0x3498db)
             .footer(serenity::CreateEmbedFooter::new("Kitsune Guardian Fox"))
     )).await?;
@@ -127,13 +205,69 @@ pub async fn blacklist_list(ctx: Context<'_>) -> Result<(), Error> {
 #[poise::command( slash_command, guild_only = true, required_permissions = "ADMINISTRATOR", rename = "blacklist_import")]
 pub async fn blacklist_import(
     ctx: Context<'_>,
-    #[description = "Server ID to import from"] server_id: String,
+    #[description = "JSON or CSV file exported with /access blacklist_export"] file: serenity::Attachment,
 ) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or("Command must be used in a guild")?.get() as i64;
+    let moderator_id = ctx.author().id.get() as i64;
+    
+    let format = blacklist::Format::from_filename(&file.filename)
+        .ok_or("File must have a .json or .csv extension")?;
+    if file.size > MAX_IMPORT_BYTES {
+        return Err("File is too large (max 2 MB)".into());
+    }
+    
+    ctx.defer().await?;
+    
+    let data = file.download().await?;
+    let records = blacklist::import(&data, format)?;
+    
+    let mut imported = 0;
+    let mut skipped = Vec::new();
+    
+    for (index, record) in records.iter().enumerate() {
+        let user_id = match record.user_id() {
+            Ok(id) => id,
+            Err(e) => {
+                skipped.push(format!("Entry {}: {}", index + 1, e));
+                continue;
+            }
+        };
+        let Some(action) = blacklist::parse_action(&record.action) else {
+            skipped.push(format!("Entry {}: unknown action `{}`", index + 1, record.action));
+            continue;
+        };
+        if record.expires_at.is_some_and(|t| t <= Utc::now()) {
+            skipped.push(format!("Entry {}: already expired", index + 1));
+            continue;
+        }
+        
+        queries::add_to_blacklist(
+            &ctx.data().pool,
+            guild_id,
+            user_id,
+            record.reason.as_deref(),
+            action,
+            moderator_id,
+            record.expires_at
+        ).await?;
+        imported += 1;
+    }
+    
+    let skipped_text = if skipped.is_empty() {
+        String::new()
+    } else {
+        let mut shown: Vec<String> = skipped.iter().take(10).cloned().collect();
+        if skipped.len() > 10 {
+            shown.push(format!("…and {} more", skipped.len() - 10));
+        }
+        format!("\n**Skipped:** {}\n{}", skipped.len(), shown.join("\n"))
+    };
+    
     ctx.send(poise::CreateReply::default().embed(
         serenity::CreateEmbed::new()
             .title("✅ Blacklist Imported")
-            .description(format!("Imported blacklist from server {}", server_id))
-            .color(0x2ecc71)
+            .description(format!("**Imported:** {} entries from `{}`{}", imported, file.filename, skipped_text))
+            .color(if skipped.is_empty() { 0x2ecc71 } else { 0xf39c12 })
             .footer(serenity::CreateEmbedFooter::new("Kitsune Guardian Fox"))
     )).await?;
     
@@ -141,14 +275,28 @@ pub async fn blacklist_import(
 }
 
 #[poise::command( slash_command, guild_only = true, required_permissions = "ADMINISTRATOR", rename = "blacklist_export")]
-pub async fn blacklist_export(ctx: Context<'_>) -> Result<(), Error> {
-    ctx.send(poise::CreateReply::default().embed(
-        serenity::CreateEmbed::new()
-            .title("📦 Blacklist Exported")
-            .description("Blacklist exported successfully")
-            .color(0x2ecc71)
-            .footer(serenity::CreateEmbedFooter::new("Kitsune Guardian Fox"))
-    )).await?;
+pub async fn blacklist_export(
+    ctx: Context<'_>,
+    #[description = "File format: json or csv (default json)"] format: Option<String>,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().ok_or("Command must be used in a guild")?.get() as i64;
+    let format = blacklist::Format::parse(format.as_deref().unwrap_or("json"))
+        .ok_or("Format must be `json` or `csv`")?;
+    
+    let entries = queries::get_blacklisted_users(&ctx.data().pool, guild_id).await?;
+    let data = blacklist::export(&entries, format)?;
+    let filename = format!("kitsune-blacklist-{}.{}", guild_id, format.extension());
+    
+    ctx.send(poise::CreateReply::default()
+        .embed(
+            serenity::CreateEmbed::new()
+                .title("📦 Blacklist Exported")
+                .description(format!("**Entries:** {}\n\nImport it in another server with `/access blacklist_import`.", entries.len()))
+                .color(0x2ecc71)
+                .footer(serenity::CreateEmbedFooter::new("Kitsune Guardian Fox"))
+        )
+        .attachment(serenity::CreateAttachment::bytes(data, filename))
+    ).await?;
     
     Ok(())
 }
@@ -6,8 +6,9 @@ use super::commands::whitelist::{whitelist, whitelist_remove, whitelist_list, bl
 use super::commands::testing::{test, debug, health};
 use super::commands::help::{about, docs, invite};
 use super::commands::analytics::{analytics, predict, compare};
-use super::commands::backup::{backup, backup_restore, backup_list, backup_download, backup_schedule};
+use super::commands::backup::{backup, backup_restore, backup_list, backup_download, backup_upload, backup_schedule};
 use super::commands::lockdown_cmd::lockdown_schedule;
+use super::commands::jobs::{jobs_list, jobs_cancel};
 use super::commands::custom::custom;
 use super::commands::integration::{webhook, api};
 
@@ -53,8 +54,8 @@ pub async fn insights(ctx: Context<'_>) -> Result<(), Error> {
     slash_command,
     subcommands(
         "backup", "backup_restore", "backup_list", 
-        "backup_download", "backup_schedule",
-        "lockdown_schedule",
+        "backup_download", "backup_upload", "backup_schedule",
+        "lockdown_schedule", "jobs_list", "jobs_cancel",
         "custom",
         "webhook", "api",
         "test", "debug", "health"
@@ -2,10 +2,17 @@ use poise::serenity_prelude as serenity;
 use chrono::Utc;
 use serde_json::json;
 
-use crate::database::{queries, models::ThreatLevel};
-use crate::security::{JoinEvent, threat_calculator::ThreatCalculator, auto_mod::ModAction};
+use crate::config::GuildConfig;
+use crate::database::{queries, models::{BlacklistedUser, ThreatLevel}};
+use crate::metrics::{self, metrics};
+use crate::security::{JoinEvent, auto_mod::ModAction};
+use crate::security::decisions::{self, BaitResponse, MessageVerdict};
+use crate::security::honeypot::{RecentJoin, TRAP_BAIT_ROLE, TRAP_FAKE_COMMAND, TRAP_HIDDEN_CHANNEL, TRAP_SUSPICIOUS_TIMING};
 
 use super::Data;
+use super::commands::honeypot::BAIT_BUTTON_ID;
+use super::alerts::{self, IncidentAlert};
+use super::{anti_nuke, lockdown, raid_mode, scheduler, webhooks};
 
 pub async fn event_handler(
     ctx: &serenity::Context,
@@ -20,19 +27,52 @@ pub async fn event_handler(
         serenity::FullEvent::Message { new_message } => {
             handle_message(ctx, new_message, data).await?;
         }
+        serenity::FullEvent::InteractionCreate { interaction: serenity::Interaction::Component(component) }
+            if component.data.custom_id == BAIT_BUTTON_ID =>
+        {
+            handle_bait_button(ctx, component, data).await?;
+        }
+        serenity::FullEvent::ReactionAdd { add_reaction } => {
+            handle_bait_reaction(ctx, add_reaction, data).await?;
+        }
+        serenity::FullEvent::GuildAuditLogEntryCreate { entry, guild_id } => {
+            anti_nuke::handle_audit_entry(ctx, *guild_id, entry, data).await?;
+        }
         serenity::FullEvent::GuildCreate { guild, .. } => {
-            handle_guild_create(guild, data).await?;
+            handle_guild_create(ctx, guild, data).await?;
+        }
+        serenity::FullEvent::GuildDelete { incomplete, .. } if !incomplete.unavailable => {
+            data.guild_configs.invalidate(incomplete.id.get() as i64);
         }
         _ => {}
     }
     Ok(())
 }
 
-async fn handle_guild_create(guild: &serenity::Guild, data: &Data) -> Result<(), super::Error> {
+async fn handle_guild_create(
+    ctx: &serenity::Context,
+    guild: &serenity::Guild,
+    data: &Data,
+) -> Result<(), super::Error> {
     let guild_id = guild.id.get() as i64;
     let owner_id = guild.owner_id.get() as i64;
     
     queries::upsert_guild(&data.pool, guild_id, &guild.name, owner_id).await?;
+    let guild_config = data.guild_configs.reload(&data.pool, guild_id).await?;
+    data.honeypot.reload(&data.pool, guild_id).await?;
+    
+    let lockdown_active = queries::get_guild(&data.pool, guild_id).await?
+        .map(|g| g.lockdown_active)
+        .unwrap_or(false);
+    if let Err(e) = lockdown::resume(&ctx.http, &data.pool, guild_id, lockdown_active, &guild_config).await {
+        tracing::error!("Failed to resume lockdown state for guild {}: {}", guild_id, e);
+    }
+    if let Err(e) = raid_mode::resume(&data.pool, &data.raid_modes, guild_id, lockdown_active).await {
+        tracing::error!("Failed to resume raid mode for guild {}: {}", guild_id, e);
+    }
+    if let Err(e) = scheduler::ensure_backup_job(&data.pool, guild_id, &guild_config.backup).await {
+        tracing::error!("Failed to schedule automatic backups for guild {}: {}", guild_id, e);
+    }
     
     tracing::info!("Registered guild: {} ({})", guild.name, guild_id);
     Ok(())
@@ -43,6 +83,7 @@ async fn handle_member_join(
     member: &serenity::Member,
     data: &Data,
 ) -> Result<(), super::Error> {
+    let received_at = Utc::now();
     let guild_id = member.guild_id.get() as i64;
     let user_id = member.user.id.get() as i64;
     
@@ -60,6 +101,16 @@ async fn handle_member_join(
         return Ok(());
     }
     
+    let joined_at = member.joined_at.map(|t| t.to_utc()).unwrap_or(received_at);
+    data.honeypot.record_join(guild_id, user_id, RecentJoin { joined_at, received_at }).await?;
+    
+    let guild_config = data.guild_configs.get(&data.pool, guild_id).await?;
+    
+    if let Some(entry) = queries::get_blacklist_entry(&data.pool, guild_id, user_id).await? {
+        enforce_blacklist(ctx, member, &entry, &guild_config, data).await?;
+        return Ok(());
+    }
+    
     let join_event = JoinEvent {
         user_id,
         username: member.user.name.clone(),
@@ -69,9 +120,27 @@ async fn handle_member_join(
         avatar_hash: member.user.avatar.as_ref().map(|a| a.to_string()),
     };
     
-    data.raid_detector.record_join(guild_id, join_event.clone());
+    data.raid_detector.record_join(guild_id, join_event.clone()).await?;
+    
+    let raid_analysis = data.raid_detector.analyze_raid_risk(guild_id, &guild_config).await?;
     
-    let raid_analysis = data.raid_detector.analyze_raid_risk(guild_id);
+    if guild_config.security.enabled {
+        if let Some(transition) = data.raid_modes.observe(guild_id, &raid_analysis, Utc::now()) {
+            if let Err(e) = raid_mode::apply_transition(&ctx.http, &data.pool, &data.guild_configs, &data.raid_modes, guild_id, transition).await {
+                tracing::error!("Failed to apply raid mode transition for guild {}: {}", guild_id, e);
+            }
+        }
+    }
+    
+    let account_age_days = (Utc::now() - join_event.account_created).num_days();
+    let join_metadata = if guild_config.forensics.detailed_logging {
+        json!({
+            "username": join_event.username,
+            "account_age_days": account_age_days,
+        })
+    } else {
+        json!({"account_age_days": account_age_days})
+    };
     
     queries::log_forensic_event(
         &data.pool,
@@ -79,27 +148,18 @@ async fn handle_member_join(
         Some(user_id),
         "member_join",
         None,
-        json!({
-            "username": join_event.username,
-            "account_age_days": (Utc::now() - join_event.account_created).num_days(),
-        }),
+        join_metadata,
         raid_analysis.threat_score,
         vec!["join".to_string()]
     ).await?;
     
-    if raid_analysis.is_raid {
-        let threat_level = ThreatLevel::from_score(raid_analysis.threat_score);
+    if decisions::join_is_incident(&guild_config, &raid_analysis) {
+        let threat_level = data.auto_mod.threat_level(&guild_config, raid_analysis.threat_score);
         
-        let action = data.auto_mod.determine_action(raid_analysis.threat_score, threat_level);
-        let action_name = action.as_ref().map(|a| match a {
-            ModAction::Monitor => "monitor",
-            ModAction::Timeout { .. } => "timeout",
-            ModAction::Kick { .. } => "kick",
-            ModAction::Ban { .. } => "ban",
-            ModAction::Lockdown => "lockdown",
-        });
+        let action = data.auto_mod.determine_action(&guild_config, raid_analysis.threat_score);
+        let action_name = action.as_ref().map(ModAction::name);
         
-        queries::create_incident(
+        let incident = queries::create_incident(
             &data.pool,
             guild_id,
             user_id,
@@ -116,15 +176,179 @@ async fn handle_member_join(
             }),
             action_name
         ).await?;
+        webhooks::emit_incident(&data.pool, &incident).await;
+        
+        alerts::dispatch_incident(&ctx.http, &guild_config, IncidentAlert {
+            incident: &incident,
+            threat_level,
+            breakdown: vec![
+                ("Joins (5s / 30s / 1m)", format!("{} / {} / {}", raid_analysis.join_rate_5s, raid_analysis.join_rate_30s, raid_analysis.join_rate_1m)),
+                ("New Accounts", format!("{:.0}%", raid_analysis.new_account_ratio * 100.0)),
+                ("Username Similarity", format!("{:.2}", raid_analysis.username_similarity)),
+                ("Duplicate Avatars", format!("{:.0}%", raid_analysis.avatar_duplication * 100.0)),
+            ],
+            reasons: raid_analysis.reasons.clone(),
+            message_link: None,
+        }).await;
         
         if let Some(action) = action {
-            execute_mod_action(ctx, guild_id, user_id, action, data).await?;
+            execute_mod_action(ctx, guild_id, user_id, action, &guild_config, data).await?;
         }
     }
     
     Ok(())
 }
 
+async fn enforce_blacklist(
+    ctx: &serenity::Context,
+    member: &serenity::Member,
+    entry: &BlacklistedUser,
+    guild_config: &GuildConfig,
+    data: &Data,
+) -> Result<(), super::Error> {
+    let guild_id = member.guild_id.get() as i64;
+    let user_id = member.user.id.get() as i64;
+    let reason = format!("Kitsune blacklist: {}", entry.reason.as_deref().unwrap_or("no reason provided"));
+    
+    tracing::warn!("Blacklisted user {} joined guild {}, applying {}", user_id, guild_id, entry.action);
+    
+    let result = if entry.action == "kick" {
+        member.guild_id.kick_with_reason(ctx, member.user.id, &reason).await
+    } else {
+        member.guild_id.ban_with_reason(ctx, member.user.id, 0, &reason).await
+    };
+    if let Err(e) = &result {
+        tracing::warn!("Failed to {} blacklisted user {} in guild {}: {}", entry.action, user_id, guild_id, e);
+        metrics::discord_failure(&format!("blacklist_{}", entry.action));
+    }
+    
+    let incident = queries::create_incident(
+        &data.pool,
+        guild_id,
+        user_id,
+        "blacklist_match",
+        ThreatLevel::Critical.as_str(),
+        1.0,
+        json!({
+            "username": member.user.name,
+            "reason": entry.reason,
+            "added_by": entry.added_by,
+            "blacklisted_at": entry.created_at,
+            "enforced": result.is_ok(),
+        }),
+        result.is_ok().then_some(entry.action.as_str())
+    ).await?;
+    webhooks::emit_incident(&data.pool, &incident).await;
+    
+    alerts::dispatch_incident(&ctx.http, guild_config, IncidentAlert {
+        incident: &incident,
+        threat_level: ThreatLevel::Critical,
+        breakdown: vec![
+            ("Added By", format!("<@{}>", entry.added_by)),
+            ("Blacklisted", format!("<t:{}:R>", entry.created_at.timestamp())),
+        ],
+        reasons: entry.reason.iter().cloned().collect(),
+        message_link: None,
+    }).await;
+    
+    Ok(())
+}
+
+async fn handle_bait_button(
+    ctx: &serenity::Context,
+    component: &serenity::ComponentInteraction,
+    data: &Data,
+) -> Result<(), super::Error> {
+    component.create_response(ctx, serenity::CreateInteractionResponse::Message(
+        serenity::CreateInteractionResponseMessage::new()
+            .content("✅ Thanks!")
+            .ephemeral(true)
+    )).await?;
+    
+    let Some(guild_id) = component.guild_id else {
+        return Ok(());
+    };
+    
+    // The interaction snowflake is stamped by Discord, so it is compared with
+    // Discord's join time and gateway latency doesn't inflate the delay.
+    let responded_at = component.id.created_at().to_utc();
+    record_bait_response(guild_id.get() as i64, component.user.id.get() as i64, BaitResponse::Button(responded_at), data).await
+}
+
+async fn handle_bait_reaction(
+    ctx: &serenity::Context,
+    reaction: &serenity::Reaction,
+    data: &Data,
+) -> Result<(), super::Error> {
+    let (Some(guild_id), Some(user_id)) = (reaction.guild_id, reaction.user_id) else {
+        return Ok(());
+    };
+    if user_id == ctx.cache.current_user().id {
+        return Ok(());
+    }
+    
+    let guild_id = guild_id.get() as i64;
+    if !data.honeypot.is_bait_message(guild_id, reaction.message_id.get() as i64) {
+        return Ok(());
+    }
+    
+    // Reactions carry no timestamp, so the delay is measured between the two
+    // gateway events as received, which share the same latency.
+    record_bait_response(guild_id, user_id.get() as i64, BaitResponse::Reaction(Utc::now()), data).await
+}
+
+/// Times the member's first answer to a bait message from their join. Later
+/// answers find no join and are ignored.
+async fn record_bait_response(
+    guild_id: i64,
+    user_id: i64,
+    response: BaitResponse,
+    data: &Data,
+) -> Result<(), super::Error> {
+    let Some(join) = data.honeypot.take_recent_join(guild_id, user_id).await? else {
+        return Ok(());
+    };
+    
+    let source = response.source();
+    let reaction_time_ms = response.delay_ms(&join);
+    
+    if data.honeypot.check_suspicious_timing(guild_id, user_id, reaction_time_ms).await? {
+        tracing::warn!("User {} in guild {} answered the bait {} {}ms after joining", user_id, guild_id, source, reaction_time_ms);
+        
+        record_honeypot_catch(
+            data,
+            guild_id,
+            user_id,
+            TRAP_SUSPICIOUS_TIMING,
+            &format!("{}_{}ms", source, reaction_time_ms),
+            json!({"source": source, "reaction_time_ms": reaction_time_ms, "joined_at": join.joined_at})
+        ).await?;
+    }
+    
+    Ok(())
+}
+
+/// Stores a honeypot catch and forwards it to the guild's webhooks.
+async fn record_honeypot_catch(
+    data: &Data,
+    guild_id: i64,
+    user_id: i64,
+    trap_type: &str,
+    trap_name: &str,
+    metadata: serde_json::Value,
+) -> Result<(), super::Error> {
+    queries::record_honeypot_catch(&data.pool, guild_id, user_id, trap_type, trap_name, metadata.clone()).await?;
+    
+    webhooks::emit(&data.pool, guild_id, webhooks::HONEYPOT_CAUGHT, json!({
+        "user_id": user_id.to_string(),
+        "trap_type": trap_type,
+        "trap_name": trap_name,
+        "metadata": metadata,
+    })).await;
+    
+    Ok(())
+}
+
 async fn handle_message(
     ctx: &serenity::Context,
     message: &serenity::Message,
@@ -147,36 +371,61 @@ async fn handle_message(
         return Ok(());
     }
     
-    if data.honeypot.check_hidden_channel(guild_id, channel_id, user_id) {
-        queries::record_honeypot_catch(
-            &data.pool,
+    let guild_config = data.guild_configs.get(&data.pool, guild_id).await?;
+    let detailed_logging = guild_config.forensics.detailed_logging;
+    
+    if data.honeypot.check_hidden_channel(guild_id, channel_id, user_id).await? {
+        record_honeypot_catch(
+            data,
             guild_id,
             user_id,
-            "hidden_channel",
+            TRAP_HIDDEN_CHANNEL,
             &format!("channel_{}", channel_id),
             json!({"channel_id": channel_id})
         ).await?;
     }
     
-    if data.honeypot.check_fake_command(guild_id, &message.content, user_id) {
-        queries::record_honeypot_catch(
-            &data.pool,
+    let mentioned_roles: Vec<i64> = message.mention_roles.iter().map(|r| r.get() as i64).collect();
+    if let Some(role_id) = data.honeypot.check_bait_role(guild_id, &mentioned_roles, user_id).await? {
+        record_honeypot_catch(
+            data,
             guild_id,
             user_id,
-            "fake_command",
-            &message.content,
-            json!({"content": message.content})
+            TRAP_BAIT_ROLE,
+            &format!("role_{}", role_id),
+            json!({"role_id": role_id, "channel_id": channel_id})
         ).await?;
     }
     
+    if data.honeypot.check_fake_command(guild_id, &message.content, user_id).await? {
+        let (trap_name, metadata) = if detailed_logging {
+            (message.content.as_str(), json!({"content": message.content}))
+        } else {
+            (TRAP_FAKE_COMMAND, json!({"channel_id": channel_id}))
+        };
+        record_honeypot_catch(
+            data,
+            guild_id,
+            user_id,
+            TRAP_FAKE_COMMAND,
+            trap_name,
+            metadata
+        ).await?;
+    }
+    
+    if !guild_config.behavior.enabled {
+        return Ok(());
+    }
+    
     let message_analysis = data.behavior_analyzer.analyze_message(
         guild_id,
         user_id,
         &message.content,
-        channel_id
-    );
+        channel_id,
+        &guild_config
+    ).await?;
     
-    let behavioral_metrics = data.behavior_analyzer.get_behavioral_metrics(guild_id, user_id);
+    let behavioral_metrics = data.behavior_analyzer.get_behavioral_metrics(guild_id, user_id, &guild_config).await?;
     
     queries::update_behavior_profile(
         &data.pool,
@@ -192,27 +441,17 @@ async fn handle_message(
         })
     ).await?;
     
-    let honeypot_multiplier = data.honeypot.get_threat_multiplier(guild_id, user_id);
+    let honeypot_multiplier = data.honeypot.get_threat_multiplier(guild_id, user_id).await?;
     let account_age = Utc::now() - message.author.id.created_at().to_utc();
-    let is_new_account = account_age.num_days() < data.config.security.new_account_days as i64;
     
-    let raid_analysis = data.raid_detector.analyze_raid_risk(guild_id);
+    let raid_analysis = data.raid_detector.analyze_raid_risk(guild_id, &guild_config).await?;
     
-    let combined_threat = ThreatCalculator::calculate_combined_threat(
-        &raid_analysis,
-        &behavioral_metrics,
-        honeypot_multiplier,
-        is_new_account
-    );
+    let verdict = MessageVerdict::new(&guild_config, &raid_analysis, &behavioral_metrics, honeypot_multiplier, account_age);
+    let combined_threat = verdict.score;
     
-    if combined_threat > data.config.auto_mod.low_threat_threshold {
-        queries::log_forensic_event(
-            &data.pool,
-            guild_id,
-            Some(user_id),
-            "message",
-            Some(&message.content),
-            json!({
+    if verdict.is_logged(&guild_config) {
+        let (content, metadata) = if detailed_logging {
+            (Some(message.content.as_str()), json!({
                 "channel_id": channel_id,
                 "threat_score": combined_threat,
                 "spam_score": behavioral_metrics.spam_score,
@@ -220,15 +459,29 @@ async fn handle_message(
                 "burst_detected": behavioral_metrics.burst_detected,
                 "has_links": message_analysis.has_links,
                 "mention_count": message_analysis.mention_count,
-            }),
+            }))
+        } else {
+            (None, json!({
+                "channel_id": channel_id,
+                "threat_score": combined_threat,
+            }))
+        };
+        
+        queries::log_forensic_event(
+            &data.pool,
+            guild_id,
+            Some(user_id),
+            "message",
+            content,
+            metadata,
             combined_threat,
             vec!["message".to_string(), "threat".to_string()]
         ).await?;
         
-        let threat_level = ThreatLevel::from_score(combined_threat);
+        let threat_level = data.auto_mod.threat_level(&guild_config, combined_threat);
         
-        if combined_threat >= data.config.auto_mod.medium_threat_threshold {
-            let honeypot_catches = data.honeypot.get_user_catches(guild_id, user_id);
+        if verdict.is_incident(&guild_config) {
+            let honeypot_catches = data.honeypot.get_user_catches(guild_id, user_id).await?;
             let trap_details: Vec<_> = honeypot_catches.iter()
                 .map(|c| json!({
                     "trap_type": c.trap_type,
@@ -237,16 +490,10 @@ async fn handle_message(
                 }))
                 .collect();
             
-            let action = data.auto_mod.determine_action(combined_threat, threat_level);
-            let action_name = action.as_ref().map(|a| match a {
-                ModAction::Monitor => "monitor",
-                ModAction::Timeout { .. } => "timeout",
-                ModAction::Kick { .. } => "kick",
-                ModAction::Ban { .. } => "ban",
-                ModAction::Lockdown => "lockdown",
-            });
+            let action = data.auto_mod.determine_action(&guild_config, combined_threat);
+            let action_name = action.as_ref().map(ModAction::name);
             
-            queries::create_incident(
+            let incident = queries::create_incident(
                 &data.pool,
                 guild_id,
                 user_id,
@@ -261,9 +508,27 @@ async fn handle_message(
                 }),
                 action_name
             ).await?;
+            webhooks::emit_incident(&data.pool, &incident).await;
+            
+            let reasons = verdict.reasons(&behavioral_metrics, &honeypot_catches, &raid_analysis);
+            
+            alerts::dispatch_incident(&ctx.http, &guild_config, IncidentAlert {
+                incident: &incident,
+                threat_level,
+                breakdown: vec![
+                    ("Behavior", format!("{:.2}", behavioral_metrics.threat_score)),
+                    ("Raid", format!("{:.2}", raid_analysis.threat_score)),
+                    ("Honeypot", format!("{:.2}", honeypot_multiplier)),
+                    ("Spam Score", format!("{:.2}", behavioral_metrics.spam_score)),
+                    ("Link Density", format!("{:.2}", behavioral_metrics.link_density)),
+                    ("Mention Ratio", format!("{:.2}", behavioral_metrics.mention_ratio)),
+                ],
+                reasons,
+                message_link: Some(message.link()),
+            }).await;
             
             if let Some(action) = action {
-                execute_mod_action(ctx, guild_id, user_id, action, data).await?;
+                execute_mod_action(ctx, guild_id, user_id, action, &guild_config, data).await?;
             }
         }
     }
@@ -276,11 +541,14 @@ async fn execute_mod_action(
     guild_id: i64,
     user_id: i64,
     action: ModAction,
+    guild_config: &GuildConfig,
     data: &Data,
 ) -> Result<(), super::Error> {
     let guild_id_u64 = serenity::GuildId::new(guild_id as u64);
     let user_id_u64 = serenity::UserId::new(user_id as u64);
     
+    metrics().mod_actions.with_label_values(&[action.name()]).inc();
+    
     match action {
         ModAction::Monitor => {
             tracing::info!("Monitoring user {} in guild {}", user_id, guild_id);
@@ -294,32 +562,47 @@ async fn execute_mod_action(
                 ).ok();
                 
                 if let Some(timestamp) = until {
-                    let _ = member.disable_communication_until_datetime(ctx, timestamp).await;
+                    if let Err(e) = member.disable_communication_until_datetime(ctx, timestamp).await {
+                        tracing::warn!("Failed to time out user {} in guild {}: {}", user_id, guild_id, e);
+                        metrics::discord_failure("timeout");
+                    }
                 }
             }
         }
         ModAction::Kick { reason } => {
             tracing::info!("Kicking user {} from guild {}: {}", user_id, guild_id, reason);
             
-            let _ = guild_id_u64.kick_with_reason(ctx, user_id_u64, &reason).await;
+            if let Err(e) = guild_id_u64.kick_with_reason(ctx, user_id_u64, &reason).await {
+                tracing::warn!("Failed to kick user {} from guild {}: {}", user_id, guild_id, e);
+                metrics::discord_failure("kick");
+            }
         }
         ModAction::Ban { reason, delete_days } => {
             tracing::info!("Banning user {} from guild {}: {}", user_id, guild_id, reason);
             
-            let _ = guild_id_u64.ban_with_reason(ctx, user_id_u64, delete_days, &reason).await;
+            if let Err(e) = guild_id_u64.ban_with_reason(ctx, user_id_u64, delete_days, &reason).await {
+                tracing::warn!("Failed to ban user {} from guild {}: {}", user_id, guild_id, e);
+                metrics::discord_failure("ban");
+            }
             
             let recent_bans = queries::count_recent_bans(&data.pool, guild_id, 60).await.unwrap_or(0);
-            let raid_analysis = data.raid_detector.analyze_raid_risk(guild_id);
+            let raid_analysis = data.raid_detector.analyze_raid_risk(guild_id, guild_config).await?;
             
-            if data.auto_mod.should_lockdown(raid_analysis.threat_score, recent_bans) {
+            if data.auto_mod.should_lockdown(guild_config, raid_analysis.threat_score, recent_bans) {
                 tracing::warn!("Auto-lockdown triggered for guild {} - {} recent bans, threat score: {}", 
                     guild_id, recent_bans, raid_analysis.threat_score);
-                queries::set_lockdown(&data.pool, guild_id, true).await?;
+                let reason = format!("{} bans in the last hour", recent_bans);
+                if let Some(transition) = data.raid_modes.escalate(guild_id, raid_analysis.threat_score, reason, Utc::now()) {
+                    raid_mode::apply_transition(&ctx.http, &data.pool, &data.guild_configs, &data.raid_modes, guild_id, transition).await?;
+                }
             }
         }
         ModAction::Lockdown => {
             tracing::warn!("Lockdown triggered for guild {}", guild_id);
-            queries::set_lockdown(&data.pool, guild_id, true).await?;
+            let threat_score = data.raid_detector.analyze_raid_risk(guild_id, guild_config).await?.threat_score;
+            if let Some(transition) = data.raid_modes.escalate(guild_id, threat_score, "Critical threat detected".to_string(), Utc::now()) {
+                raid_mode::apply_transition(&ctx.http, &data.pool, &data.guild_configs, &data.raid_modes, guild_id, transition).await?;
+            }
         }
     }
     
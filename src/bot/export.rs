@@ -0,0 +1,479 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde_json::{json, Value};
+use sqlx::PgPool;
+use std::collections::VecDeque;
+use uuid::Uuid;
+
+use crate::database::{
+    models::{BehaviorProfile, ForensicEvent, HoneypotCatch, Incident},
+    queries,
+};
+use crate::security::honeypot::TRAP_FAKE_COMMAND;
+
+const PAGE_SIZE: i64 = 500;
+/// Leaves headroom under Discord's attachment limit.
+pub const MAX_PART_BYTES: usize = 8 * 1024 * 1024;
+pub const MAX_PARTS: usize = 20;
+pub const REDACTED: &str = "[redacted]";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dataset {
+    Incidents,
+    ForensicEvents,
+    HoneypotCatches,
+    BehaviorProfiles,
+}
+
+impl Dataset {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "incidents" => Some(Dataset::Incidents),
+            "forensics" | "forensic_events" => Some(Dataset::ForensicEvents),
+            "honeypot" | "honeypot_catches" => Some(Dataset::HoneypotCatches),
+            "profiles" | "behavior_profiles" => Some(Dataset::BehaviorProfiles),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Dataset::Incidents => "incidents",
+            Dataset::ForensicEvents => "forensic_events",
+            Dataset::HoneypotCatches => "honeypot_catches",
+            Dataset::BehaviorProfiles => "behavior_profiles",
+        }
+    }
+
+    /// CSV column order. JSON blobs come last since they are the widest.
+    fn columns(&self) -> &'static [&'static str] {
+        match self {
+            Dataset::Incidents => &[
+                "id", "user_id", "incident_type", "severity", "threat_score", "action_taken",
+                "moderator_id", "resolved", "created_at", "evidence",
+            ],
+            Dataset::ForensicEvents => &[
+                "id", "user_id", "event_type", "threat_score", "tags", "related_events",
+                "created_at", "content", "metadata",
+            ],
+            Dataset::HoneypotCatches => &[
+                "id", "user_id", "trap_type", "trap_name", "created_at", "metadata",
+            ],
+            Dataset::BehaviorProfiles => &[
+                "id", "user_id", "message_count", "join_timestamp", "last_message_time",
+                "spam_score", "link_density", "mention_ratio", "caps_ratio", "emoji_density",
+                "channel_diversity", "reply_ratio", "unique_interactions", "threat_score",
+                "created_at", "updated_at", "features",
+            ],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Json,
+    Csv,
+    Ndjson,
+}
+
+impl Format {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "json" => Some(Format::Json),
+            "csv" => Some(Format::Csv),
+            "ndjson" | "jsonl" => Some(Format::Ndjson),
+            _ => None,
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Format::Json => "json",
+            Format::Csv => "csv",
+            Format::Ndjson => "ndjson",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ExportRequest {
+    pub guild_id: i64,
+    pub dataset: Dataset,
+    pub format: Format,
+    pub since: DateTime<Utc>,
+    pub until: DateTime<Utc>,
+    pub redact: bool,
+}
+
+pub struct Part {
+    pub index: usize,
+    pub filename: String,
+    pub data: Vec<u8>,
+    pub records: usize,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Cursor {
+    Uuid(DateTime<Utc>, Uuid),
+    Serial(DateTime<Utc>, i32),
+}
+
+impl Cursor {
+    fn uuid(self) -> Option<(DateTime<Utc>, Uuid)> {
+        match self {
+            Cursor::Uuid(time, id) => Some((time, id)),
+            Cursor::Serial(..) => None,
+        }
+    }
+
+    fn serial(self) -> Option<(DateTime<Utc>, i32)> {
+        match self {
+            Cursor::Serial(time, id) => Some((time, id)),
+            Cursor::Uuid(..) => None,
+        }
+    }
+}
+
+/// Pages through a dataset and cuts it into attachment-sized files, so only
+/// one page and one file are ever held in memory.
+pub struct Export<'a> {
+    pool: &'a PgPool,
+    request: ExportRequest,
+    cursor: Option<Cursor>,
+    exhausted: bool,
+    /// Encoded records fetched but not yet written, with their timestamps.
+    pending: VecDeque<(DateTime<Utc>, Vec<u8>)>,
+    parts: usize,
+    records: usize,
+    last_written: Option<DateTime<Utc>>,
+}
+
+impl<'a> Export<'a> {
+    pub fn new(pool: &'a PgPool, request: ExportRequest) -> Self {
+        Self {
+            pool,
+            request,
+            cursor: None,
+            exhausted: false,
+            pending: VecDeque::new(),
+            parts: 0,
+            records: 0,
+            last_written: None,
+        }
+    }
+
+    pub fn records(&self) -> usize {
+        self.records
+    }
+
+    pub fn parts(&self) -> usize {
+        self.parts
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.exhausted && self.pending.is_empty()
+    }
+
+    /// Timestamp of the last exported record, to resume a truncated export.
+    pub fn last_written(&self) -> Option<DateTime<Utc>> {
+        self.last_written
+    }
+
+    pub async fn next_part(&mut self) -> Result<Option<Part>> {
+        let mut writer = PartWriter::new(self.request.format, self.request.dataset);
+
+        loop {
+            while let Some((time, encoded)) = self.pending.pop_front() {
+                if !writer.fits(encoded.len()) {
+                    self.pending.push_front((time, encoded));
+                    return Ok(Some(self.finish_part(writer)));
+                }
+                writer.push(&encoded);
+                self.last_written = Some(time);
+            }
+
+            if self.exhausted {
+                break;
+            }
+            self.fetch_page().await?;
+        }
+
+        if writer.records == 0 {
+            return Ok(None);
+        }
+        Ok(Some(self.finish_part(writer)))
+    }
+
+    fn finish_part(&mut self, writer: PartWriter) -> Part {
+        self.parts += 1;
+        self.records += writer.records;
+
+        let request = &self.request;
+        Part {
+            index: self.parts,
+            filename: format!(
+                "kitsune-{}-{}-{}-{}-{:02}.{}",
+                request.dataset.name(),
+                request.guild_id,
+                request.since.format("%Y%m%d"),
+                request.until.format("%Y%m%d"),
+                self.parts,
+                request.format.extension()
+            ),
+            records: writer.records,
+            data: writer.finish(),
+        }
+    }
+
+    async fn fetch_page(&mut self) -> Result<()> {
+        let ExportRequest { guild_id, since, until, .. } = self.request;
+        let after = self.cursor;
+
+        let rows: Vec<(DateTime<Utc>, Value)> = match self.request.dataset {
+            Dataset::Incidents => {
+                let rows = queries::export_incidents(self.pool, guild_id, since, until, after.and_then(Cursor::uuid), PAGE_SIZE).await?;
+                self.cursor = rows.last().map(|row| Cursor::Uuid(row.created_at, row.id)).or(self.cursor);
+                rows.into_iter().map(|row| (row.created_at, incident_record(row))).collect()
+            }
+            Dataset::ForensicEvents => {
+                let rows = queries::export_forensic_events(self.pool, guild_id, since, until, after.and_then(Cursor::uuid), PAGE_SIZE).await?;
+                self.cursor = rows.last().map(|row| Cursor::Uuid(row.created_at, row.id)).or(self.cursor);
+                rows.into_iter().map(|row| (row.created_at, forensic_record(row))).collect()
+            }
+            Dataset::HoneypotCatches => {
+                let rows = queries::export_honeypot_catches(self.pool, guild_id, since, until, after.and_then(Cursor::uuid), PAGE_SIZE).await?;
+                self.cursor = rows.last().map(|row| Cursor::Uuid(row.created_at, row.id)).or(self.cursor);
+                rows.into_iter().map(|row| (row.created_at, honeypot_record(row))).collect()
+            }
+            Dataset::BehaviorProfiles => {
+                let rows = queries::export_behavior_profiles(self.pool, guild_id, since, until, after.and_then(Cursor::serial), PAGE_SIZE).await?;
+                self.cursor = rows.last().map(|row| Cursor::Serial(row.updated_at, row.id)).or(self.cursor);
+                rows.into_iter().map(|row| (row.updated_at, profile_record(row))).collect()
+            }
+        };
+
+        self.exhausted = (rows.len() as i64) < PAGE_SIZE;
+
+        for (time, mut record) in rows {
+            if self.request.redact {
+                redact(self.request.dataset, &mut record);
+            }
+            self.pending.push_back((time, encode(self.request.format, self.request.dataset, &record)));
+        }
+
+        Ok(())
+    }
+}
+
+/// Builds one file: a JSON array, a CSV file with its own header, or plain
+/// NDJSON lines.
+struct PartWriter {
+    format: Format,
+    data: Vec<u8>,
+    records: usize,
+}
+
+impl PartWriter {
+    fn new(format: Format, dataset: Dataset) -> Self {
+        let data = match format {
+            Format::Json => b"[\n".to_vec(),
+            Format::Csv => format!("{}\n", dataset.columns().join(",")).into_bytes(),
+            Format::Ndjson => Vec::new(),
+        };
+        Self { format, data, records: 0 }
+    }
+
+    /// A part always takes at least one record, however large.
+    fn fits(&self, len: usize) -> bool {
+        self.records == 0 || self.data.len() + len + 4 <= MAX_PART_BYTES
+    }
+
+    fn push(&mut self, encoded: &[u8]) {
+        if self.format == Format::Json && self.records > 0 {
+            self.data.extend_from_slice(b",\n");
+        }
+        self.data.extend_from_slice(encoded);
+        self.records += 1;
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.format == Format::Json {
+            self.data.extend_from_slice(b"\n]\n");
+        }
+        self.data
+    }
+}
+
+fn encode(format: Format, dataset: Dataset, record: &Value) -> Vec<u8> {
+    match format {
+        Format::Json => serde_json::to_vec(record).unwrap_or_default(),
+        Format::Ndjson => {
+            let mut line = serde_json::to_vec(record).unwrap_or_default();
+            line.push(b'\n');
+            line
+        }
+        Format::Csv => {
+            let fields: Vec<String> = dataset.columns().iter()
+                .map(|column| match record.get(column) {
+                    None | Some(Value::Null) => String::new(),
+                    Some(Value::String(text)) => csv_field(&defuse_formula(text)),
+                    Some(other) => csv_field(&other.to_string()),
+                })
+                .collect();
+            format!("{}\n", fields.join(",")).into_bytes()
+        }
+    }
+}
+
+/// Quotes a CSV field when needed. Unlike the blacklist export, newlines are
+/// kept: message content in an evidence export must come out unchanged.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Text written by members can start like a spreadsheet formula. A leading
+/// `'` makes spreadsheets show it as text instead of running it.
+fn defuse_formula(text: &str) -> std::borrow::Cow<'_, str> {
+    if text.starts_with(['=', '+', '-', '@', '\t', '\r']) {
+        format!("'{}", text).into()
+    } else {
+        text.into()
+    }
+}
+
+/// Blanks message content: the `content` column, the fake command text a
+/// honeypot caught, and any `content` key inside JSON evidence.
+fn redact(dataset: Dataset, record: &mut Value) {
+    match dataset {
+        Dataset::ForensicEvents => {
+            if record["content"].is_string() {
+                record["content"] = json!(REDACTED);
+            }
+        }
+        Dataset::HoneypotCatches => {
+            if record["trap_type"] == TRAP_FAKE_COMMAND {
+                record["trap_name"] = json!(REDACTED);
+            }
+        }
+        Dataset::Incidents | Dataset::BehaviorProfiles => {}
+    }
+
+    for blob in ["evidence", "metadata", "features"] {
+        if let Some(value) = record.get_mut(blob) {
+            redact_content_keys(value);
+        }
+    }
+}
+
+fn redact_content_keys(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (key, inner) in map.iter_mut() {
+                if key == "content" && inner.is_string() {
+                    *inner = json!(REDACTED);
+                } else {
+                    redact_content_keys(inner);
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(redact_content_keys),
+        _ => {}
+    }
+}
+
+// IDs are written as strings so JSON consumers don't lose precision on
+// snowflakes.
+
+fn incident_record(incident: Incident) -> Value {
+    json!({
+        "id": incident.id,
+        "user_id": incident.user_id.to_string(),
+        "incident_type": incident.incident_type,
+        "severity": incident.severity,
+        "threat_score": incident.threat_score,
+        "action_taken": incident.action_taken,
+        "moderator_id": incident.moderator_id.map(|id| id.to_string()),
+        "resolved": incident.resolved,
+        "created_at": incident.created_at,
+        "evidence": incident.evidence,
+    })
+}
+
+fn forensic_record(event: ForensicEvent) -> Value {
+    json!({
+        "id": event.id,
+        "user_id": event.user_id.map(|id| id.to_string()),
+        "event_type": event.event_type,
+        "threat_score": event.threat_score,
+        "tags": event.tags.unwrap_or_default(),
+        "related_events": event.related_events.unwrap_or_default(),
+        "created_at": event.created_at,
+        "content": event.content,
+        "metadata": event.metadata,
+    })
+}
+
+fn honeypot_record(catch: HoneypotCatch) -> Value {
+    json!({
+        "id": catch.id,
+        "user_id": catch.user_id.to_string(),
+        "trap_type": catch.trap_type,
+        "trap_name": catch.trap_name,
+        "created_at": catch.created_at,
+        "metadata": catch.metadata,
+    })
+}
+
+fn profile_record(profile: BehaviorProfile) -> Value {
+    json!({
+        "id": profile.id,
+        "user_id": profile.user_id.to_string(),
+        "message_count": profile.message_count,
+        "join_timestamp": profile.join_timestamp,
+        "last_message_time": profile.last_message_time,
+        "spam_score": profile.spam_score,
+        "link_density": profile.link_density,
+        "mention_ratio": profile.mention_ratio,
+        "caps_ratio": profile.caps_ratio,
+        "emoji_density": profile.emoji_density,
+        "channel_diversity": profile.channel_diversity,
+        "reply_ratio": profile.reply_ratio,
+        "unique_interactions": profile.unique_interactions,
+        "threat_score": profile.threat_score,
+        "created_at": profile.created_at,
+        "updated_at": profile.updated_at,
+        "features": profile.features,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_keeps_newlines_and_quotes() {
+        assert_eq!(csv_field("line one\nline two"), "\"line one\nline two\"");
+        assert_eq!(csv_field(r#"say "hi", then leave"#), r#""say ""hi"", then leave""#);
+        assert_eq!(csv_field("plain"), "plain");
+    }
+
+    #[test]
+    fn csv_defuses_formulas() {
+        for text in ["=HYPERLINK(\"http://evil\")", "+1", "-2+3", "@SUM(A1)"] {
+            assert_eq!(defuse_formula(text), format!("'{}", text));
+        }
+        assert_eq!(defuse_formula("hello = world"), "hello = world");
+    }
+
+    #[test]
+    fn csv_only_defuses_text() {
+        let record = json!({"user_id": "42", "threat_score": -0.5, "content": "=1+1\nsecond line"});
+        let line = String::from_utf8(encode(Format::Csv, Dataset::ForensicEvents, &record)).unwrap();
+        assert!(line.contains("-0.5"));
+        assert!(line.contains("\"'=1+1\nsecond line\""));
+    }
+}
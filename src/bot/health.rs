@@ -0,0 +1,244 @@
+use anyhow::{Context, Result};
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::routing::get;
+use axum::{Json, Router};
+use chrono::{DateTime, Utc};
+use poise::serenity_prelude as serenity;
+use redis::aio::ConnectionManager;
+use serde::Serialize;
+use serde_json::json;
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use super::sharding::{self, ShardPlan};
+use crate::config::HealthConfig;
+use crate::database::queries;
+use crate::security::state::{StateBackend, StateUsage};
+use crate::utils::redis_client;
+
+const MAX_ERROR_LEN: usize = 500;
+
+/// Checks the bot's dependencies on demand, for `/admin health` and the
+/// orchestrator probes.
+pub struct HealthMonitor {
+    pool: PgPool,
+    redis: ConnectionManager,
+    check_timeout: Duration,
+    started: Instant,
+    started_at: DateTime<Utc>,
+    shard_manager: OnceLock<Arc<serenity::ShardManager>>,
+    cache: OnceLock<Arc<serenity::Cache>>,
+    shard_plan: ShardPlan,
+    state: Arc<dyn StateBackend>,
+    last_error: Mutex<Option<LastError>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HealthReport {
+    pub ready: bool,
+    pub started_at: DateTime<Utc>,
+    pub uptime_secs: u64,
+    pub database: DependencyCheck,
+    pub redis: DependencyCheck,
+    pub shard_plan: ShardPlan,
+    pub shards: Vec<ShardHealth>,
+    pub state_backend: &'static str,
+    /// Only reported by the in-memory state backend.
+    pub memory: Option<StateUsage>,
+    pub last_error: Option<LastError>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DependencyCheck {
+    pub ok: bool,
+    pub latency_ms: u64,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ShardHealth {
+    pub id: u32,
+    pub stage: String,
+    pub connected: bool,
+    pub latency_ms: Option<u64>,
+    /// Guilds in the cache routed to this shard.
+    pub guilds: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LastError {
+    pub at: DateTime<Utc>,
+    pub message: String,
+}
+
+impl HealthMonitor {
+    pub fn new(
+        pool: PgPool,
+        redis: ConnectionManager,
+        config: &HealthConfig,
+        state: Arc<dyn StateBackend>,
+        shard_plan: ShardPlan,
+    ) -> Self {
+        Self {
+            pool,
+            redis,
+            check_timeout: Duration::from_secs(config.check_timeout_secs.max(1)),
+            started: Instant::now(),
+            started_at: Utc::now(),
+            shard_manager: OnceLock::new(),
+            cache: OnceLock::new(),
+            shard_plan,
+            state,
+            last_error: Mutex::new(None),
+        }
+    }
+
+    /// The shard manager and cache only exist once the client is built,
+    /// after the framework that owns this monitor.
+    pub fn attach_shards(&self, shard_manager: Arc<serenity::ShardManager>, cache: Arc<serenity::Cache>) {
+        let _ = self.shard_manager.set(shard_manager);
+        let _ = self.cache.set(cache);
+    }
+
+    pub fn record_error(&self, message: impl Into<String>) {
+        let mut message = message.into();
+        if message.len() > MAX_ERROR_LEN {
+            let mut end = MAX_ERROR_LEN;
+            while !message.is_char_boundary(end) {
+                end -= 1;
+            }
+            message.truncate(end);
+        }
+
+        if let Ok(mut last_error) = self.last_error.lock() {
+            *last_error = Some(LastError { at: Utc::now(), message });
+        }
+    }
+
+    pub fn uptime(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    pub async fn report(&self) -> HealthReport {
+        let (database, redis, shards) = tokio::join!(
+            self.check(queries::ping(&self.pool)),
+            self.check(redis_client::ping(&self.redis)),
+            self.shards(),
+        );
+
+        let ready = database.ok
+            && redis.ok
+            && !shards.is_empty()
+            && shards.iter().all(|shard| shard.connected);
+
+        HealthReport {
+            ready,
+            started_at: self.started_at,
+            uptime_secs: self.uptime().as_secs(),
+            database,
+            redis,
+            shard_plan: self.shard_plan,
+            shards,
+            state_backend: self.state.name(),
+            memory: self.state.usage(),
+            last_error: self.last_error.lock().ok().and_then(|last_error| last_error.clone()),
+        }
+    }
+
+    async fn check(&self, ping: impl Future<Output = Result<()>>) -> DependencyCheck {
+        let start = Instant::now();
+        let error = match tokio::time::timeout(self.check_timeout, ping).await {
+            Ok(Ok(())) => None,
+            Ok(Err(e)) => Some(format!("{:#}", e)),
+            Err(_) => Some(format!("No response within {}s", self.check_timeout.as_secs())),
+        };
+
+        DependencyCheck {
+            ok: error.is_none(),
+            latency_ms: start.elapsed().as_millis() as u64,
+            error,
+        }
+    }
+
+    async fn shards(&self) -> Vec<ShardHealth> {
+        let Some(shard_manager) = self.shard_manager.get() else {
+            return Vec::new();
+        };
+
+        let mut guilds: HashMap<u32, usize> = HashMap::new();
+        if let Some(cache) = self.cache.get() {
+            for guild_id in cache.guilds() {
+                *guilds.entry(sharding::shard_for(guild_id.get() as i64, self.shard_plan.total)).or_default() += 1;
+            }
+        }
+
+        let mut shards: Vec<ShardHealth> = shard_manager.runners.lock().await
+            .iter()
+            .map(|(shard_id, runner)| ShardHealth {
+                id: shard_id.0,
+                stage: runner.stage.to_string(),
+                connected: runner.stage == serenity::ConnectionStage::Connected,
+                latency_ms: runner.latency.map(|latency| latency.as_millis() as u64),
+                guilds: guilds.get(&shard_id.0).copied().unwrap_or(0),
+            })
+            .collect();
+        shards.sort_by_key(|shard| shard.id);
+
+        shards
+    }
+}
+
+/// Formats a duration as `3d 4h 12m`, dropping leading zero units.
+pub fn format_uptime(uptime: Duration) -> String {
+    let total_minutes = uptime.as_secs() / 60;
+    let (days, hours, minutes) = (total_minutes / 1440, total_minutes / 60 % 24, total_minutes % 60);
+
+    match (days, hours) {
+        (0, 0) => format!("{}m", minutes),
+        (0, _) => format!("{}h {}m", hours, minutes),
+        _ => format!("{}d {}h {}m", days, hours, minutes),
+    }
+}
+
+pub fn spawn(config: HealthConfig, monitor: Arc<HealthMonitor>) {
+    tokio::spawn(async move {
+        if let Err(e) = serve(&config.bind_address, monitor).await {
+            tracing::error!("Health endpoints stopped: {:#}", e);
+        }
+    });
+}
+
+async fn serve(bind_address: &str, monitor: Arc<HealthMonitor>) -> Result<()> {
+    let app = Router::new()
+        .route("/livez", get(liveness))
+        .route("/readyz", get(readiness))
+        .with_state(monitor);
+
+    let listener = tokio::net::TcpListener::bind(bind_address).await
+        .with_context(|| format!("Failed to bind health endpoints to {}", bind_address))?;
+    tracing::info!("Health endpoints listening on {}", listener.local_addr()?);
+
+    axum::serve(listener, app).await?;
+    Ok(())
+}
+
+/// Answers as long as the runtime is responsive; dependencies are left to
+/// readiness so an outage doesn't get the process restarted.
+async fn liveness(State(monitor): State<Arc<HealthMonitor>>) -> impl IntoResponse {
+    Json(json!({
+        "status": "alive",
+        "uptime_secs": monitor.uptime().as_secs(),
+    }))
+}
+
+async fn readiness(State(monitor): State<Arc<HealthMonitor>>) -> impl IntoResponse {
+    let report = monitor.report().await;
+    let status = if report.ready { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+
+    (status, Json(report))
+}
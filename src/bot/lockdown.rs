@@ -0,0 +1,159 @@
+use anyhow::Result;
+use poise::serenity_prelude as serenity;
+use serde_json::json;
+use sqlx::PgPool;
+
+use crate::config::GuildConfig;
+use crate::database::queries;
+use crate::metrics;
+
+use super::webhooks;
+
+const LOCKED_PERMISSIONS: serenity::Permissions = serenity::Permissions::SEND_MESSAGES
+    .union(serenity::Permissions::SEND_MESSAGES_IN_THREADS)
+    .union(serenity::Permissions::CREATE_INSTANT_INVITE);
+
+#[derive(Debug, Default)]
+pub struct LockdownReport {
+    pub channels: usize,
+    pub failed: usize,
+}
+
+/// Denies messaging and invites for @everyone on every text channel (or the
+/// guild's configured lockdown channels). The original overwrite of each
+/// channel is stored before it is touched, so `lift` can restore it exactly
+/// even after a restart.
+pub async fn engage(
+    http: &serenity::Http,
+    pool: &PgPool,
+    guild_id: i64,
+    config: &GuildConfig,
+) -> Result<LockdownReport> {
+    queries::set_lockdown(pool, guild_id, true).await?;
+
+    let guild = serenity::GuildId::new(guild_id as u64);
+    let everyone = serenity::RoleId::new(guild_id as u64);
+    let channels = guild.channels(http).await?;
+
+    let mut report = LockdownReport::default();
+
+    for channel in channels.values() {
+        let lockable = matches!(
+            channel.kind,
+            serenity::ChannelType::Text | serenity::ChannelType::News | serenity::ChannelType::Forum
+        );
+        if !lockable {
+            continue;
+        }
+        if !config.lockdown.channels.is_empty() && !config.lockdown.channels.contains(&channel.id.get()) {
+            continue;
+        }
+
+        let existing = channel.permission_overwrites.iter()
+            .find(|o| o.kind == serenity::PermissionOverwriteType::Role(everyone));
+
+        let (allow, deny) = existing
+            .map(|o| (o.allow, o.deny))
+            .unwrap_or((serenity::Permissions::empty(), serenity::Permissions::empty()));
+
+        queries::save_lockdown_snapshot(
+            pool,
+            guild_id,
+            channel.id.get() as i64,
+            existing.is_some(),
+            allow.bits() as i64,
+            deny.bits() as i64
+        ).await?;
+
+        let overwrite = serenity::PermissionOverwrite {
+            allow: allow - LOCKED_PERMISSIONS,
+            deny: deny | LOCKED_PERMISSIONS,
+            kind: serenity::PermissionOverwriteType::Role(everyone),
+        };
+
+        match channel.id.create_permission(http, overwrite).await {
+            Ok(()) => report.channels += 1,
+            Err(e) => {
+                tracing::warn!("Failed to lock channel {} in guild {}: {}", channel.id, guild_id, e);
+                metrics::discord_failure("lock_channel");
+                report.failed += 1;
+            }
+        }
+    }
+
+    tracing::warn!("Lockdown engaged in guild {}: {} channels locked, {} failed", guild_id, report.channels, report.failed);
+    emit_change(pool, guild_id, true, &report).await;
+    Ok(report)
+}
+
+/// Restores every stored overwrite and drops its snapshot. Snapshots that fail
+/// to restore are kept so a later `lift` (or the next startup) can retry.
+pub async fn lift(http: &serenity::Http, pool: &PgPool, guild_id: i64) -> Result<LockdownReport> {
+    queries::set_lockdown(pool, guild_id, false).await?;
+
+    let everyone = serenity::RoleId::new(guild_id as u64);
+    let snapshots = queries::get_lockdown_snapshots(pool, guild_id).await?;
+
+    let mut report = LockdownReport::default();
+
+    for snapshot in snapshots {
+        let channel_id = serenity::ChannelId::new(snapshot.channel_id as u64);
+        let kind = serenity::PermissionOverwriteType::Role(everyone);
+
+        let result = if snapshot.had_overwrite {
+            channel_id.create_permission(http, serenity::PermissionOverwrite {
+                allow: serenity::Permissions::from_bits_truncate(snapshot.allow_bits as u64),
+                deny: serenity::Permissions::from_bits_truncate(snapshot.deny_bits as u64),
+                kind,
+            }).await
+        } else {
+            channel_id.delete_permission(http, kind).await
+        };
+
+        match result {
+            Ok(()) => {
+                report.channels += 1;
+                queries::delete_lockdown_snapshot(pool, guild_id, snapshot.channel_id).await?;
+            }
+            Err(serenity::Error::Http(e)) if e.status_code() == Some(serenity::StatusCode::NOT_FOUND) => {
+                queries::delete_lockdown_snapshot(pool, guild_id, snapshot.channel_id).await?;
+            }
+            Err(e) => {
+                tracing::warn!("Failed to restore channel {} in guild {}: {}", channel_id, guild_id, e);
+                metrics::discord_failure("restore_channel");
+                report.failed += 1;
+            }
+        }
+    }
+
+    tracing::info!("Lockdown lifted in guild {}: {} channels restored, {} failed", guild_id, report.channels, report.failed);
+    emit_change(pool, guild_id, false, &report).await;
+    Ok(report)
+}
+
+async fn emit_change(pool: &PgPool, guild_id: i64, active: bool, report: &LockdownReport) {
+    webhooks::emit(pool, guild_id, webhooks::LOCKDOWN_CHANGED, json!({
+        "active": active,
+        "channels": report.channels,
+        "failed": report.failed,
+    })).await;
+}
+
+/// Brings Discord back in line with the stored lockdown state after a restart:
+/// re-applies an active lockdown (covering channels created while the bot was
+/// offline) or finishes restoring one that was being lifted.
+pub async fn resume(
+    http: &serenity::Http,
+    pool: &PgPool,
+    guild_id: i64,
+    lockdown_active: bool,
+    config: &GuildConfig,
+) -> Result<()> {
+    if lockdown_active {
+        engage(http, pool, guild_id, config).await?;
+    } else if !queries::get_lockdown_snapshots(pool, guild_id).await?.is_empty() {
+        lift(http, pool, guild_id).await?;
+    }
+
+    Ok(())
+}
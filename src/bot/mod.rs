@@ -1,38 +1,98 @@
 pub mod commands;
 pub mod commands_extra;
 pub mod events;
+pub mod alerts;
+pub mod anti_nuke;
+pub mod backup;
+pub mod blacklist;
+pub mod export;
+pub mod health;
+pub mod lockdown;
+pub mod scheduler;
+pub mod raid_mode;
+pub mod retention;
+pub mod sharding;
+pub mod webhooks;
 
 use anyhow::Result;
 use redis::aio::ConnectionManager;
 use sqlx::PgPool;
 use std::sync::Arc;
+use std::time::Duration;
 
-use crate::config::Config;
+use crate::api::{self, ApiState};
+use crate::config::{Config, StateBackendKind};
+use crate::metrics;
 use crate::security::{
     raid_detector::RaidDetector,
     behavior_analyzer::BehaviorAnalyzer,
     honeypot::HoneypotSystem,
     auto_mod::AutoModerator,
+    guild_config::GuildConfigCache,
+    raid_mode::RaidModeTracker,
+    anti_nuke::AntiNukeTracker,
+    state::{MemoryState, RedisState, StateBackend},
 };
+use crate::utils::time::Clock;
+
+use health::HealthMonitor;
+use sharding::ShardPlan;
+use webhooks::WebhookSender;
 
 pub struct Data {
     pub pool: PgPool,
-    pub _redis: ConnectionManager,
-    pub config: Config,
+    pub guild_configs: Arc<GuildConfigCache>,
     pub raid_detector: Arc<RaidDetector>,
+    pub raid_modes: Arc<RaidModeTracker>,
     pub behavior_analyzer: Arc<BehaviorAnalyzer>,
     pub honeypot: Arc<HoneypotSystem>,
     pub auto_mod: Arc<AutoModerator>,
+    pub anti_nuke: Arc<AntiNukeTracker>,
+    pub webhooks: Arc<WebhookSender>,
+    pub health: Arc<HealthMonitor>,
 }
 
 pub type Error = Box<dyn std::error::Error + Send + Sync>;
 pub type Context<'a> = poise::Context<'a, Data, Error>;
 
-pub async fn create_framework(config: Config, pool: PgPool, redis: ConnectionManager) -> Result<poise::Framework<Data, Error>> {
-    let raid_detector = Arc::new(RaidDetector::new(config.security.clone()));
-    let behavior_analyzer = Arc::new(BehaviorAnalyzer::new());
-    let honeypot = Arc::new(HoneypotSystem::new());
-    let auto_mod = Arc::new(AutoModerator::new(config.auto_mod.clone()));
+pub async fn create_framework(
+    config: Config,
+    pool: PgPool,
+    redis: ConnectionManager,
+    shard_plan: ShardPlan,
+) -> Result<(poise::Framework<Data, Error>, Arc<HealthMonitor>)> {
+    let scheduler_config = config.scheduler.clone();
+    let forensics_config = config.forensics.clone();
+    let health_config = config.health.clone();
+    let state_config = config.state.clone();
+    let state: Arc<dyn StateBackend> = match state_config.backend {
+        StateBackendKind::Memory => Arc::new(MemoryState::new(Clock::system())),
+        StateBackendKind::Redis => Arc::new(RedisState::new(redis.clone(), &state_config.key_prefix)),
+    };
+    tracing::info!("Detector state kept in {}", state.name());
+    let webhooks = Arc::new(WebhookSender::new(config.webhooks.clone())?);
+    let guild_configs = Arc::new(GuildConfigCache::new(config));
+    let raid_detector = Arc::new(RaidDetector::new(state.clone(), Clock::system()));
+    let raid_modes = Arc::new(RaidModeTracker::new(guild_configs.clone()));
+    let behavior_analyzer = Arc::new(BehaviorAnalyzer::new(
+        state.clone(),
+        Duration::from_secs(state_config.message_history_ttl_secs),
+        Clock::system(),
+    ));
+    let honeypot = Arc::new(HoneypotSystem::new(
+        state.clone(),
+        Duration::from_secs(state_config.catch_ttl_secs),
+    ));
+    let auto_mod = Arc::new(AutoModerator::new());
+    let anti_nuke = Arc::new(AntiNukeTracker::new(guild_configs.clone()));
+    let health = Arc::new(HealthMonitor::new(
+        pool.clone(),
+        redis,
+        &health_config,
+        state,
+        shard_plan,
+    ));
+    let data_health = health.clone();
 
     let framework = poise::Framework::builder()
         .options(poise::FrameworkOptions {
@@ -48,25 +108,66 @@ pub async fn create_framework(config: Config, pool: PgPool, redis: ConnectionMan
             event_handler: |ctx, event, framework, data| {
                 Box::pin(events::event_handler(ctx, event, framework, data))
             },
+            on_error: |error| Box::pin(on_error(error)),
             ..Default::default()
         })
-        .setup(|ctx, _ready, framework| {
+        .setup(move |ctx, _ready, framework| {
             Box::pin(async move {
-                poise::builtins::register_globally(ctx, &framework.options().commands).await?;
-                tracing::info!("All slash commands registered with Discord");
+                if shard_plan.registers_commands() {
+                    poise::builtins::register_globally(ctx, &framework.options().commands).await?;
+                    tracing::info!("All slash commands registered with Discord");
+                }
+                
+                scheduler::spawn(ctx.http.clone(), pool.clone(), guild_configs.clone(), scheduler_config);
+                raid_mode::spawn(ctx.http.clone(), pool.clone(), guild_configs.clone(), raid_detector.clone(), raid_modes.clone());
+                webhooks::spawn(pool.clone(), webhooks.clone());
+                retention::spawn(pool.clone(), guild_configs.clone(), forensics_config, shard_plan);
+                metrics::spawn_gateway_sampler(framework.shard_manager().clone());
+                
+                let api_config = guild_configs.file_config().api.clone();
+                if api_config.enabled {
+                    api::spawn(api_config.clone(), ApiState {
+                        pool: pool.clone(),
+                        http: ctx.http.clone(),
+                        guild_configs: guild_configs.clone(),
+                        raid_modes: raid_modes.clone(),
+                        max_page_size: api_config.max_page_size,
+                    });
+                }
                 
                 Ok(Data {
                     pool,
-                    _redis: redis,
-                    config,
+                    guild_configs,
                     raid_detector,
+                    raid_modes,
                     behavior_analyzer,
                     honeypot,
                     auto_mod,
+                    anti_nuke,
+                    webhooks,
+                    health: data_health,
                 })
             })
         })
         .build();
 
-    Ok(framework)
+    Ok((framework, health))
+}
+
+/// Remembers the error for `/admin health` before handing it to poise's
+/// default handler.
+async fn on_error(error: poise::FrameworkError<'_, Data, Error>) {
+    match &error {
+        poise::FrameworkError::Command { error, ctx, .. } => {
+            ctx.data().health.record_error(format!("/{}: {}", ctx.command().qualified_name, error));
+        }
+        poise::FrameworkError::EventHandler { error, event, framework, .. } => {
+            framework.user_data.health.record_error(format!("{} handler: {}", event.snake_case_name(), error));
+        }
+        _ => {}
+    }
+
+    if let Err(e) = poise::builtins::on_error(error).await {
+        tracing::error!("Error while handling error: {}", e);
+    }
 }
@@ -0,0 +1,172 @@
+use anyhow::Result;
+use chrono::Utc;
+use poise::serenity_prelude as serenity;
+use serde_json::json;
+use sqlx::PgPool;
+use std::sync::Arc;
+
+use crate::database::queries;
+use crate::security::{
+    guild_config::GuildConfigCache,
+    raid_detector::RaidDetector,
+    raid_mode::{RaidMode, RaidModeTracker, RaidTransition},
+};
+
+use super::{alerts, lockdown, scheduler::{self, Job}, webhooks};
+
+const TICK_INTERVAL_SECS: u64 = 15;
+
+/// Carries out a raid mode transition: locks the server when entering `Raid`,
+/// lifts a lockdown raid mode engaged itself once back to `Normal`, and
+/// announces the change in the alert channel.
+pub async fn apply_transition(
+    http: &serenity::Http,
+    pool: &PgPool,
+    guild_configs: &GuildConfigCache,
+    raid_modes: &RaidModeTracker,
+    guild_id: i64,
+    transition: RaidTransition,
+) -> Result<()> {
+    let guild_config = guild_configs.get(pool, guild_id).await?;
+    let mut actions = Vec::new();
+
+    tracing::warn!("Raid mode for guild {}: {} -> {} (score {:.2})",
+        guild_id, transition.from.as_str(), transition.to.as_str(), transition.threat_score);
+
+    match transition.to {
+        RaidMode::Raid if guild_config.raid_mode.auto_lockdown && !transition.auto_locked => {
+            let already_locked = queries::get_guild(pool, guild_id).await?
+                .map(|g| g.lockdown_active)
+                .unwrap_or(false);
+
+            if !already_locked {
+                let report = lockdown::engage(http, pool, guild_id, &guild_config).await?;
+                raid_modes.set_auto_locked(guild_id, true);
+                persist(pool, raid_modes, guild_id).await?;
+                actions.push(format!("🔒 Locked {} channels", report.channels));
+
+                if guild_config.lockdown.auto_lift_minutes > 0 {
+                    let lift = scheduler::schedule_lift(pool, guild_id, guild_config.lockdown.auto_lift_minutes, None).await?;
+                    actions.push(format!("⏰ Lockdown expires <t:{}:R> at the latest", lift.run_at.timestamp()));
+                }
+            }
+        }
+        RaidMode::Normal if transition.auto_locked => {
+            let report = lockdown::lift(http, pool, guild_id).await?;
+            queries::cancel_jobs_of_type(pool, guild_id, Job::LIFT_LOCKDOWN).await?;
+            actions.push(format!("🔓 Restored {} channels", report.channels));
+        }
+        _ => {}
+    }
+    persist(pool, raid_modes, guild_id).await?;
+
+    if transition.to == RaidMode::Raid {
+        webhooks::emit(pool, guild_id, webhooks::RAID_DETECTED, json!({
+            "previous_mode": transition.from.as_str(),
+            "raid_score": transition.threat_score,
+            "reasons": transition.reasons,
+            "actions": actions,
+        })).await;
+    }
+
+    let summary = match transition.to {
+        RaidMode::Elevated => "Join activity is above normal. Kitsune is watching closely.".to_string(),
+        RaidMode::Raid => "Raid detected. Kitsune stays in raid mode until join activity calms down.".to_string(),
+        RaidMode::Cooldown => format!(
+            "Join activity has calmed down. Raid mode ends after {} minutes without new raid signals.",
+            guild_config.raid_mode.cooldown_secs / 60
+        ),
+        RaidMode::Normal => "Raid mode has ended.".to_string(),
+    };
+
+    let mut embed = serenity::CreateEmbed::new()
+        .title(format!("🛡️ Raid Mode: {} → {}", transition.from.as_str(), transition.to.as_str()))
+        .description(summary)
+        .color(transition.to.color())
+        .field("Raid Score", format!("{:.2}", transition.threat_score), true);
+
+    if !transition.reasons.is_empty() {
+        embed = embed.field("Reasons", transition.reasons.iter().map(|r| format!("• {}", r)).collect::<Vec<_>>().join("\n"), false);
+    }
+    if !actions.is_empty() {
+        embed = embed.field("Actions", actions.join("\n"), false);
+    }
+
+    alerts::dispatch_alert(http, &guild_config, embed
+        .footer(serenity::CreateEmbedFooter::new("Kitsune Guardian Fox"))
+    ).await;
+
+    Ok(())
+}
+
+/// Mirrors the guild's raid mode in the database; guilds back in `Normal`
+/// have no row.
+pub async fn persist(pool: &PgPool, raid_modes: &RaidModeTracker, guild_id: i64) -> Result<()> {
+    match raid_modes.state(guild_id) {
+        Some(state) => queries::save_raid_mode(pool, guild_id, state.mode.as_str(), state.since, state.auto_locked).await,
+        None => queries::delete_raid_mode(pool, guild_id).await,
+    }
+}
+
+/// Picks up the raid mode a guild was in before a restart, so the periodic
+/// re-evaluation can wind it down and lift the lockdown it engaged. A lockdown
+/// lifted in the meantime is no longer raid mode's to lift.
+pub async fn resume(pool: &PgPool, raid_modes: &RaidModeTracker, guild_id: i64, lockdown_active: bool) -> Result<()> {
+    if raid_modes.state(guild_id).is_some() {
+        return Ok(());
+    }
+    let Some(record) = queries::get_raid_mode(pool, guild_id).await? else {
+        return Ok(());
+    };
+
+    match RaidMode::parse(&record.mode) {
+        Some(mode) => {
+            raid_modes.restore(guild_id, mode, record.since, record.auto_locked && lockdown_active);
+            tracing::info!("Resumed raid mode {} for guild {}", mode.as_str(), guild_id);
+            persist(pool, raid_modes, guild_id).await
+        }
+        None => queries::delete_raid_mode(pool, guild_id).await,
+    }
+}
+
+/// Re-evaluates every guild outside `Normal` on a fixed interval, so raid mode
+/// can wind down when joins simply stop.
+pub fn spawn(
+    http: Arc<serenity::Http>,
+    pool: PgPool,
+    guild_configs: Arc<GuildConfigCache>,
+    raid_detector: Arc<RaidDetector>,
+    raid_modes: Arc<RaidModeTracker>,
+) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(TICK_INTERVAL_SECS));
+
+        loop {
+            interval.tick().await;
+
+            for guild_id in raid_modes.active_guilds() {
+                let guild_config = match guild_configs.get(&pool, guild_id).await {
+                    Ok(config) => config,
+                    Err(e) => {
+                        tracing::warn!("Failed to load configuration for guild {}: {}", guild_id, e);
+                        continue;
+                    }
+                };
+
+                let analysis = match raid_detector.analyze_raid_risk(guild_id, &guild_config).await {
+                    Ok(analysis) => analysis,
+                    Err(e) => {
+                        tracing::warn!("Failed to analyze raid risk for guild {}: {}", guild_id, e);
+                        continue;
+                    }
+                };
+
+                if let Some(transition) = raid_modes.observe(guild_id, &analysis, Utc::now()) {
+                    if let Err(e) = apply_transition(&http, &pool, &guild_configs, &raid_modes, guild_id, transition).await {
+                        tracing::error!("Failed to apply raid mode transition for guild {}: {}", guild_id, e);
+                    }
+                }
+            }
+        }
+    });
+}
@@ -0,0 +1,113 @@
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use sqlx::PgPool;
+use std::sync::Arc;
+
+use super::sharding::ShardPlan;
+use crate::config::ForensicsConfig;
+use crate::database::queries;
+use crate::metrics::metrics;
+use crate::security::guild_config::GuildConfigCache;
+
+/// Pause between batches so the purge never holds locks for long or starves
+/// live writes.
+const BATCH_PAUSE: std::time::Duration = std::time::Duration::from_millis(200);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RetainedTable {
+    ForensicEvents,
+    ResolvedIncidents,
+    HoneypotCatches,
+}
+
+impl RetainedTable {
+    pub const ALL: [RetainedTable; 3] = [
+        RetainedTable::ForensicEvents,
+        RetainedTable::ResolvedIncidents,
+        RetainedTable::HoneypotCatches,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            RetainedTable::ForensicEvents => "forensic_events",
+            RetainedTable::ResolvedIncidents => "incidents",
+            RetainedTable::HoneypotCatches => "honeypot_catches",
+        }
+    }
+
+    async fn purge_batch(&self, pool: &PgPool, guild_id: i64, before: DateTime<Utc>, config: &ForensicsConfig) -> Result<u64> {
+        let batch_size = config.purge_batch_size.max(1);
+        match self {
+            RetainedTable::ForensicEvents => queries::purge_forensic_events(pool, guild_id, before, batch_size, config.archive).await,
+            RetainedTable::ResolvedIncidents => queries::purge_resolved_incidents(pool, guild_id, before, batch_size, config.archive).await,
+            RetainedTable::HoneypotCatches => queries::purge_honeypot_catches(pool, guild_id, before, batch_size, config.archive).await,
+        }
+    }
+}
+
+pub fn spawn(pool: PgPool, guild_configs: Arc<GuildConfigCache>, config: ForensicsConfig, shard_plan: ShardPlan) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(config.purge_interval_mins.max(1) * 60));
+
+        loop {
+            interval.tick().await;
+
+            if let Err(e) = purge_all(&pool, &guild_configs, &config, &shard_plan).await {
+                tracing::error!("Retention purge failed: {}", e);
+            }
+        }
+    });
+}
+
+async fn purge_all(pool: &PgPool, guild_configs: &GuildConfigCache, config: &ForensicsConfig, shard_plan: &ShardPlan) -> Result<()> {
+    for guild_id in queries::get_guild_ids(pool).await? {
+        if !shard_plan.owns(guild_id) {
+            continue;
+        }
+
+        let retention_days = guild_configs.get(pool, guild_id).await?.forensics.retention_days;
+        if retention_days <= 0 {
+            continue;
+        }
+
+        let before = Utc::now() - Duration::days(retention_days);
+        for table in RetainedTable::ALL {
+            match purge_table(pool, guild_id, table, before, config).await {
+                Ok(0) => {}
+                Ok(rows) => tracing::info!(
+                    "{} {} {} rows older than {} days for guild {}",
+                    if config.archive { "Archived" } else { "Purged" },
+                    rows,
+                    table.name(),
+                    retention_days,
+                    guild_id
+                ),
+                Err(e) => tracing::warn!("Failed to purge {} for guild {}: {}", table.name(), guild_id, e),
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Removes expired rows in batches until none are left.
+async fn purge_table(
+    pool: &PgPool,
+    guild_id: i64,
+    table: RetainedTable,
+    before: DateTime<Utc>,
+    config: &ForensicsConfig,
+) -> Result<u64> {
+    let mut total = 0;
+
+    loop {
+        let rows = table.purge_batch(pool, guild_id, before, config).await?;
+        total += rows;
+        metrics().rows_purged.with_label_values(&[table.name()]).inc_by(rows);
+
+        if rows < config.purge_batch_size.max(1) as u64 {
+            return Ok(total);
+        }
+        tokio::time::sleep(BATCH_PAUSE).await;
+    }
+}
@@ -0,0 +1,234 @@
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Duration, Utc};
+use poise::serenity_prelude as serenity;
+use serde_json::json;
+use sqlx::PgPool;
+use std::sync::Arc;
+
+use crate::config::{BackupConfig, BackupFrequency, SchedulerConfig};
+use crate::database::{models::ScheduledJob, queries};
+use crate::security::guild_config::GuildConfigCache;
+
+use super::{alerts, backup, lockdown};
+
+const CLAIM_BATCH: i64 = 20;
+const STALE_JOB_MINUTES: i32 = 15;
+
+/// Longest delay accepted for a lockdown job or its duration: one year.
+pub const MAX_DELAY_MINUTES: u64 = 525_600;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Job {
+    LiftLockdown,
+    StartLockdown { duration_minutes: Option<u64> },
+    CreateBackup,
+}
+
+impl Job {
+    pub const LIFT_LOCKDOWN: &'static str = "lift_lockdown";
+    pub const START_LOCKDOWN: &'static str = "start_lockdown";
+    pub const CREATE_BACKUP: &'static str = "create_backup";
+
+    pub fn job_type(&self) -> &'static str {
+        match self {
+            Job::LiftLockdown => Self::LIFT_LOCKDOWN,
+            Job::StartLockdown { .. } => Self::START_LOCKDOWN,
+            Job::CreateBackup => Self::CREATE_BACKUP,
+        }
+    }
+
+    fn payload(&self) -> serde_json::Value {
+        match self {
+            Job::LiftLockdown | Job::CreateBackup => json!({}),
+            Job::StartLockdown { duration_minutes } => json!({ "duration_minutes": duration_minutes }),
+        }
+    }
+
+    pub fn from_row(job: &ScheduledJob) -> Result<Self> {
+        match job.job_type.as_str() {
+            Self::LIFT_LOCKDOWN => Ok(Job::LiftLockdown),
+            Self::CREATE_BACKUP => Ok(Job::CreateBackup),
+            Self::START_LOCKDOWN => Ok(Job::StartLockdown {
+                duration_minutes: job.payload.get("duration_minutes").and_then(|v| v.as_u64()),
+            }),
+            other => Err(anyhow!("Unknown job type: {}", other)),
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            Job::LiftLockdown => "Lift lockdown".to_string(),
+            Job::StartLockdown { duration_minutes: Some(minutes) } => format!("Start lockdown for {} minutes", minutes),
+            Job::StartLockdown { duration_minutes: None } => "Start lockdown".to_string(),
+            Job::CreateBackup => "Automatic backup".to_string(),
+        }
+    }
+}
+
+pub async fn schedule(
+    pool: &PgPool,
+    guild_id: i64,
+    job: Job,
+    run_at: DateTime<Utc>,
+    created_by: Option<i64>,
+) -> Result<ScheduledJob> {
+    queries::create_scheduled_job(pool, guild_id, job.job_type(), job.payload(), run_at, created_by).await
+}
+
+/// The time `minutes` from now, rejecting delays over `MAX_DELAY_MINUTES`
+/// instead of overflowing.
+pub fn minutes_from_now(minutes: u64) -> Result<DateTime<Utc>> {
+    if minutes > MAX_DELAY_MINUTES {
+        return Err(anyhow!("Delays are limited to {} minutes (one year)", MAX_DELAY_MINUTES));
+    }
+
+    Duration::try_minutes(minutes as i64)
+        .and_then(|delay| Utc::now().checked_add_signed(delay))
+        .ok_or_else(|| anyhow!("{} minutes from now is out of range", minutes))
+}
+
+/// Replaces any pending lift for the guild with one `minutes` from now, so the
+/// latest lockdown always decides when the server reopens.
+pub async fn schedule_lift(
+    pool: &PgPool,
+    guild_id: i64,
+    minutes: u64,
+    created_by: Option<i64>,
+) -> Result<ScheduledJob> {
+    let run_at = minutes_from_now(minutes)?;
+    queries::cancel_jobs_of_type(pool, guild_id, Job::LIFT_LOCKDOWN).await?;
+    schedule(pool, guild_id, Job::LiftLockdown, run_at, created_by).await
+}
+
+/// Replaces any pending automatic backup for the guild with one a full
+/// interval from now. Each backup job schedules its successor, so this only
+/// needs to be called when the frequency changes or the chain is missing.
+pub async fn schedule_backup(
+    pool: &PgPool,
+    guild_id: i64,
+    frequency: BackupFrequency,
+) -> Result<ScheduledJob> {
+    queries::cancel_jobs_of_type(pool, guild_id, Job::CREATE_BACKUP).await?;
+    schedule(pool, guild_id, Job::CreateBackup, Utc::now() + frequency.interval(), None).await
+}
+
+/// Starts the automatic backup chain for a guild that has a frequency
+/// configured but no backup job queued, e.g. one configured in the file.
+pub async fn ensure_backup_job(pool: &PgPool, guild_id: i64, config: &BackupConfig) -> Result<()> {
+    let Some(frequency) = config.frequency else {
+        return Ok(());
+    };
+
+    let queued = queries::get_pending_jobs(pool, guild_id).await?
+        .iter()
+        .any(|job| job.job_type == Job::CREATE_BACKUP);
+    if !queued {
+        schedule_backup(pool, guild_id, frequency).await?;
+    }
+
+    Ok(())
+}
+
+pub fn spawn(
+    http: Arc<serenity::Http>,
+    pool: PgPool,
+    guild_configs: Arc<GuildConfigCache>,
+    config: SchedulerConfig,
+) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(config.poll_interval_secs.max(1)));
+
+        loop {
+            interval.tick().await;
+
+            if let Err(e) = poll(&http, &pool, &guild_configs, &config).await {
+                tracing::error!("Scheduler poll failed: {}", e);
+            }
+        }
+    });
+}
+
+async fn poll(
+    http: &serenity::Http,
+    pool: &PgPool,
+    guild_configs: &GuildConfigCache,
+    config: &SchedulerConfig,
+) -> Result<()> {
+    let requeued = queries::requeue_stale_jobs(pool, STALE_JOB_MINUTES).await?;
+    if requeued > 0 {
+        tracing::warn!("Requeued {} scheduled jobs left running by a previous process", requeued);
+    }
+
+    for job in queries::claim_due_jobs(pool, CLAIM_BATCH).await? {
+        match run(http, pool, guild_configs, &job).await {
+            Ok(()) => {
+                queries::complete_job(pool, job.id).await?;
+                tracing::info!("Completed {} job {} for guild {}", job.job_type, job.id, job.guild_id);
+            }
+            Err(e) => {
+                let retry_at = (job.attempts < config.max_attempts)
+                    .then(|| Utc::now() + Duration::seconds(30 * 2_i64.pow(job.attempts.clamp(0, 10) as u32)));
+
+                tracing::warn!("{} job {} for guild {} failed (attempt {}): {}", job.job_type, job.id, job.guild_id, job.attempts, e);
+                queries::fail_job(pool, job.id, &e.to_string(), retry_at).await?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+async fn run(
+    http: &serenity::Http,
+    pool: &PgPool,
+    guild_configs: &GuildConfigCache,
+    job: &ScheduledJob,
+) -> Result<()> {
+    let guild_id = job.guild_id;
+    let guild_config = guild_configs.get(pool, guild_id).await?;
+
+    match Job::from_row(job)? {
+        Job::LiftLockdown => {
+            let report = lockdown::lift(http, pool, guild_id).await?;
+            if report.failed > 0 {
+                return Err(anyhow!("{} channels could not be restored", report.failed));
+            }
+
+            alerts::dispatch_alert(http, &guild_config, serenity::CreateEmbed::new()
+                .title("✅ Lockdown Lifted")
+                .description(format!("Scheduled lockdown expiry reached\n**Channels restored:** {}", report.channels))
+                .color(0x2ecc71)
+                .footer(serenity::CreateEmbedFooter::new("Kitsune Guardian Fox"))
+            ).await;
+        }
+        Job::StartLockdown { duration_minutes } => {
+            let report = lockdown::engage(http, pool, guild_id, &guild_config).await?;
+
+            let lift_text = match duration_minutes {
+                Some(minutes) => {
+                    let lift = schedule_lift(pool, guild_id, minutes, job.created_by).await?;
+                    format!("\n**Lifts:** <t:{}:R>", lift.run_at.timestamp())
+                }
+                None => String::new(),
+            };
+
+            alerts::dispatch_alert(http, &guild_config, serenity::CreateEmbed::new()
+                .title("🔒 Scheduled Lockdown Engaged")
+                .description(format!("**Channels locked:** {}{}", report.channels, lift_text))
+                .color(0xe74c3c)
+                .footer(serenity::CreateEmbedFooter::new("Kitsune Guardian Fox"))
+            ).await;
+        }
+        Job::CreateBackup => {
+            let Some(frequency) = guild_config.backup.frequency else {
+                return Ok(());
+            };
+
+            // Queue the next run first so a failing backup doesn't end the chain.
+            schedule_backup(pool, guild_id, frequency).await?;
+            backup::create_automatic(http, pool, guild_id, &guild_config.backup).await?;
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,86 @@
+use anyhow::{bail, Context, Result};
+use poise::serenity_prelude as serenity;
+use serde::Serialize;
+
+use crate::config::{ShardingConfig, ShardingMode};
+
+/// The shards this process runs, out of the bot's total.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct ShardPlan {
+    pub first: u32,
+    /// Inclusive.
+    pub last: u32,
+    pub total: u32,
+}
+
+impl ShardPlan {
+    /// `auto` asks Discord for its recommended shard count, which is why this
+    /// needs the HTTP client before the gateway connects.
+    pub async fn resolve(config: &ShardingConfig, http: &serenity::Http) -> Result<Self> {
+        let plan = match config.mode {
+            ShardingMode::Auto => {
+                let gateway = http.get_bot_gateway().await
+                    .context("Failed to fetch the recommended shard count")?;
+                Self::all(gateway.shards.max(1))
+            }
+            ShardingMode::Fixed => Self::all(config.total_shards),
+            ShardingMode::Range => Self {
+                first: config.first_shard,
+                last: config.last_shard,
+                total: config.total_shards,
+            },
+        };
+
+        if plan.total == 0 {
+            bail!("sharding.total_shards must be at least 1");
+        }
+        if plan.first > plan.last || plan.last >= plan.total {
+            bail!(
+                "Invalid shard range {}-{}: first_shard must not exceed last_shard, and last_shard must be below total_shards ({})",
+                plan.first, plan.last, plan.total
+            );
+        }
+
+        Ok(plan)
+    }
+
+    fn all(total: u32) -> Self {
+        Self { first: 0, last: total.saturating_sub(1), total }
+    }
+
+    pub fn runs_all(&self) -> bool {
+        self.first == 0 && self.last + 1 == self.total
+    }
+
+    /// Whether the guild's events arrive on one of this process's shards.
+    /// Work driven by the database rather than by events (retention, for
+    /// instance) is limited to these guilds so processes don't overlap.
+    pub fn owns(&self, guild_id: i64) -> bool {
+        (self.first..=self.last).contains(&shard_for(guild_id, self.total))
+    }
+
+    /// Global commands only need registering once per deploy, not once per
+    /// process.
+    pub fn registers_commands(&self) -> bool {
+        self.first == 0
+    }
+
+    pub fn describe(&self) -> String {
+        if self.runs_all() {
+            format!("all {} shard(s)", self.total)
+        } else {
+            format!("shards {}-{} of {}", self.first, self.last, self.total)
+        }
+    }
+}
+
+/// Discord's routing: `(guild_id >> 22) % shard_count`.
+pub fn shard_for(guild_id: i64, total: u32) -> u32 {
+    ((guild_id as u64 >> 22) % total.max(1) as u64) as u32
+}
+
+pub async fn start(client: &mut serenity::Client, plan: ShardPlan) -> Result<()> {
+    // Serenity treats the end of this range as inclusive.
+    client.start_shard_range(plan.first..plan.last, plan.total).await?;
+    Ok(())
+}
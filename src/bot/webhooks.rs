@@ -0,0 +1,389 @@
+use anyhow::Result;
+use chrono::{Duration, Utc};
+use futures::StreamExt;
+use hmac::{Hmac, Mac};
+use serde_json::json;
+use sha2::Sha256;
+use sqlx::PgPool;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::config::WebhookConfig;
+use crate::database::{
+    models::{Incident, Webhook, WebhookDelivery},
+    queries,
+};
+
+pub const INCIDENT_CREATED: &str = "incident.created";
+pub const LOCKDOWN_CHANGED: &str = "lockdown.changed";
+pub const HONEYPOT_CAUGHT: &str = "honeypot.caught";
+pub const RAID_DETECTED: &str = "raid.detected";
+/// Only sent by `/admin webhook test`, never subscribed to.
+pub const TEST: &str = "webhook.test";
+
+pub const EVENTS: [&str; 4] = [INCIDENT_CREATED, LOCKDOWN_CHANGED, HONEYPOT_CAUGHT, RAID_DETECTED];
+
+const CLAIM_BATCH: i64 = 20;
+const STALE_DELIVERY_MINUTES: i32 = 15;
+const MAX_ERROR_LEN: usize = 300;
+
+/// Parses a comma separated list of event names; `all` subscribes to every
+/// event.
+pub fn parse_events(input: &str) -> Result<Vec<String>, String> {
+    let mut events = Vec::new();
+
+    for name in input.split(',').map(str::trim).filter(|name| !name.is_empty()) {
+        if name.eq_ignore_ascii_case("all") {
+            return Ok(EVENTS.iter().map(|event| event.to_string()).collect());
+        }
+        if !EVENTS.contains(&name) {
+            return Err(format!("Unknown event `{}`. Valid events: {}, all", name, EVENTS.join(", ")));
+        }
+        if !events.iter().any(|event| event == name) {
+            events.push(name.to_string());
+        }
+    }
+
+    if events.is_empty() {
+        return Err(format!("No events given. Valid events: {}, all", EVENTS.join(", ")));
+    }
+
+    Ok(events)
+}
+
+/// The body every delivery is sent with. IDs are strings because snowflakes
+/// don't fit in a JavaScript number.
+pub fn envelope(delivery_id: Uuid, event: &str, guild_id: i64, data: &serde_json::Value) -> serde_json::Value {
+    json!({
+        "id": delivery_id,
+        "event": event,
+        "guild_id": guild_id.to_string(),
+        "created_at": Utc::now(),
+        "data": data,
+    })
+}
+
+/// Queues `event` for every webhook of the guild subscribed to it. Failures
+/// are logged rather than returned, so a webhook problem never interrupts the
+/// moderation flow that raised the event.
+pub async fn emit(pool: &PgPool, guild_id: i64, event: &str, data: serde_json::Value) {
+    let queued = queries::enqueue_webhook_deliveries(pool, guild_id, event, |delivery_id| {
+        envelope(delivery_id, event, guild_id, &data)
+    }).await;
+
+    match queued {
+        Ok(0) => {}
+        Ok(count) => tracing::debug!("Queued {} {} webhook deliveries for guild {}", count, event, guild_id),
+        Err(e) => tracing::error!("Failed to queue {} webhook deliveries for guild {}: {}", event, guild_id, e),
+    }
+}
+
+pub async fn emit_incident(pool: &PgPool, incident: &Incident) {
+    emit(pool, incident.guild_id, INCIDENT_CREATED, json!({
+        "incident_id": incident.id,
+        "user_id": incident.user_id.to_string(),
+        "incident_type": incident.incident_type,
+        "severity": incident.severity,
+        "threat_score": incident.threat_score,
+        "action_taken": incident.action_taken,
+        "evidence": incident.evidence,
+        "created_at": incident.created_at,
+    })).await;
+}
+
+/// `sha256=` followed by the hex HMAC-SHA256 of `{timestamp}.{body}`, keyed
+/// with the webhook secret. Including the timestamp lets receivers reject
+/// replayed deliveries.
+pub fn sign(secret: &str, timestamp: i64, body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())
+        .expect("HMAC accepts keys of any length");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body);
+
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+/// Outcome of a single delivery attempt.
+#[derive(Debug)]
+pub struct Attempt {
+    pub status_code: Option<i32>,
+    pub error: Option<String>,
+}
+
+impl Attempt {
+    pub fn succeeded(&self) -> bool {
+        self.error.is_none()
+    }
+
+    pub fn describe(&self) -> String {
+        match (&self.error, self.status_code) {
+            (None, Some(code)) => format!("Delivered (HTTP {})", code),
+            (None, None) => "Delivered".to_string(),
+            (Some(error), _) => error.clone(),
+        }
+    }
+}
+
+/// Whether a webhook may be sent to `ip`. Loopback, private, link-local and
+/// unspecified addresses are refused so a webhook can't reach services on
+/// the bot's own network, such as a cloud metadata endpoint. IPv6 addresses
+/// that carry an IPv4 one are judged by the address they carry.
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let shared = ip.octets()[0] == 100 && (ip.octets()[1] & 0xc0) == 64;
+            !(ip.is_loopback() || ip.is_private() || ip.is_link_local() || ip.is_unspecified() || ip.is_broadcast() || shared)
+        }
+        IpAddr::V6(ip) => match embedded_ipv4(ip) {
+            Some(ip) => is_public(IpAddr::V4(ip)),
+            None => !(ip.is_loopback() || ip.is_unique_local() || ip.is_unicast_link_local() || ip.is_unspecified()),
+        },
+    }
+}
+
+/// The IPv4 address inside an IPv4-mapped (`::ffff:0:0/96`), NAT64
+/// (`64:ff9b::/96`) or 6to4 (`2002::/16`) address.
+fn embedded_ipv4(ip: Ipv6Addr) -> Option<Ipv4Addr> {
+    let octets = ip.octets();
+    let segments = ip.segments();
+
+    if let Some(ip) = ip.to_ipv4_mapped() {
+        Some(ip)
+    } else if segments[..6] == [0x64, 0xff9b, 0, 0, 0, 0] {
+        Some(Ipv4Addr::new(octets[12], octets[13], octets[14], octets[15]))
+    } else if segments[0] == 0x2002 {
+        Some(Ipv4Addr::new(octets[2], octets[3], octets[4], octets[5]))
+    } else {
+        None
+    }
+}
+
+/// Scheme and host of a webhook URL. Paths and queries of third-party
+/// webhooks often carry a secret token, so only this part is shown in
+/// Discord.
+pub fn display_url(url: &str) -> String {
+    match reqwest::Url::parse(url) {
+        Ok(parsed) => match (parsed.host_str(), parsed.port()) {
+            (Some(host), Some(port)) => format!("{}://{}:{}/…", parsed.scheme(), host, port),
+            (Some(host), None) => format!("{}://{}/…", parsed.scheme(), host),
+            (None, _) => format!("{}:…", parsed.scheme()),
+        },
+        Err(_) => "(invalid URL)".to_string(),
+    }
+}
+
+/// Resolves `host` and fails if any of its addresses isn't public.
+async fn resolve_public(host: &str, port: u16) -> Result<Vec<SocketAddr>, String> {
+    let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host, port)).await
+        .map_err(|e| format!("Could not resolve {}: {}", host, e))?
+        .collect();
+
+    match addrs.iter().find(|addr| !is_public(addr.ip())) {
+        _ if addrs.is_empty() => Err(format!("{} does not resolve to any address", host)),
+        Some(addr) => Err(format!("{} resolves to {}, which is not a public address", host, addr.ip())),
+        None => Ok(addrs),
+    }
+}
+
+/// Resolver for the delivery client. It repeats the public address check
+/// when connecting, so a host can't pass `check_url` and later resolve to an
+/// internal address.
+struct PublicResolver;
+
+impl reqwest::dns::Resolve for PublicResolver {
+    fn resolve(&self, name: reqwest::dns::Name) -> reqwest::dns::Resolving {
+        Box::pin(async move {
+            let addrs = resolve_public(name.as_str(), 0).await?;
+            Ok(Box::new(addrs.into_iter()) as reqwest::dns::Addrs)
+        })
+    }
+}
+
+pub struct WebhookSender {
+    client: reqwest::Client,
+    config: WebhookConfig,
+}
+
+impl WebhookSender {
+    pub fn new(config: WebhookConfig) -> Result<Self> {
+        let mut builder = reqwest::Client::builder()
+            .timeout(std::time::Duration::from_secs(config.timeout_secs.max(1)))
+            .user_agent(concat!("Kitsune-Webhooks/", env!("CARGO_PKG_VERSION")))
+            .redirect(reqwest::redirect::Policy::none());
+        if !config.allow_private_targets {
+            builder = builder.dns_resolver(Arc::new(PublicResolver));
+        }
+
+        Ok(Self { client: builder.build()?, config })
+    }
+
+    /// Checks that `url` is an http(s) URL whose host is a public address,
+    /// unless `allow_private_targets` is set. Used when a webhook is added
+    /// and again before every delivery.
+    pub async fn check_url(&self, url: &str) -> Result<(), String> {
+        let parsed = reqwest::Url::parse(url).map_err(|e| format!("Invalid URL: {}", e))?;
+        if !matches!(parsed.scheme(), "http" | "https") {
+            return Err("Webhook URLs must use http or https".to_string());
+        }
+        if self.config.allow_private_targets {
+            return Ok(());
+        }
+
+        let host = parsed.host_str().ok_or("Webhook URLs must include a host")?;
+        match host.trim_start_matches('[').trim_end_matches(']').parse::<IpAddr>() {
+            Ok(ip) if is_public(ip) => Ok(()),
+            Ok(ip) => Err(format!("{} is not a public address", ip)),
+            Err(_) => resolve_public(host, parsed.port_or_known_default().unwrap_or(443)).await.map(|_| ()),
+        }
+    }
+
+    /// Sends a claimed delivery and records the result: delivered, queued for
+    /// a retry with exponential backoff, or failed once `max_attempts` is
+    /// used up.
+    pub async fn attempt(&self, pool: &PgPool, webhook: &Webhook, delivery: &WebhookDelivery) -> Result<Attempt> {
+        let attempt = self.send(webhook, delivery).await;
+
+        match &attempt.error {
+            None => {
+                queries::complete_delivery(pool, delivery.id, attempt.status_code.unwrap_or_default()).await?;
+            }
+            Some(error) => {
+                let retry_at = (delivery.attempts < self.config.max_attempts).then(|| {
+                    let backoff = self.config.base_backoff_secs * 2_i64.pow(delivery.attempts.clamp(1, 12) as u32 - 1);
+                    Utc::now() + Duration::seconds(backoff)
+                });
+
+                tracing::warn!("Webhook {} delivery {} failed (attempt {}): {}", webhook.id, delivery.id, delivery.attempts, error);
+                queries::fail_delivery(pool, delivery.id, attempt.status_code, error, retry_at).await?;
+            }
+        }
+
+        Ok(attempt)
+    }
+
+    async fn send(&self, webhook: &Webhook, delivery: &WebhookDelivery) -> Attempt {
+        if let Err(error) = self.check_url(&webhook.url).await {
+            return Attempt { status_code: None, error: Some(error) };
+        }
+
+        let body = match serde_json::to_vec(&delivery.payload) {
+            Ok(body) => body,
+            Err(e) => return Attempt { status_code: None, error: Some(e.to_string()) },
+        };
+        let timestamp = Utc::now().timestamp();
+
+        let response = self.client.post(&webhook.url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header("X-Kitsune-Event", &delivery.event)
+            .header("X-Kitsune-Delivery", delivery.id.to_string())
+            .header("X-Kitsune-Timestamp", timestamp.to_string())
+            .header("X-Kitsune-Signature", sign(&webhook.secret, timestamp, &body))
+            .body(body)
+            .send()
+            .await;
+
+        match response {
+            Ok(response) if response.status().is_success() => Attempt {
+                status_code: Some(response.status().as_u16() as i32),
+                error: None,
+            },
+            Ok(response) => Attempt {
+                status_code: Some(response.status().as_u16() as i32),
+                error: Some(format!("HTTP {}", response.status())),
+            },
+            Err(e) => {
+                // Without the URL, which ends up in the deliveries listing.
+                let mut error = e.without_url().to_string();
+                error.truncate(MAX_ERROR_LEN);
+                Attempt { status_code: None, error: Some(error) }
+            }
+        }
+    }
+}
+
+pub fn spawn(pool: PgPool, sender: Arc<WebhookSender>) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(sender.config.poll_interval_secs.max(1)));
+
+        loop {
+            interval.tick().await;
+
+            if let Err(e) = poll(&pool, &sender).await {
+                tracing::error!("Webhook delivery poll failed: {}", e);
+            }
+        }
+    });
+}
+
+async fn poll(pool: &PgPool, sender: &WebhookSender) -> Result<()> {
+    let requeued = queries::requeue_stale_deliveries(pool, STALE_DELIVERY_MINUTES).await?;
+    if requeued > 0 {
+        tracing::warn!("Requeued {} webhook deliveries left sending by a previous process", requeued);
+    }
+
+    let deliveries = queries::claim_due_deliveries(pool, CLAIM_BATCH).await?;
+    futures::stream::iter(deliveries)
+        .for_each_concurrent(sender.config.concurrency.max(1), |delivery| deliver(pool, sender, delivery))
+        .await;
+
+    Ok(())
+}
+
+/// Sends one claimed delivery. Errors are logged rather than returned so one
+/// bad delivery doesn't hold back the rest of the batch, and a claim that
+/// couldn't be sent goes straight back to the queue instead of waiting to be
+/// requeued as stale.
+async fn deliver(pool: &PgPool, sender: &WebhookSender, delivery: WebhookDelivery) {
+    let webhook = match queries::get_webhook(pool, delivery.guild_id, delivery.webhook_id).await {
+        Ok(Some(webhook)) => webhook,
+        // Deleting a webhook cascades to its deliveries, so a missing one was
+        // removed after this delivery was claimed.
+        Ok(None) => return,
+        Err(e) => {
+            tracing::error!("Failed to load webhook {} for delivery {}: {}", delivery.webhook_id, delivery.id, e);
+            if let Err(e) = queries::release_delivery(pool, delivery.id).await {
+                tracing::error!("Failed to release webhook delivery {}: {}", delivery.id, e);
+            }
+            return;
+        }
+    };
+
+    if let Err(e) = sender.attempt(pool, &webhook, &delivery).await {
+        tracing::error!("Failed to record webhook delivery {}: {}", delivery.id, e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn public(ip: &str) -> bool {
+        is_public(ip.parse().unwrap())
+    }
+
+    #[test]
+    fn internal_addresses_are_refused() {
+        for ip in ["127.0.0.1", "10.1.2.3", "192.168.0.10", "169.254.169.254", "100.64.0.1", "0.0.0.0", "::1", "fd00::1", "fe80::1"] {
+            assert!(!public(ip), "{} should be refused", ip);
+        }
+        assert!(public("93.184.216.34"));
+        assert!(public("2606:4700::1111"));
+    }
+
+    #[test]
+    fn embedded_ipv4_is_unwrapped() {
+        for ip in ["::ffff:127.0.0.1", "64:ff9b::a9fe:a9fe", "64:ff9b::10.0.0.1", "2002:c0a8:0001::1", "2002:7f00:1::"] {
+            assert!(!public(ip), "{} should be refused", ip);
+        }
+        assert!(public("64:ff9b::5db8:d822"));
+        assert!(public("2002:5db8:d822::1"));
+    }
+
+    #[test]
+    fn display_url_hides_path_and_query() {
+        assert_eq!(display_url("https://discord.com/api/webhooks/1/secret-token"), "https://discord.com/…");
+        assert_eq!(display_url("http://hooks.example.com:8080/x?token=abc"), "http://hooks.example.com:8080/…");
+    }
+}
@@ -0,0 +1,77 @@
+use anyhow::{bail, Context, Result};
+use std::env;
+
+use crate::database::{self, migrations::{self, MigrationState}};
+use crate::replay;
+
+const USAGE: &str = "\
+Usage:
+  kitsune                   Start the bot
+  kitsune migrate up        Apply pending database migrations
+  kitsune migrate status    List migrations and whether they have been applied
+  kitsune replay <file> [--fail-on-mismatch]
+                            Run recorded events through the detectors without
+                            Discord or the database, and report the decisions";
+
+/// Runs a one-off command instead of starting the bot.
+pub async fn run(args: &[String]) -> Result<()> {
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+
+    match args.as_slice() {
+        ["migrate", "up"] => migrate_up().await,
+        ["migrate", "status"] => migrate_status().await,
+        ["replay", path] => replay::run(path, false).await,
+        ["replay", path, "--fail-on-mismatch"] | ["replay", "--fail-on-mismatch", path] => replay::run(path, true).await,
+        ["help" | "-h" | "--help"] => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+        _ => bail!("Unknown command: {}\n\n{}", args.join(" "), USAGE),
+    }
+}
+
+async fn connect() -> Result<sqlx::PgPool> {
+    let database_url = env::var("DATABASE_URL")
+        .context("DATABASE_URL environment variable not set")?;
+
+    database::create_pool(&database_url)
+        .await
+        .context("Failed to create database pool")
+}
+
+async fn migrate_up() -> Result<()> {
+    let pool = connect().await?;
+    let applied = migrations::run(&pool).await?;
+
+    if applied == 0 {
+        println!("Database is up to date");
+    } else {
+        println!("Applied {} migration(s)", applied);
+    }
+
+    Ok(())
+}
+
+async fn migrate_status() -> Result<()> {
+    let pool = connect().await?;
+    let statuses = migrations::status(&pool).await?;
+
+    println!("{:<8} {:<9} DESCRIPTION", "VERSION", "STATE");
+    for migration in &statuses {
+        println!("{:<8} {:<9} {}", format!("{:03}", migration.version), migration.state.name(), migration.description);
+    }
+
+    let pending = statuses.iter().filter(|m| m.state == MigrationState::Pending).count();
+    let broken = statuses.iter()
+        .filter(|m| matches!(m.state, MigrationState::Modified | MigrationState::Failed))
+        .count();
+
+    println!();
+    println!("{} pending, {} needing attention", pending, broken);
+
+    if broken > 0 {
+        bail!("{} migration(s) were modified after being applied or failed", broken);
+    }
+
+    Ok(())
+}
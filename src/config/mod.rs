@@ -2,6 +2,8 @@ use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 use std::env;
 
+use crate::database::models::{Guild, ThreatLevel};
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
     pub discord_token: String,
@@ -10,6 +12,34 @@ pub struct Config {
     pub security: SecurityConfig,
     pub auto_mod: AutoModConfig,
     pub forensics: ForensicsConfig,
+    #[serde(default)]
+    pub behavior: BehaviorConfig,
+    #[serde(default)]
+    pub ml: MlConfig,
+    #[serde(default)]
+    pub lockdown: LockdownConfig,
+    #[serde(default)]
+    pub raid_mode: RaidModeConfig,
+    #[serde(default)]
+    pub scheduler: SchedulerConfig,
+    #[serde(default)]
+    pub backup: BackupConfig,
+    #[serde(default)]
+    pub anti_nuke: AntiNukeConfig,
+    #[serde(default)]
+    pub webhooks: WebhookConfig,
+    #[serde(default)]
+    pub api: ApiConfig,
+    #[serde(default)]
+    pub metrics: MetricsConfig,
+    #[serde(default)]
+    pub health: HealthConfig,
+    #[serde(default)]
+    pub database: DatabaseConfig,
+    #[serde(default)]
+    pub state: StateConfig,
+    #[serde(default)]
+    pub sharding: ShardingConfig,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -21,6 +51,8 @@ pub struct SecurityConfig {
     pub new_account_days: u32,
     pub username_similarity_threshold: f64,
     pub spam_similarity_threshold: f64,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -34,10 +66,266 @@ pub struct AutoModConfig {
     pub message_burst_seconds: u64,
 }
 
+/// Forensic events, resolved incidents and honeypot catches older than
+/// `retention_days` are purged (or moved to the archive tables when `archive`
+/// is set); 0 keeps them forever. Without `detailed_logging` no message
+/// content is stored.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct ForensicsConfig {
     pub retention_days: i64,
     pub detailed_logging: bool,
+    /// Replaces message content with a placeholder in `/kitsune export data`.
+    pub redact_exported_content: bool,
+    pub archive: bool,
+    pub purge_interval_mins: u64,
+    pub purge_batch_size: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BehaviorConfig {
+    pub enabled: bool,
+    pub link_spam_threshold: u32,
+    pub mention_spam_threshold: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MlConfig {
+    pub enabled: bool,
+    pub model_path: String,
+    pub inference_timeout_ms: u32,
+    pub confidence_threshold: f32,
+}
+
+/// Effective configuration for a single guild: the file defaults with the
+/// guild's stored overrides applied on top.
+#[derive(Debug, Clone, Serialize)]
+pub struct GuildConfig {
+    pub security: SecurityConfig,
+    pub auto_mod: AutoModConfig,
+    pub behavior: BehaviorConfig,
+    pub ml: MlConfig,
+    pub alerts: AlertConfig,
+    pub lockdown: LockdownConfig,
+    pub raid_mode: RaidModeConfig,
+    pub backup: BackupConfig,
+    pub anti_nuke: AntiNukeConfig,
+    pub forensics: ForensicsConfig,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AlertConfig {
+    pub alert_channel_id: Option<u64>,
+    pub log_channel_id: Option<u64>,
+    pub report_channel_id: Option<u64>,
+    pub notify_roles: Vec<u64>,
+    #[serde(serialize_with = "serialize_threat_level")]
+    pub notify_level: ThreatLevel,
+}
+
+fn serialize_threat_level<S: serde::Serializer>(level: &ThreatLevel, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(level.as_str())
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LockdownConfig {
+    pub channels: Vec<u64>,
+    pub auto_lift_minutes: u64,
+}
+
+/// Raid mode enters at `elevated_score`/`raid_score` but only leaves once the
+/// raid score has stayed below `exit_score`, so a guild hovering around the
+/// entry threshold doesn't flap between locked and unlocked.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RaidModeConfig {
+    pub elevated_score: f32,
+    pub raid_score: f32,
+    pub exit_score: f32,
+    pub min_dwell_secs: u64,
+    pub cooldown_secs: u64,
+    pub auto_lockdown: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SchedulerConfig {
+    pub poll_interval_secs: u64,
+    pub max_attempts: i32,
+}
+
+/// Automatic backups run every `frequency` (off when unset). Only the newest
+/// `retention` automatic backups are kept; manual backups are never pruned.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BackupConfig {
+    pub frequency: Option<BackupFrequency>,
+    pub retention: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BackupFrequency {
+    Hourly,
+    Daily,
+    Weekly,
+}
+
+impl BackupFrequency {
+    pub fn parse(frequency: &str) -> Option<Self> {
+        match frequency.trim().to_lowercase().as_str() {
+            "hourly" => Some(BackupFrequency::Hourly),
+            "daily" => Some(BackupFrequency::Daily),
+            "weekly" => Some(BackupFrequency::Weekly),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BackupFrequency::Hourly => "hourly",
+            BackupFrequency::Daily => "daily",
+            BackupFrequency::Weekly => "weekly",
+        }
+    }
+
+    pub fn interval(&self) -> chrono::Duration {
+        match self {
+            BackupFrequency::Hourly => chrono::Duration::hours(1),
+            BackupFrequency::Daily => chrono::Duration::days(1),
+            BackupFrequency::Weekly => chrono::Duration::weeks(1),
+        }
+    }
+}
+
+/// Destructive actions a single actor may take within `window_secs` before
+/// Kitsune strips their roles (or bans them, for bots). A threshold of 0
+/// disables tracking for that action.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AntiNukeConfig {
+    pub enabled: bool,
+    pub window_secs: u64,
+    pub channel_delete: u32,
+    pub role_delete: u32,
+    pub permission_escalation: u32,
+    pub ban: u32,
+    pub kick: u32,
+    pub webhook_create: u32,
+}
+
+impl AntiNukeConfig {
+    pub fn threshold_mut(&mut self, action: &str) -> Option<&mut u32> {
+        match action {
+            "channel_delete" => Some(&mut self.channel_delete),
+            "role_delete" => Some(&mut self.role_delete),
+            "permission_escalation" => Some(&mut self.permission_escalation),
+            "ban" => Some(&mut self.ban),
+            "kick" => Some(&mut self.kick),
+            "webhook_create" => Some(&mut self.webhook_create),
+            _ => None,
+        }
+    }
+}
+
+/// Failed webhook deliveries are retried after `base_backoff_secs`, doubling
+/// on every attempt, until `max_attempts` is reached. Up to `concurrency`
+/// deliveries are sent at once. Webhooks may only point at public addresses
+/// unless `allow_private_targets` is set, for setups that deliver to a
+/// receiver on the same host or network.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WebhookConfig {
+    pub poll_interval_secs: u64,
+    pub max_attempts: i32,
+    pub timeout_secs: u64,
+    pub base_backoff_secs: i64,
+    pub concurrency: usize,
+    pub allow_private_targets: bool,
+}
+
+/// Embedded REST API. Off unless enabled; requests authenticate with per-guild
+/// tokens created through `/admin api token_create`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ApiConfig {
+    pub enabled: bool,
+    pub bind_address: String,
+    pub max_page_size: i64,
+}
+
+/// Prometheus endpoint, served on its own address so it can stay internal
+/// while the REST API is exposed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MetricsConfig {
+    pub enabled: bool,
+    pub bind_address: String,
+    pub path: String,
+}
+
+/// Liveness and readiness probes for the container orchestrator.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HealthConfig {
+    pub enabled: bool,
+    pub bind_address: String,
+    pub check_timeout_secs: u64,
+}
+
+/// Schema handling at startup. The connection itself comes from `DATABASE_URL`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DatabaseConfig {
+    pub auto_migrate: bool,
+}
+
+/// Where the detectors keep join windows, message histories and honeypot
+/// catches. `redis` shares them between processes and keeps them across
+/// restarts.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StateConfig {
+    pub backend: StateBackendKind,
+    pub key_prefix: String,
+    pub message_history_ttl_secs: u64,
+    pub catch_ttl_secs: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StateBackendKind {
+    Memory,
+    Redis,
+}
+
+/// Which gateway shards this process runs. `range` runs
+/// `first_shard..=last_shard` of `total_shards`, one range per process when
+/// clustering.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ShardingConfig {
+    pub mode: ShardingMode,
+    pub total_shards: u32,
+    pub first_shard: u32,
+    pub last_shard: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ShardingMode {
+    /// As many shards as Discord recommends, all in this process.
+    Auto,
+    /// `total_shards` shards, all in this process.
+    Fixed,
+    Range,
+}
+
+fn default_enabled() -> bool {
+    true
 }
 
 impl Default for SecurityConfig {
@@ -50,6 +338,7 @@ impl Default for SecurityConfig {
             new_account_days: 7,
             username_similarity_threshold: 0.85,
             spam_similarity_threshold: 0.80,
+            enabled: true,
         }
     }
 }
@@ -73,6 +362,159 @@ impl Default for ForensicsConfig {
         Self {
             retention_days: 90,
             detailed_logging: true,
+            redact_exported_content: false,
+            archive: false,
+            purge_interval_mins: 60,
+            purge_batch_size: 1000,
+        }
+    }
+}
+
+impl Default for BehaviorConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            link_spam_threshold: 5,
+            mention_spam_threshold: 5,
+        }
+    }
+}
+
+impl Default for MlConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            model_path: "./models/threat_detector.onnx".to_string(),
+            inference_timeout_ms: 1000,
+            confidence_threshold: 0.75,
+        }
+    }
+}
+
+impl Default for LockdownConfig {
+    fn default() -> Self {
+        Self {
+            channels: Vec::new(),
+            auto_lift_minutes: 60,
+        }
+    }
+}
+
+impl Default for RaidModeConfig {
+    fn default() -> Self {
+        Self {
+            elevated_score: 0.3,
+            raid_score: 0.6,
+            exit_score: 0.2,
+            min_dwell_secs: 120,
+            cooldown_secs: 600,
+            auto_lockdown: true,
+        }
+    }
+}
+
+impl Default for SchedulerConfig {
+    fn default() -> Self {
+        Self {
+            poll_interval_secs: 5,
+            max_attempts: 5,
+        }
+    }
+}
+
+impl Default for BackupConfig {
+    fn default() -> Self {
+        Self {
+            frequency: None,
+            retention: 10,
+        }
+    }
+}
+
+impl Default for AntiNukeConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            window_secs: 60,
+            channel_delete: 3,
+            role_delete: 3,
+            permission_escalation: 3,
+            ban: 5,
+            kick: 5,
+            webhook_create: 5,
+        }
+    }
+}
+
+impl Default for WebhookConfig {
+    fn default() -> Self {
+        Self {
+            poll_interval_secs: 5,
+            max_attempts: 8,
+            timeout_secs: 10,
+            base_backoff_secs: 30,
+            concurrency: 4,
+            allow_private_targets: false,
+        }
+    }
+}
+
+impl Default for ApiConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            bind_address: "127.0.0.1:8080".to_string(),
+            max_page_size: 100,
+        }
+    }
+}
+
+impl Default for MetricsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            bind_address: "127.0.0.1:9100".to_string(),
+            path: "/metrics".to_string(),
+        }
+    }
+}
+
+impl Default for HealthConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            bind_address: "127.0.0.1:8081".to_string(),
+            check_timeout_secs: 3,
+        }
+    }
+}
+
+impl Default for StateConfig {
+    fn default() -> Self {
+        Self {
+            backend: StateBackendKind::Memory,
+            key_prefix: "kitsune".to_string(),
+            message_history_ttl_secs: 3600,
+            catch_ttl_secs: 86400,
+        }
+    }
+}
+
+impl Default for ShardingConfig {
+    fn default() -> Self {
+        Self {
+            mode: ShardingMode::Auto,
+            total_shards: 1,
+            first_shard: 0,
+            last_shard: 0,
+        }
+    }
+}
+
+impl Default for DatabaseConfig {
+    fn default() -> Self {
+        Self {
+            auto_migrate: true,
         }
     }
 }
@@ -97,6 +539,19 @@ impl Config {
         }
     }
 
+    /// Settings for commands that never connect to Discord or the database:
+    /// the file at `CONFIG_PATH` if there is one, without requiring any of the
+    /// connection variables.
+    pub fn offline() -> Result<Self> {
+        let config_path = env::var("CONFIG_PATH").unwrap_or_else(|_| "config.toml".to_string());
+
+        if std::path::Path::new(&config_path).exists() {
+            Self::from_file(&config_path, String::new(), String::new(), String::new())
+        } else {
+            Ok(Self::with_defaults(String::new(), String::new(), String::new()))
+        }
+    }
+
     fn from_file(path: &str, discord_token: String, database_url: String, redis_url: String) -> Result<Self> {
         let contents = std::fs::read_to_string(path)
             .context(format!("Failed to read config file: {}", path))?;
@@ -111,7 +566,7 @@ impl Config {
         Ok(config)
     }
 
-    fn with_defaults(discord_token: String, database_url: String, redis_url: String) -> Self {
+    pub(crate) fn with_defaults(discord_token: String, database_url: String, redis_url: String) -> Self {
         Self {
             discord_token,
             database_url,
@@ -119,6 +574,158 @@ impl Config {
             security: SecurityConfig::default(),
             auto_mod: AutoModConfig::default(),
             forensics: ForensicsConfig::default(),
+            behavior: BehaviorConfig::default(),
+            ml: MlConfig::default(),
+            lockdown: LockdownConfig::default(),
+            raid_mode: RaidModeConfig::default(),
+            scheduler: SchedulerConfig::default(),
+            backup: BackupConfig::default(),
+            anti_nuke: AntiNukeConfig::default(),
+            webhooks: WebhookConfig::default(),
+            api: ApiConfig::default(),
+            metrics: MetricsConfig::default(),
+            health: HealthConfig::default(),
+            database: DatabaseConfig::default(),
+            state: StateConfig::default(),
+            sharding: ShardingConfig::default(),
+        }
+    }
+
+    pub fn for_guild(&self, guild: Option<&Guild>) -> GuildConfig {
+        let mut effective = GuildConfig {
+            security: self.security.clone(),
+            auto_mod: self.auto_mod.clone(),
+            behavior: self.behavior.clone(),
+            ml: self.ml.clone(),
+            alerts: AlertConfig {
+                alert_channel_id: None,
+                log_channel_id: None,
+                report_channel_id: None,
+                notify_roles: Vec::new(),
+                notify_level: ThreatLevel::High,
+            },
+            lockdown: self.lockdown.clone(),
+            raid_mode: self.raid_mode.clone(),
+            backup: self.backup.clone(),
+            anti_nuke: self.anti_nuke.clone(),
+            forensics: self.forensics.clone(),
+        };
+
+        let guild = match guild {
+            Some(g) => g,
+            None => return effective,
+        };
+
+        if let Some(threshold) = guild.raid_threshold_5s {
+            effective.security.raid_threshold_5s = threshold.max(1) as u32;
+        }
+        if let Some(threshold) = guild.raid_threshold_30s {
+            effective.security.raid_threshold_30s = threshold.max(1) as u32;
+        }
+        if let Some(threshold) = guild.raid_threshold_1m {
+            effective.security.raid_threshold_1m = threshold.max(1) as u32;
+        }
+        if let Some(threshold) = guild.raid_threshold_5m {
+            effective.security.raid_threshold_5m = threshold.max(1) as u32;
+        }
+        if let Some(days) = guild.new_account_days {
+            effective.security.new_account_days = days.max(0) as u32;
+        }
+        if let Some(enabled) = guild.auto_mod_enabled {
+            effective.auto_mod.enabled = enabled;
+        }
+
+        let settings = guild.settings().clamped();
+
+        if let Some(enabled) = settings.raid_enabled {
+            effective.security.enabled = enabled;
+        }
+        if let Some(threshold) = settings.username_similarity_threshold {
+            effective.security.username_similarity_threshold = threshold;
         }
+        if let Some(threshold) = settings.spam_similarity_threshold {
+            effective.security.spam_similarity_threshold = threshold;
+        }
+        if let Some(count) = settings.message_burst_count {
+            effective.auto_mod.message_burst_count = count.max(1);
+        }
+        if let Some(enabled) = settings.behavior_enabled {
+            effective.behavior.enabled = enabled;
+        }
+        if let Some(threshold) = settings.link_spam_threshold {
+            effective.behavior.link_spam_threshold = threshold.max(1);
+        }
+        if let Some(threshold) = settings.mention_spam_threshold {
+            effective.behavior.mention_spam_threshold = threshold.max(1);
+        }
+        if let Some(threshold) = settings.low_threat_threshold {
+            effective.auto_mod.low_threat_threshold = threshold;
+        }
+        if let Some(threshold) = settings.medium_threat_threshold {
+            effective.auto_mod.medium_threat_threshold = threshold;
+        }
+        if let Some(threshold) = settings.high_threat_threshold {
+            effective.auto_mod.high_threat_threshold = threshold;
+        }
+        if let Some(threshold) = settings.critical_threat_threshold {
+            effective.auto_mod.critical_threat_threshold = threshold;
+        }
+        if let Some(enabled) = settings.ml_enabled {
+            effective.ml.enabled = enabled;
+        }
+        if let Some(path) = settings.ml_model_path {
+            effective.ml.model_path = path;
+        }
+        if let Some(timeout) = settings.ml_inference_timeout_ms {
+            effective.ml.inference_timeout_ms = timeout;
+        }
+        if let Some(threshold) = settings.ml_confidence_threshold {
+            effective.ml.confidence_threshold = threshold;
+        }
+
+        effective.alerts.alert_channel_id = settings.alert_channel_id.map(|id| id as u64);
+        effective.alerts.log_channel_id = settings.log_channel_id.map(|id| id as u64);
+        effective.alerts.report_channel_id = settings.report_channel_id.map(|id| id as u64);
+        effective.alerts.notify_roles = settings.notify_roles.iter().map(|id| *id as u64).collect();
+        if let Some(level) = settings.notify_level.as_deref().and_then(ThreatLevel::parse) {
+            effective.alerts.notify_level = level;
+        }
+        if let Some(secs) = settings.raid_cooldown_secs {
+            effective.raid_mode.cooldown_secs = secs;
+        }
+        if let Some(enabled) = settings.raid_auto_lockdown {
+            effective.raid_mode.auto_lockdown = enabled;
+        }
+        if !settings.lockdown_channels.is_empty() {
+            effective.lockdown.channels = settings.lockdown_channels.iter().map(|id| *id as u64).collect();
+        }
+        if let Some(frequency) = settings.backup_frequency.as_deref() {
+            effective.backup.frequency = BackupFrequency::parse(frequency);
+        }
+        if let Some(retention) = settings.backup_retention {
+            effective.backup.retention = retention.max(1);
+        }
+        if let Some(enabled) = settings.anti_nuke_enabled {
+            effective.anti_nuke.enabled = enabled;
+        }
+        if let Some(secs) = settings.anti_nuke_window_secs {
+            effective.anti_nuke.window_secs = secs.max(1);
+        }
+        for (action, threshold) in &settings.anti_nuke_thresholds {
+            if let Some(slot) = effective.anti_nuke.threshold_mut(action) {
+                *slot = *threshold;
+            }
+        }
+        if let Some(days) = settings.forensics_retention_days {
+            effective.forensics.retention_days = days.max(0);
+        }
+        if let Some(enabled) = settings.detailed_logging {
+            effective.forensics.detailed_logging = enabled;
+        }
+        if let Some(redact) = settings.redact_exported_content {
+            effective.forensics.redact_exported_content = redact;
+        }
+
+        effective
     }
 }
@@ -0,0 +1,100 @@
+use anyhow::{Context, Result};
+use sqlx::migrate::{Migrate, Migrator};
+use sqlx::PgPool;
+use std::collections::HashMap;
+
+/// The files under `migrations/`, embedded at compile time. Applied versions
+/// are tracked in `_sqlx_migrations` together with their checksum.
+static MIGRATOR: Migrator = sqlx::migrate!("./migrations");
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MigrationState {
+    Applied,
+    Pending,
+    /// Applied, but the embedded file no longer matches what ran.
+    Modified,
+    /// Started and failed; needs a manual look before anything else runs.
+    Failed,
+    /// Recorded in the database but not embedded in this binary, usually
+    /// after rolling back to an older release.
+    Unknown,
+}
+
+#[derive(Debug, Clone)]
+pub struct MigrationStatus {
+    pub version: i64,
+    pub description: String,
+    pub state: MigrationState,
+}
+
+impl MigrationState {
+    pub fn name(&self) -> &'static str {
+        match self {
+            MigrationState::Applied => "applied",
+            MigrationState::Pending => "pending",
+            MigrationState::Modified => "modified",
+            MigrationState::Failed => "failed",
+            MigrationState::Unknown => "unknown",
+        }
+    }
+}
+
+/// Applies every pending migration and returns how many ran. Concurrent
+/// instances serialize on a Postgres advisory lock held by the migrator.
+pub async fn run(pool: &PgPool) -> Result<usize> {
+    let pending = status(pool).await?
+        .iter()
+        .filter(|migration| migration.state == MigrationState::Pending)
+        .count();
+
+    MIGRATOR.run(pool).await
+        .context("Failed to apply migrations")?;
+
+    Ok(pending)
+}
+
+/// Compares the embedded migrations with the ones recorded in the database
+/// without changing anything.
+pub async fn status(pool: &PgPool) -> Result<Vec<MigrationStatus>> {
+    let mut conn = pool.acquire().await?;
+
+    let tracked: bool = sqlx::query_scalar("SELECT to_regclass('_sqlx_migrations') IS NOT NULL")
+        .fetch_one(&mut *conn)
+        .await?;
+
+    let (mut applied, dirty) = if tracked {
+        let applied: HashMap<i64, Vec<u8>> = conn.list_applied_migrations().await?
+            .into_iter()
+            .map(|migration| (migration.version, migration.checksum.into_owned()))
+            .collect();
+        (applied, conn.dirty_version().await?)
+    } else {
+        (HashMap::new(), None)
+    };
+
+    let mut statuses: Vec<MigrationStatus> = MIGRATOR.iter()
+        .map(|migration| {
+            let state = match applied.remove(&migration.version) {
+                _ if dirty == Some(migration.version) => MigrationState::Failed,
+                Some(checksum) if checksum == *migration.checksum => MigrationState::Applied,
+                Some(_) => MigrationState::Modified,
+                None => MigrationState::Pending,
+            };
+
+            MigrationStatus {
+                version: migration.version,
+                description: migration.description.to_string(),
+                state,
+            }
+        })
+        .collect();
+
+    statuses.extend(applied.into_keys().map(|version| MigrationStatus {
+        version,
+        description: String::new(),
+        state: MigrationState::Unknown,
+    }));
+    statuses.sort_by_key(|migration| migration.version);
+
+    Ok(statuses)
+}
@@ -2,6 +2,7 @@ use anyhow::Result;
 use sqlx::{PgPool, postgres::PgPoolOptions};
 use std::time::Duration;
 
+pub mod migrations;
 pub mod models;
 pub mod queries;
 
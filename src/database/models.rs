@@ -1,6 +1,8 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
+use std::collections::HashMap;
+use std::ops::RangeInclusive;
 use uuid::Uuid;
 
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
@@ -9,17 +11,198 @@ pub struct Guild {
     pub name: String,
     pub owner_id: i64,
     pub config: JsonValue,
-    pub raid_threshold_5s: i32,
-    pub raid_threshold_30s: i32,
-    pub raid_threshold_1m: i32,
-    pub raid_threshold_5m: i32,
-    pub new_account_days: i32,
-    pub auto_mod_enabled: bool,
+    pub raid_threshold_5s: Option<i32>,
+    pub raid_threshold_30s: Option<i32>,
+    pub raid_threshold_1m: Option<i32>,
+    pub raid_threshold_5m: Option<i32>,
+    pub new_account_days: Option<i32>,
+    pub auto_mod_enabled: Option<bool>,
     pub lockdown_active: bool,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
+impl Guild {
+    pub fn settings(&self) -> GuildSettings {
+        serde_json::from_value(self.config.clone()).unwrap_or_default()
+    }
+}
+
+/// The Kitsune-owned columns of a guild row, as captured in backups.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GuildSettingsBackup {
+    pub config: JsonValue,
+    pub raid_threshold_5s: Option<i32>,
+    pub raid_threshold_30s: Option<i32>,
+    pub raid_threshold_1m: Option<i32>,
+    pub raid_threshold_5m: Option<i32>,
+    pub new_account_days: Option<i32>,
+    pub auto_mod_enabled: Option<bool>,
+}
+
+impl From<&Guild> for GuildSettingsBackup {
+    fn from(guild: &Guild) -> Self {
+        Self {
+            config: guild.config.clone(),
+            raid_threshold_5s: guild.raid_threshold_5s,
+            raid_threshold_30s: guild.raid_threshold_30s,
+            raid_threshold_1m: guild.raid_threshold_1m,
+            raid_threshold_5m: guild.raid_threshold_5m,
+            new_account_days: guild.new_account_days,
+            auto_mod_enabled: guild.auto_mod_enabled,
+        }
+    }
+}
+
+/// Overrides stored in `guilds.config`. Anything left as `None` falls back to
+/// the file configuration.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GuildSettings {
+    pub raid_enabled: Option<bool>,
+    pub username_similarity_threshold: Option<f64>,
+    pub spam_similarity_threshold: Option<f64>,
+    pub message_burst_count: Option<u32>,
+    pub behavior_enabled: Option<bool>,
+    pub link_spam_threshold: Option<u32>,
+    pub mention_spam_threshold: Option<u32>,
+    pub ml_enabled: Option<bool>,
+    pub ml_model_path: Option<String>,
+    pub ml_inference_timeout_ms: Option<u32>,
+    pub ml_confidence_threshold: Option<f32>,
+    pub low_threat_threshold: Option<f32>,
+    pub medium_threat_threshold: Option<f32>,
+    pub high_threat_threshold: Option<f32>,
+    pub critical_threat_threshold: Option<f32>,
+    pub alert_channel_id: Option<i64>,
+    pub log_channel_id: Option<i64>,
+    pub report_channel_id: Option<i64>,
+    pub notify_roles: Vec<i64>,
+    pub notify_level: Option<String>,
+    pub lockdown_channels: Vec<i64>,
+    pub raid_cooldown_secs: Option<u64>,
+    pub raid_auto_lockdown: Option<bool>,
+    /// `hourly`, `daily`, `weekly` or `off`
+    pub backup_frequency: Option<String>,
+    pub backup_retention: Option<u32>,
+    pub anti_nuke_enabled: Option<bool>,
+    pub anti_nuke_window_secs: Option<u64>,
+    pub anti_nuke_thresholds: HashMap<String, u32>,
+    /// Accounts anti-nuke leaves alone besides the owner. Separate from the
+    /// whitelist, which staff are usually on.
+    pub anti_nuke_trusted: Vec<i64>,
+    /// 0 keeps forensic data forever.
+    pub forensics_retention_days: Option<i64>,
+    pub detailed_logging: Option<bool>,
+    pub redact_exported_content: Option<bool>,
+}
+
+const UNIT: RangeInclusive<f64> = 0.0..=1.0;
+const UNIT_F32: RangeInclusive<f32> = 0.0..=1.0;
+const SPAM_COUNT: RangeInclusive<u32> = 1..=1000;
+const ML_TIMEOUT_MS: RangeInclusive<u32> = 1..=60_000;
+const RAID_COOLDOWN_SECS: RangeInclusive<u64> = 60..=86_400;
+const BACKUP_RETENTION: RangeInclusive<u32> = 1..=1000;
+const ANTI_NUKE_WINDOW_SECS: RangeInclusive<u64> = 10..=3600;
+const RETENTION_DAYS: RangeInclusive<i64> = 0..=3650;
+
+impl GuildSettings {
+    /// Checks every override against the bounds the slash commands enforce,
+    /// for settings written through the API.
+    pub fn validate(&self) -> Result<(), String> {
+        let mut errors = Vec::new();
+
+        check(&mut errors, "username_similarity_threshold", self.username_similarity_threshold, UNIT);
+        check(&mut errors, "spam_similarity_threshold", self.spam_similarity_threshold, UNIT);
+        check(&mut errors, "message_burst_count", self.message_burst_count, SPAM_COUNT);
+        check(&mut errors, "link_spam_threshold", self.link_spam_threshold, SPAM_COUNT);
+        check(&mut errors, "mention_spam_threshold", self.mention_spam_threshold, SPAM_COUNT);
+        check(&mut errors, "ml_inference_timeout_ms", self.ml_inference_timeout_ms, ML_TIMEOUT_MS);
+        check(&mut errors, "ml_confidence_threshold", self.ml_confidence_threshold, UNIT_F32);
+        check(&mut errors, "low_threat_threshold", self.low_threat_threshold, UNIT_F32);
+        check(&mut errors, "medium_threat_threshold", self.medium_threat_threshold, UNIT_F32);
+        check(&mut errors, "high_threat_threshold", self.high_threat_threshold, UNIT_F32);
+        check(&mut errors, "critical_threat_threshold", self.critical_threat_threshold, UNIT_F32);
+        check(&mut errors, "raid_cooldown_secs", self.raid_cooldown_secs, RAID_COOLDOWN_SECS);
+        check(&mut errors, "backup_retention", self.backup_retention, BACKUP_RETENTION);
+        check(&mut errors, "anti_nuke_window_secs", self.anti_nuke_window_secs, ANTI_NUKE_WINDOW_SECS);
+        check(&mut errors, "forensics_retention_days", self.forensics_retention_days, RETENTION_DAYS);
+
+        for (key, id) in [
+            ("alert_channel_id", self.alert_channel_id),
+            ("log_channel_id", self.log_channel_id),
+            ("report_channel_id", self.report_channel_id),
+        ] {
+            if id.is_some_and(|id| id <= 0) {
+                errors.push(format!("`{}` must be a Discord ID", key));
+            }
+        }
+        for (key, ids) in [
+            ("notify_roles", &self.notify_roles),
+            ("lockdown_channels", &self.lockdown_channels),
+            ("anti_nuke_trusted", &self.anti_nuke_trusted),
+        ] {
+            if ids.iter().any(|id| *id <= 0) {
+                errors.push(format!("`{}` must only contain Discord IDs", key));
+            }
+        }
+        if self.notify_level.as_deref().is_some_and(|level| ThreatLevel::parse(level).is_none()) {
+            errors.push("`notify_level` must be one of: low, medium, high, critical".to_string());
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors.join("; "))
+        }
+    }
+
+    /// Brings stored overrides inside the same bounds, so values saved before
+    /// they were validated can't break detection or panic a handler.
+    pub fn clamped(mut self) -> Self {
+        clamp(&mut self.username_similarity_threshold, UNIT);
+        clamp(&mut self.spam_similarity_threshold, UNIT);
+        clamp(&mut self.message_burst_count, SPAM_COUNT);
+        clamp(&mut self.link_spam_threshold, SPAM_COUNT);
+        clamp(&mut self.mention_spam_threshold, SPAM_COUNT);
+        clamp(&mut self.ml_inference_timeout_ms, ML_TIMEOUT_MS);
+        clamp(&mut self.ml_confidence_threshold, UNIT_F32);
+        clamp(&mut self.low_threat_threshold, UNIT_F32);
+        clamp(&mut self.medium_threat_threshold, UNIT_F32);
+        clamp(&mut self.high_threat_threshold, UNIT_F32);
+        clamp(&mut self.critical_threat_threshold, UNIT_F32);
+        clamp(&mut self.raid_cooldown_secs, RAID_COOLDOWN_SECS);
+        clamp(&mut self.backup_retention, BACKUP_RETENTION);
+        clamp(&mut self.anti_nuke_window_secs, ANTI_NUKE_WINDOW_SECS);
+        clamp(&mut self.forensics_retention_days, RETENTION_DAYS);
+
+        for id in [&mut self.alert_channel_id, &mut self.log_channel_id, &mut self.report_channel_id] {
+            *id = id.filter(|id| *id > 0);
+        }
+        for ids in [&mut self.notify_roles, &mut self.lockdown_channels, &mut self.anti_nuke_trusted] {
+            ids.retain(|id| *id > 0);
+        }
+
+        self
+    }
+}
+
+fn check<T: PartialOrd + Copy + std::fmt::Display>(errors: &mut Vec<String>, key: &str, value: Option<T>, range: RangeInclusive<T>) {
+    if value.is_some_and(|value| !range.contains(&value)) {
+        errors.push(format!("`{}` must be between {} and {}", key, range.start(), range.end()));
+    }
+}
+
+fn clamp<T: PartialOrd + Copy>(value: &mut Option<T>, range: RangeInclusive<T>) {
+    if let Some(v) = value.as_mut() {
+        if *v < *range.start() {
+            *v = *range.start();
+        } else if *v > *range.end() {
+            *v = *range.end();
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
 pub struct User {
     pub user_id: i64,
@@ -83,6 +266,49 @@ pub struct ForensicEvent {
     pub created_at: DateTime<Utc>,
 }
 
+/// Optional filters for incident listings. `before` pages backwards through
+/// results by creation time; with `before_id` the pair is the `(created_at,
+/// id)` of the last row already seen, so rows sharing a timestamp aren't
+/// skipped.
+#[derive(Debug, Clone, Default)]
+pub struct IncidentFilter {
+    pub incident_type: Option<String>,
+    pub severity: Option<String>,
+    pub user_id: Option<i64>,
+    pub resolved: Option<bool>,
+    pub since: Option<DateTime<Utc>>,
+    pub before: Option<DateTime<Utc>>,
+    pub before_id: Option<Uuid>,
+    pub limit: i64,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ForensicFilter {
+    /// Full-text query over the content, also matched against tags.
+    pub text: Option<String>,
+    pub event_type: Option<String>,
+    pub user_id: Option<i64>,
+    pub tag: Option<String>,
+    pub min_score: Option<f32>,
+    pub max_score: Option<f32>,
+    pub since: Option<DateTime<Utc>>,
+    pub before: Option<DateTime<Utc>>,
+    pub before_id: Option<Uuid>,
+    pub limit: i64,
+    pub offset: i64,
+}
+
+/// Totals of a user's recorded activity in a period, counted in the database
+/// rather than from a capped timeline.
+#[derive(Debug, Clone, Default)]
+pub struct UserActivity {
+    pub joins: i64,
+    pub messages: i64,
+    pub incidents: i64,
+    pub catches: i64,
+    pub peak_score: f32,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
 pub struct HoneypotCatch {
     pub id: Uuid,
@@ -94,6 +320,18 @@ pub struct HoneypotCatch {
     pub created_at: DateTime<Utc>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct HoneypotTrap {
+    pub id: i32,
+    pub guild_id: i64,
+    pub trap_type: String,
+    pub target: String,
+    pub label: Option<String>,
+    pub created_by: i64,
+    pub created_at: DateTime<Utc>,
+    pub managed: bool,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
 pub struct WhitelistedUser {
     pub id: i32,
@@ -104,7 +342,104 @@ pub struct WhitelistedUser {
     pub created_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct BlacklistedUser {
+    pub id: i32,
+    pub guild_id: i64,
+    pub user_id: i64,
+    pub reason: Option<String>,
+    pub action: String,
+    pub added_by: i64,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct LockdownSnapshot {
+    pub guild_id: i64,
+    pub channel_id: i64,
+    pub had_overwrite: bool,
+    pub allow_bits: i64,
+    pub deny_bits: i64,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Persisted raid mode of a guild outside `Normal`.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct RaidModeRecord {
+    pub guild_id: i64,
+    pub mode: String,
+    pub since: DateTime<Utc>,
+    pub auto_locked: bool,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct ScheduledJob {
+    pub id: Uuid,
+    pub guild_id: i64,
+    pub job_type: String,
+    pub payload: JsonValue,
+    pub run_at: DateTime<Utc>,
+    pub status: String,
+    pub attempts: i32,
+    pub last_error: Option<String>,
+    pub created_by: Option<i64>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct GuildBackup {
+    pub id: Uuid,
+    pub guild_id: i64,
+    pub version: i32,
+    pub label: Option<String>,
+    pub data: JsonValue,
+    pub created_by: Option<i64>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct Webhook {
+    pub id: i32,
+    pub guild_id: i64,
+    pub url: String,
+    pub secret: String,
+    pub events: Vec<String>,
+    pub created_by: i64,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct WebhookDelivery {
+    pub id: Uuid,
+    pub webhook_id: i32,
+    pub guild_id: i64,
+    pub event: String,
+    pub payload: JsonValue,
+    pub status: String,
+    pub attempts: i32,
+    pub next_attempt_at: DateTime<Utc>,
+    pub last_status_code: Option<i32>,
+    pub last_error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub delivered_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct ApiToken {
+    pub id: i32,
+    pub guild_id: i64,
+    pub name: String,
+    pub token_hash: String,
+    pub created_by: i64,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub enum ThreatLevel {
     Low,
     Medium,
@@ -113,15 +448,13 @@ pub enum ThreatLevel {
 }
 
 impl ThreatLevel {
-    pub fn from_score(score: f32) -> Self {
-        if score >= 0.95 {
-            ThreatLevel::Critical
-        } else if score >= 0.8 {
-            ThreatLevel::High
-        } else if score >= 0.6 {
-            ThreatLevel::Medium
-        } else {
-            ThreatLevel::Low
+    pub fn parse(level: &str) -> Option<Self> {
+        match level.to_lowercase().as_str() {
+            "low" => Some(ThreatLevel::Low),
+            "medium" => Some(ThreatLevel::Medium),
+            "high" => Some(ThreatLevel::High),
+            "critical" => Some(ThreatLevel::Critical),
+            _ => None,
         }
     }
 
@@ -143,3 +476,49 @@ impl ThreatLevel {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(value: serde_json::Value) -> GuildSettings {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn out_of_range_settings_are_rejected() {
+        for value in [
+            serde_json::json!({"forensics_retention_days": 1_000_000_000_000_000i64}),
+            serde_json::json!({"anti_nuke_window_secs": 10_000_000_000_000_000u64}),
+            serde_json::json!({"raid_cooldown_secs": 0}),
+            serde_json::json!({"alert_channel_id": 0}),
+            serde_json::json!({"notify_roles": [1, 0]}),
+            serde_json::json!({"message_burst_count": 0}),
+            serde_json::json!({"spam_similarity_threshold": 1.5}),
+            serde_json::json!({"notify_level": "urgent"}),
+        ] {
+            assert!(settings(value.clone()).validate().is_err(), "{} should be rejected", value);
+        }
+
+        let valid = settings(serde_json::json!({"raid_cooldown_secs": 600, "notify_roles": [42], "notify_level": "high"}));
+        assert!(valid.validate().is_ok());
+    }
+
+    #[test]
+    fn stored_settings_are_clamped() {
+        let clamped = settings(serde_json::json!({
+            "forensics_retention_days": 1_000_000_000_000_000i64,
+            "anti_nuke_window_secs": 10_000_000_000_000_000u64,
+            "link_spam_threshold": 0,
+            "log_channel_id": 0,
+            "lockdown_channels": [0, 5],
+        })).clamped();
+
+        assert!(clamped.validate().is_ok());
+        assert_eq!(clamped.forensics_retention_days, Some(3650));
+        assert_eq!(clamped.anti_nuke_window_secs, Some(3600));
+        assert_eq!(clamped.link_spam_threshold, Some(1));
+        assert_eq!(clamped.log_channel_id, None);
+        assert_eq!(clamped.lockdown_channels, vec![5]);
+    }
+}
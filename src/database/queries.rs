@@ -1,9 +1,13 @@
 use anyhow::Result;
+use chrono::{DateTime, Utc};
 use sqlx::PgPool;
+use uuid::Uuid;
 
 use super::models::*;
+use crate::metrics;
 
 pub async fn upsert_guild(pool: &PgPool, guild_id: i64, name: &str, owner_id: i64) -> Result<()> {
+    let _timer = metrics::time_query("upsert_guild");
     sqlx::query!(
         r#"
         INSERT INTO guilds (guild_id, name, owner_id)
@@ -22,6 +26,7 @@ pub async fn upsert_guild(pool: &PgPool, guild_id: i64, name: &str, owner_id: i6
 }
 
 pub async fn get_guild(pool: &PgPool, guild_id: i64) -> Result<Option<Guild>> {
+    let _timer = metrics::time_query("get_guild");
     let guild = sqlx::query_as!(
         Guild,
         r#"SELECT * FROM guilds WHERE guild_id = $1"#,
@@ -34,6 +39,7 @@ pub async fn get_guild(pool: &PgPool, guild_id: i64) -> Result<Option<Guild>> {
 }
 
 pub async fn upsert_user(pool: &PgPool, user_id: i64, username: &str, discriminator: Option<&str>) -> Result<()> {
+    let _timer = metrics::time_query("upsert_user");
     sqlx::query!(
         r#"
         INSERT INTO users (user_id, username, discriminator, last_seen)
@@ -52,6 +58,7 @@ pub async fn upsert_user(pool: &PgPool, user_id: i64, username: &str, discrimina
 }
 
 pub async fn get_user(pool: &PgPool, user_id: i64) -> Result<Option<User>> {
+    let _timer = metrics::time_query("get_user");
     let user = sqlx::query_as!(
         User,
         r#"SELECT * FROM users WHERE user_id = $1"#,
@@ -68,6 +75,7 @@ pub async fn get_or_create_behavior_profile(
     guild_id: i64,
     user_id: i64
 ) -> Result<BehaviorProfile> {
+    let _timer = metrics::time_query("get_or_create_behavior_profile");
     let profile = sqlx::query_as!(
         BehaviorProfile,
         r#"
@@ -92,6 +100,7 @@ pub async fn update_behavior_profile(
     user_id: i64,
     features: serde_json::Value
 ) -> Result<()> {
+    let _timer = metrics::time_query("update_behavior_profile");
     sqlx::query!(
         r#"
         UPDATE behavior_profiles
@@ -118,6 +127,7 @@ pub async fn create_incident(
     evidence: serde_json::Value,
     action_taken: Option<&str>
 ) -> Result<Incident> {
+    let _timer = metrics::time_query("create_incident");
     let incident = sqlx::query_as!(
         Incident,
         r#"
@@ -149,6 +159,7 @@ pub async fn log_forensic_event(
     threat_score: f32,
     tags: Vec<String>
 ) -> Result<()> {
+    let _timer = metrics::time_query("log_forensic_event");
     sqlx::query!(
         r#"
         INSERT INTO forensic_events (guild_id, user_id, event_type, content, metadata, threat_score, tags)
@@ -176,6 +187,7 @@ pub async fn record_honeypot_catch(
     trap_name: &str,
     metadata: serde_json::Value
 ) -> Result<()> {
+    let _timer = metrics::time_query("record_honeypot_catch");
     sqlx::query!(
         r#"
         INSERT INTO honeypot_catches (guild_id, user_id, trap_type, trap_name, metadata)
@@ -193,7 +205,153 @@ pub async fn record_honeypot_catch(
     Ok(())
 }
 
+pub async fn get_honeypot_catches(pool: &PgPool, guild_id: i64, since: DateTime<Utc>, limit: i64) -> Result<Vec<HoneypotCatch>> {
+    let _timer = metrics::time_query("get_honeypot_catches");
+    let catches = sqlx::query_as!(
+        HoneypotCatch,
+        r#"
+        SELECT * FROM honeypot_catches
+        WHERE guild_id = $1 AND created_at >= $2
+        ORDER BY created_at DESC
+        LIMIT $3
+        "#,
+        guild_id,
+        since,
+        limit
+    )
+    .fetch_all(pool)
+    .await?;
+    
+    Ok(catches)
+}
+
+pub async fn get_user_honeypot_catches(
+    pool: &PgPool,
+    guild_id: i64,
+    user_id: i64,
+    since: DateTime<Utc>,
+    limit: i64
+) -> Result<Vec<HoneypotCatch>> {
+    let _timer = metrics::time_query("get_user_honeypot_catches");
+    let catches = sqlx::query_as!(
+        HoneypotCatch,
+        r#"
+        SELECT * FROM honeypot_catches
+        WHERE guild_id = $1 AND user_id = $2 AND created_at >= $3
+        ORDER BY created_at DESC
+        LIMIT $4
+        "#,
+        guild_id,
+        user_id,
+        since,
+        limit
+    )
+    .fetch_all(pool)
+    .await?;
+    
+    Ok(catches)
+}
+
+pub async fn count_honeypot_catches(pool: &PgPool, guild_id: i64, since: DateTime<Utc>) -> Result<i64> {
+    let _timer = metrics::time_query("count_honeypot_catches");
+    let result = sqlx::query!(
+        r#"
+        SELECT COUNT(*) as "count!"
+        FROM honeypot_catches
+        WHERE guild_id = $1 AND created_at >= $2
+        "#,
+        guild_id,
+        since
+    )
+    .fetch_one(pool)
+    .await?;
+    
+    Ok(result.count)
+}
+
+pub async fn clear_honeypot_catches(pool: &PgPool, guild_id: i64) -> Result<u64> {
+    let _timer = metrics::time_query("clear_honeypot_catches");
+    let result = sqlx::query!(
+        r#"
+        DELETE FROM honeypot_catches
+        WHERE guild_id = $1
+        "#,
+        guild_id
+    )
+    .execute(pool)
+    .await?;
+    
+    Ok(result.rows_affected())
+}
+
+pub async fn add_honeypot_trap(
+    pool: &PgPool,
+    guild_id: i64,
+    trap_type: &str,
+    target: &str,
+    label: Option<&str>,
+    created_by: i64,
+    managed: bool
+) -> Result<HoneypotTrap> {
+    let _timer = metrics::time_query("add_honeypot_trap");
+    let trap = sqlx::query_as!(
+        HoneypotTrap,
+        r#"
+        INSERT INTO honeypot_traps (guild_id, trap_type, target, label, created_by, managed)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        ON CONFLICT (guild_id, trap_type, target) DO UPDATE
+        SET label = EXCLUDED.label,
+            managed = EXCLUDED.managed
+        RETURNING *
+        "#,
+        guild_id,
+        trap_type,
+        target,
+        label,
+        created_by,
+        managed
+    )
+    .fetch_one(pool)
+    .await?;
+    
+    Ok(trap)
+}
+
+pub async fn remove_honeypot_trap(pool: &PgPool, guild_id: i64, trap_id: i32) -> Result<bool> {
+    let _timer = metrics::time_query("remove_honeypot_trap");
+    let result = sqlx::query!(
+        r#"
+        DELETE FROM honeypot_traps
+        WHERE guild_id = $1 AND id = $2
+        "#,
+        guild_id,
+        trap_id
+    )
+    .execute(pool)
+    .await?;
+    
+    Ok(result.rows_affected() > 0)
+}
+
+pub async fn get_honeypot_traps(pool: &PgPool, guild_id: i64) -> Result<Vec<HoneypotTrap>> {
+    let _timer = metrics::time_query("get_honeypot_traps");
+    let traps = sqlx::query_as!(
+        HoneypotTrap,
+        r#"
+        SELECT * FROM honeypot_traps
+        WHERE guild_id = $1
+        ORDER BY id
+        "#,
+        guild_id
+    )
+    .fetch_all(pool)
+    .await?;
+    
+    Ok(traps)
+}
+
 pub async fn is_whitelisted(pool: &PgPool, guild_id: i64, user_id: i64) -> Result<bool> {
+    let _timer = metrics::time_query("is_whitelisted");
     let result = sqlx::query!(
         r#"
         SELECT EXISTS(
@@ -217,6 +375,7 @@ pub async fn add_to_whitelist(
     reason: Option<&str>,
     added_by: i64
 ) -> Result<()> {
+    let _timer = metrics::time_query("add_to_whitelist");
     sqlx::query!(
         r#"
         INSERT INTO whitelisted_users (guild_id, user_id, reason, added_by)
@@ -235,6 +394,7 @@ pub async fn add_to_whitelist(
 }
 
 pub async fn get_recent_incidents(pool: &PgPool, guild_id: i64, limit: i64) -> Result<Vec<Incident>> {
+    let _timer = metrics::time_query("get_recent_incidents");
     let incidents = sqlx::query_as!(
         Incident,
         r#"
@@ -253,6 +413,7 @@ pub async fn get_recent_incidents(pool: &PgPool, guild_id: i64, limit: i64) -> R
 }
 
 pub async fn get_user_incidents(pool: &PgPool, user_id: i64, limit: i64) -> Result<Vec<Incident>> {
+    let _timer = metrics::time_query("get_user_incidents");
     let incidents = sqlx::query_as!(
         Incident,
         r#"
@@ -270,7 +431,114 @@ pub async fn get_user_incidents(pool: &PgPool, user_id: i64, limit: i64) -> Resu
     Ok(incidents)
 }
 
+/// Incidents matching `filter`, newest first. Backs the forensic timelines
+/// and the API's incident listing.
+pub async fn search_incidents(pool: &PgPool, guild_id: i64, filter: &IncidentFilter) -> Result<Vec<Incident>> {
+    let _timer = metrics::time_query("search_incidents");
+    let incidents = sqlx::query_as!(
+        Incident,
+        r#"
+        SELECT * FROM incidents
+        WHERE guild_id = $1
+        AND ($2::text IS NULL OR incident_type = $2)
+        AND ($3::text IS NULL OR severity = $3)
+        AND ($4::bigint IS NULL OR user_id = $4)
+        AND ($5::boolean IS NULL OR resolved = $5)
+        AND ($6::timestamptz IS NULL OR created_at >= $6)
+        AND ($7::timestamptz IS NULL OR created_at < $7 OR (created_at = $7 AND id < $8::uuid))
+        ORDER BY created_at DESC, id DESC
+        LIMIT $9
+        "#,
+        guild_id,
+        filter.incident_type,
+        filter.severity,
+        filter.user_id,
+        filter.resolved,
+        filter.since,
+        filter.before,
+        filter.before_id,
+        filter.limit
+    )
+    .fetch_all(pool)
+    .await?;
+    
+    Ok(incidents)
+}
+
+/// Forensic events matching `filter`, newest first, with full-text search
+/// over their content and tags.
+pub async fn search_forensic_events(pool: &PgPool, guild_id: i64, filter: &ForensicFilter) -> Result<Vec<ForensicEvent>> {
+    let _timer = metrics::time_query("search_forensic_events");
+    let events = sqlx::query_as!(
+        ForensicEvent,
+        r#"
+        SELECT * FROM forensic_events
+        WHERE guild_id = $1
+        AND ($2::text IS NULL
+            OR to_tsvector('simple', COALESCE(content, '')) @@ websearch_to_tsquery('simple', $2)
+            OR tags && regexp_split_to_array(lower(trim($2)), '\s+'))
+        AND ($3::text IS NULL OR event_type = $3)
+        AND ($4::bigint IS NULL OR user_id = $4)
+        AND ($5::text IS NULL OR tags @> ARRAY[$5])
+        AND ($6::real IS NULL OR threat_score >= $6)
+        AND ($7::real IS NULL OR threat_score <= $7)
+        AND ($8::timestamptz IS NULL OR created_at >= $8)
+        AND ($9::timestamptz IS NULL OR created_at < $9 OR (created_at = $9 AND id < $10::uuid))
+        ORDER BY created_at DESC, id DESC
+        LIMIT $11 OFFSET $12
+        "#,
+        guild_id,
+        filter.text,
+        filter.event_type,
+        filter.user_id,
+        filter.tag,
+        filter.min_score,
+        filter.max_score,
+        filter.since,
+        filter.before,
+        filter.before_id,
+        filter.limit,
+        filter.offset
+    )
+    .fetch_all(pool)
+    .await?;
+    
+    Ok(events)
+}
+
+pub async fn get_user_activity(pool: &PgPool, guild_id: i64, user_id: i64, since: DateTime<Utc>) -> Result<UserActivity> {
+    let _timer = metrics::time_query("get_user_activity");
+    let activity = sqlx::query_as!(
+        UserActivity,
+        r#"
+        SELECT
+            (SELECT COUNT(*) FROM forensic_events
+             WHERE guild_id = $1 AND user_id = $2 AND created_at >= $3 AND event_type = 'member_join') as "joins!",
+            (SELECT COUNT(*) FROM forensic_events
+             WHERE guild_id = $1 AND user_id = $2 AND created_at >= $3 AND event_type = 'message') as "messages!",
+            (SELECT COUNT(*) FROM incidents
+             WHERE guild_id = $1 AND user_id = $2 AND created_at >= $3) as "incidents!",
+            (SELECT COUNT(*) FROM honeypot_catches
+             WHERE guild_id = $1 AND user_id = $2 AND created_at >= $3) as "catches!",
+            COALESCE(GREATEST(
+                (SELECT MAX(threat_score) FROM forensic_events
+                 WHERE guild_id = $1 AND user_id = $2 AND created_at >= $3),
+                (SELECT MAX(threat_score) FROM incidents
+                 WHERE guild_id = $1 AND user_id = $2 AND created_at >= $3)
+            ), 0)::real as "peak_score!"
+        "#,
+        guild_id,
+        user_id,
+        since
+    )
+    .fetch_one(pool)
+    .await?;
+    
+    Ok(activity)
+}
+
 pub async fn set_lockdown(pool: &PgPool, guild_id: i64, active: bool) -> Result<()> {
+    let _timer = metrics::time_query("set_lockdown");
     sqlx::query!(
         r#"
         UPDATE guilds
@@ -286,7 +554,65 @@ pub async fn set_lockdown(pool: &PgPool, guild_id: i64, active: bool) -> Result<
     Ok(())
 }
 
+pub async fn save_raid_mode(
+    pool: &PgPool,
+    guild_id: i64,
+    mode: &str,
+    since: DateTime<Utc>,
+    auto_locked: bool
+) -> Result<()> {
+    let _timer = metrics::time_query("save_raid_mode");
+    sqlx::query!(
+        r#"
+        INSERT INTO raid_mode_states (guild_id, mode, since, auto_locked)
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT (guild_id) DO UPDATE
+        SET mode = $2, since = $3, auto_locked = $4, updated_at = NOW()
+        "#,
+        guild_id,
+        mode,
+        since,
+        auto_locked
+    )
+    .execute(pool)
+    .await?;
+    
+    Ok(())
+}
+
+pub async fn get_raid_mode(pool: &PgPool, guild_id: i64) -> Result<Option<RaidModeRecord>> {
+    let _timer = metrics::time_query("get_raid_mode");
+    let record = sqlx::query_as!(
+        RaidModeRecord,
+        r#"
+        SELECT * FROM raid_mode_states
+        WHERE guild_id = $1
+        "#,
+        guild_id
+    )
+    .fetch_optional(pool)
+    .await?;
+    
+    Ok(record)
+}
+
+pub async fn delete_raid_mode(pool: &PgPool, guild_id: i64) -> Result<()> {
+    let _timer = metrics::time_query("delete_raid_mode");
+    sqlx::query!(
+        r#"
+        DELETE FROM raid_mode_states
+        WHERE guild_id = $1
+        "#,
+        guild_id
+    )
+    .execute(pool)
+    .await?;
+    
+    Ok(())
+}
+
 pub async fn count_recent_bans(pool: &PgPool, guild_id: i64, minutes: i32) -> Result<u32> {
+    let _timer = metrics::time_query("count_recent_bans");
     let result = sqlx::query!(
         r#"
         SELECT COUNT(*) as count
@@ -306,6 +632,7 @@ pub async fn count_recent_bans(pool: &PgPool, guild_id: i64, minutes: i32) -> Re
 
 #[allow(dead_code)]
 pub async fn update_incident_action(pool: &PgPool, guild_id: i64, user_id: i64, action: &str) -> Result<()> {
+    let _timer = metrics::time_query("update_incident_action");
     sqlx::query!(
         r#"
         UPDATE incidents
@@ -331,6 +658,7 @@ pub async fn update_incident_action(pool: &PgPool, guild_id: i64, user_id: i64,
 }
 
 pub async fn update_user_reputation(pool: &PgPool, user_id: i64, delta: i32) -> Result<()> {
+    let _timer = metrics::time_query("update_user_reputation");
     sqlx::query!(
         r#"
         UPDATE users
@@ -347,6 +675,7 @@ pub async fn update_user_reputation(pool: &PgPool, user_id: i64, delta: i32) ->
 }
 
 pub async fn remove_from_whitelist(pool: &PgPool, guild_id: i64, user_id: i64) -> Result<()> {
+    let _timer = metrics::time_query("remove_from_whitelist");
     sqlx::query!(
         r#"
         DELETE FROM whitelisted_users
@@ -362,6 +691,7 @@ pub async fn remove_from_whitelist(pool: &PgPool, guild_id: i64, user_id: i64) -
 }
 
 pub async fn get_whitelisted_users(pool: &PgPool, guild_id: i64) -> Result<Vec<WhitelistedUser>> {
+    let _timer = metrics::time_query("get_whitelisted_users");
     let users = sqlx::query_as!(
         WhitelistedUser,
         r#"
@@ -376,3 +706,1162 @@ pub async fn get_whitelisted_users(pool: &PgPool, guild_id: i64) -> Result<Vec<W
     
     Ok(users)
 }
+
+pub async fn update_raid_thresholds(
+    pool: &PgPool,
+    guild_id: i64,
+    threshold_5s: Option<i32>,
+    threshold_30s: Option<i32>,
+    threshold_1m: Option<i32>,
+    threshold_5m: Option<i32>,
+    new_account_days: Option<i32>
+) -> Result<()> {
+    let _timer = metrics::time_query("update_raid_thresholds");
+    sqlx::query!(
+        r#"
+        UPDATE guilds
+        SET raid_threshold_5s = COALESCE($2, raid_threshold_5s),
+            raid_threshold_30s = COALESCE($3, raid_threshold_30s),
+            raid_threshold_1m = COALESCE($4, raid_threshold_1m),
+            raid_threshold_5m = COALESCE($5, raid_threshold_5m),
+            new_account_days = COALESCE($6, new_account_days),
+            updated_at = NOW()
+        WHERE guild_id = $1
+        "#,
+        guild_id,
+        threshold_5s,
+        threshold_30s,
+        threshold_1m,
+        threshold_5m,
+        new_account_days
+    )
+    .execute(pool)
+    .await?;
+    
+    Ok(())
+}
+
+pub async fn set_auto_mod_enabled(pool: &PgPool, guild_id: i64, enabled: bool) -> Result<()> {
+    let _timer = metrics::time_query("set_auto_mod_enabled");
+    sqlx::query!(
+        r#"
+        UPDATE guilds
+        SET auto_mod_enabled = $2, updated_at = NOW()
+        WHERE guild_id = $1
+        "#,
+        guild_id,
+        enabled
+    )
+    .execute(pool)
+    .await?;
+    
+    Ok(())
+}
+
+pub async fn set_guild_setting(
+    pool: &PgPool,
+    guild_id: i64,
+    key: &str,
+    value: serde_json::Value
+) -> Result<()> {
+    let _timer = metrics::time_query("set_guild_setting");
+    sqlx::query!(
+        r#"
+        UPDATE guilds
+        SET config = config || jsonb_build_object($2::text, $3::jsonb), updated_at = NOW()
+        WHERE guild_id = $1
+        "#,
+        guild_id,
+        key,
+        value
+    )
+    .execute(pool)
+    .await?;
+    
+    Ok(())
+}
+
+pub async fn add_to_blacklist(
+    pool: &PgPool,
+    guild_id: i64,
+    user_id: i64,
+    reason: Option<&str>,
+    action: &str,
+    added_by: i64,
+    expires_at: Option<DateTime<Utc>>
+) -> Result<()> {
+    let _timer = metrics::time_query("add_to_blacklist");
+    sqlx::query!(
+        r#"
+        INSERT INTO blacklisted_users (guild_id, user_id, reason, action, added_by, expires_at)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        ON CONFLICT (guild_id, user_id) DO UPDATE
+        SET reason = EXCLUDED.reason,
+            action = EXCLUDED.action,
+            added_by = EXCLUDED.added_by,
+            expires_at = EXCLUDED.expires_at,
+            created_at = NOW()
+        "#,
+        guild_id,
+        user_id,
+        reason,
+        action,
+        added_by,
+        expires_at
+    )
+    .execute(pool)
+    .await?;
+    
+    Ok(())
+}
+
+pub async fn remove_from_blacklist(pool: &PgPool, guild_id: i64, user_id: i64) -> Result<bool> {
+    let _timer = metrics::time_query("remove_from_blacklist");
+    let result = sqlx::query!(
+        r#"
+        DELETE FROM blacklisted_users
+        WHERE guild_id = $1 AND user_id = $2
+        "#,
+        guild_id,
+        user_id
+    )
+    .execute(pool)
+    .await?;
+    
+    Ok(result.rows_affected() > 0)
+}
+
+pub async fn get_blacklist_entry(pool: &PgPool, guild_id: i64, user_id: i64) -> Result<Option<BlacklistedUser>> {
+    let _timer = metrics::time_query("get_blacklist_entry");
+    let entry = sqlx::query_as!(
+        BlacklistedUser,
+        r#"
+        SELECT * FROM blacklisted_users
+        WHERE guild_id = $1 AND user_id = $2
+        AND (expires_at IS NULL OR expires_at > NOW())
+        "#,
+        guild_id,
+        user_id
+    )
+    .fetch_optional(pool)
+    .await?;
+    
+    Ok(entry)
+}
+
+pub async fn get_blacklisted_users(pool: &PgPool, guild_id: i64) -> Result<Vec<BlacklistedUser>> {
+    let _timer = metrics::time_query("get_blacklisted_users");
+    let users = sqlx::query_as!(
+        BlacklistedUser,
+        r#"
+        SELECT * FROM blacklisted_users
+        WHERE guild_id = $1
+        AND (expires_at IS NULL OR expires_at > NOW())
+        ORDER BY created_at DESC
+        "#,
+        guild_id
+    )
+    .fetch_all(pool)
+    .await?;
+    
+    Ok(users)
+}
+
+pub async fn save_lockdown_snapshot(
+    pool: &PgPool,
+    guild_id: i64,
+    channel_id: i64,
+    had_overwrite: bool,
+    allow_bits: i64,
+    deny_bits: i64
+) -> Result<()> {
+    let _timer = metrics::time_query("save_lockdown_snapshot");
+    sqlx::query!(
+        r#"
+        INSERT INTO lockdown_snapshots (guild_id, channel_id, had_overwrite, allow_bits, deny_bits)
+        VALUES ($1, $2, $3, $4, $5)
+        ON CONFLICT (guild_id, channel_id) DO NOTHING
+        "#,
+        guild_id,
+        channel_id,
+        had_overwrite,
+        allow_bits,
+        deny_bits
+    )
+    .execute(pool)
+    .await?;
+    
+    Ok(())
+}
+
+pub async fn get_lockdown_snapshots(pool: &PgPool, guild_id: i64) -> Result<Vec<LockdownSnapshot>> {
+    let _timer = metrics::time_query("get_lockdown_snapshots");
+    let snapshots = sqlx::query_as!(
+        LockdownSnapshot,
+        r#"
+        SELECT * FROM lockdown_snapshots
+        WHERE guild_id = $1
+        ORDER BY created_at
+        "#,
+        guild_id
+    )
+    .fetch_all(pool)
+    .await?;
+    
+    Ok(snapshots)
+}
+
+pub async fn delete_lockdown_snapshot(pool: &PgPool, guild_id: i64, channel_id: i64) -> Result<()> {
+    let _timer = metrics::time_query("delete_lockdown_snapshot");
+    sqlx::query!(
+        r#"
+        DELETE FROM lockdown_snapshots
+        WHERE guild_id = $1 AND channel_id = $2
+        "#,
+        guild_id,
+        channel_id
+    )
+    .execute(pool)
+    .await?;
+    
+    Ok(())
+}
+
+pub async fn create_scheduled_job(
+    pool: &PgPool,
+    guild_id: i64,
+    job_type: &str,
+    payload: serde_json::Value,
+    run_at: DateTime<Utc>,
+    created_by: Option<i64>
+) -> Result<ScheduledJob> {
+    let _timer = metrics::time_query("create_scheduled_job");
+    let job = sqlx::query_as!(
+        ScheduledJob,
+        r#"
+        INSERT INTO scheduled_jobs (guild_id, job_type, payload, run_at, created_by)
+        VALUES ($1, $2, $3, $4, $5)
+        RETURNING *
+        "#,
+        guild_id,
+        job_type,
+        payload,
+        run_at,
+        created_by
+    )
+    .fetch_one(pool)
+    .await?;
+    
+    Ok(job)
+}
+
+pub async fn get_pending_jobs(pool: &PgPool, guild_id: i64) -> Result<Vec<ScheduledJob>> {
+    let _timer = metrics::time_query("get_pending_jobs");
+    let jobs = sqlx::query_as!(
+        ScheduledJob,
+        r#"
+        SELECT * FROM scheduled_jobs
+        WHERE guild_id = $1 AND status IN ('pending', 'running')
+        ORDER BY run_at
+        "#,
+        guild_id
+    )
+    .fetch_all(pool)
+    .await?;
+    
+    Ok(jobs)
+}
+
+/// Marks up to `limit` due jobs as running and returns them. `SKIP LOCKED`
+/// lets several bot processes poll the same table without double-running a job.
+pub async fn claim_due_jobs(pool: &PgPool, limit: i64) -> Result<Vec<ScheduledJob>> {
+    let _timer = metrics::time_query("claim_due_jobs");
+    let jobs = sqlx::query_as!(
+        ScheduledJob,
+        r#"
+        UPDATE scheduled_jobs
+        SET status = 'running', attempts = attempts + 1, updated_at = NOW()
+        WHERE id IN (
+            SELECT id FROM scheduled_jobs
+            WHERE status = 'pending' AND run_at <= NOW()
+            ORDER BY run_at
+            LIMIT $1
+            FOR UPDATE SKIP LOCKED
+        )
+        RETURNING *
+        "#,
+        limit
+    )
+    .fetch_all(pool)
+    .await?;
+    
+    Ok(jobs)
+}
+
+/// Puts jobs back in the queue that were claimed by a process which died
+/// before finishing them.
+pub async fn requeue_stale_jobs(pool: &PgPool, stale_minutes: i32) -> Result<u64> {
+    let _timer = metrics::time_query("requeue_stale_jobs");
+    let result = sqlx::query!(
+        r#"
+        UPDATE scheduled_jobs
+        SET status = 'pending', updated_at = NOW()
+        WHERE status = 'running'
+        AND updated_at < NOW() - ($1 || ' minutes')::INTERVAL
+        "#,
+        stale_minutes.to_string()
+    )
+    .execute(pool)
+    .await?;
+    
+    Ok(result.rows_affected())
+}
+
+pub async fn complete_job(pool: &PgPool, job_id: Uuid) -> Result<()> {
+    let _timer = metrics::time_query("complete_job");
+    sqlx::query!(
+        r#"
+        UPDATE scheduled_jobs
+        SET status = 'completed', last_error = NULL, updated_at = NOW()
+        WHERE id = $1
+        "#,
+        job_id
+    )
+    .execute(pool)
+    .await?;
+    
+    Ok(())
+}
+
+pub async fn fail_job(pool: &PgPool, job_id: Uuid, error: &str, retry_at: Option<DateTime<Utc>>) -> Result<()> {
+    let _timer = metrics::time_query("fail_job");
+    sqlx::query!(
+        r#"
+        UPDATE scheduled_jobs
+        SET status = CASE WHEN $3::timestamptz IS NULL THEN 'failed' ELSE 'pending' END,
+            run_at = COALESCE($3, run_at),
+            last_error = $2,
+            updated_at = NOW()
+        WHERE id = $1
+        "#,
+        job_id,
+        error,
+        retry_at
+    )
+    .execute(pool)
+    .await?;
+    
+    Ok(())
+}
+
+pub async fn cancel_job(pool: &PgPool, guild_id: i64, job_id: Uuid) -> Result<bool> {
+    let _timer = metrics::time_query("cancel_job");
+    let result = sqlx::query!(
+        r#"
+        UPDATE scheduled_jobs
+        SET status = 'cancelled', updated_at = NOW()
+        WHERE id = $1 AND guild_id = $2 AND status = 'pending'
+        "#,
+        job_id,
+        guild_id
+    )
+    .execute(pool)
+    .await?;
+    
+    Ok(result.rows_affected() > 0)
+}
+
+pub async fn cancel_jobs_of_type(pool: &PgPool, guild_id: i64, job_type: &str) -> Result<u64> {
+    let _timer = metrics::time_query("cancel_jobs_of_type");
+    let result = sqlx::query!(
+        r#"
+        UPDATE scheduled_jobs
+        SET status = 'cancelled', updated_at = NOW()
+        WHERE guild_id = $1 AND job_type = $2 AND status = 'pending'
+        "#,
+        guild_id,
+        job_type
+    )
+    .execute(pool)
+    .await?;
+    
+    Ok(result.rows_affected())
+}
+
+pub async fn create_backup(
+    pool: &PgPool,
+    guild_id: i64,
+    label: Option<&str>,
+    data: serde_json::Value,
+    created_by: Option<i64>
+) -> Result<GuildBackup> {
+    let _timer = metrics::time_query("create_backup");
+    let backup = sqlx::query_as!(
+        GuildBackup,
+        r#"
+        INSERT INTO guild_backups (guild_id, version, label, data, created_by)
+        VALUES (
+            $1,
+            (SELECT COALESCE(MAX(version), 0) + 1 FROM guild_backups WHERE guild_id = $1),
+            $2, $3, $4
+        )
+        RETURNING *
+        "#,
+        guild_id,
+        label,
+        data,
+        created_by
+    )
+    .fetch_one(pool)
+    .await?;
+    
+    Ok(backup)
+}
+
+pub async fn get_backup(pool: &PgPool, guild_id: i64, version: i32) -> Result<Option<GuildBackup>> {
+    let _timer = metrics::time_query("get_backup");
+    let backup = sqlx::query_as!(
+        GuildBackup,
+        r#"SELECT * FROM guild_backups WHERE guild_id = $1 AND version = $2"#,
+        guild_id,
+        version
+    )
+    .fetch_optional(pool)
+    .await?;
+    
+    Ok(backup)
+}
+
+pub async fn get_backups(pool: &PgPool, guild_id: i64, limit: i64) -> Result<Vec<GuildBackup>> {
+    let _timer = metrics::time_query("get_backups");
+    let backups = sqlx::query_as!(
+        GuildBackup,
+        r#"
+        SELECT * FROM guild_backups
+        WHERE guild_id = $1
+        ORDER BY version DESC
+        LIMIT $2
+        "#,
+        guild_id,
+        limit
+    )
+    .fetch_all(pool)
+    .await?;
+    
+    Ok(backups)
+}
+
+/// Writes back the Kitsune settings captured in a backup.
+pub async fn restore_guild_settings(pool: &PgPool, guild_id: i64, settings: &GuildSettingsBackup) -> Result<()> {
+    let _timer = metrics::time_query("restore_guild_settings");
+    sqlx::query!(
+        r#"
+        UPDATE guilds
+        SET config = $2,
+            raid_threshold_5s = $3,
+            raid_threshold_30s = $4,
+            raid_threshold_1m = $5,
+            raid_threshold_5m = $6,
+            new_account_days = $7,
+            auto_mod_enabled = $8,
+            updated_at = NOW()
+        WHERE guild_id = $1
+        "#,
+        guild_id,
+        settings.config,
+        settings.raid_threshold_5s,
+        settings.raid_threshold_30s,
+        settings.raid_threshold_1m,
+        settings.raid_threshold_5m,
+        settings.new_account_days,
+        settings.auto_mod_enabled
+    )
+    .execute(pool)
+    .await?;
+    
+    Ok(())
+}
+
+/// Deletes automatic backups beyond the newest `keep`. Backups taken by a
+/// moderator (`created_by` set) are never pruned.
+pub async fn prune_backups(pool: &PgPool, guild_id: i64, keep: i64) -> Result<u64> {
+    let _timer = metrics::time_query("prune_backups");
+    let result = sqlx::query!(
+        r#"
+        DELETE FROM guild_backups
+        WHERE guild_id = $1 AND created_by IS NULL AND id NOT IN (
+            SELECT id FROM guild_backups
+            WHERE guild_id = $1 AND created_by IS NULL
+            ORDER BY version DESC
+            LIMIT $2
+        )
+        "#,
+        guild_id,
+        keep
+    )
+    .execute(pool)
+    .await?;
+    
+    Ok(result.rows_affected())
+}
+
+pub async fn add_webhook(
+    pool: &PgPool,
+    guild_id: i64,
+    url: &str,
+    secret: &str,
+    events: &[String],
+    created_by: i64
+) -> Result<Webhook> {
+    let _timer = metrics::time_query("add_webhook");
+    let webhook = sqlx::query_as!(
+        Webhook,
+        r#"
+        INSERT INTO webhooks (guild_id, url, secret, events, created_by)
+        VALUES ($1, $2, $3, $4, $5)
+        RETURNING *
+        "#,
+        guild_id,
+        url,
+        secret,
+        events,
+        created_by
+    )
+    .fetch_one(pool)
+    .await?;
+    
+    Ok(webhook)
+}
+
+pub async fn remove_webhook(pool: &PgPool, guild_id: i64, webhook_id: i32) -> Result<bool> {
+    let _timer = metrics::time_query("remove_webhook");
+    let result = sqlx::query!(
+        r#"DELETE FROM webhooks WHERE guild_id = $1 AND id = $2"#,
+        guild_id,
+        webhook_id
+    )
+    .execute(pool)
+    .await?;
+    
+    Ok(result.rows_affected() > 0)
+}
+
+pub async fn get_webhook(pool: &PgPool, guild_id: i64, webhook_id: i32) -> Result<Option<Webhook>> {
+    let _timer = metrics::time_query("get_webhook");
+    let webhook = sqlx::query_as!(
+        Webhook,
+        r#"SELECT * FROM webhooks WHERE guild_id = $1 AND id = $2"#,
+        guild_id,
+        webhook_id
+    )
+    .fetch_optional(pool)
+    .await?;
+    
+    Ok(webhook)
+}
+
+pub async fn get_webhooks(pool: &PgPool, guild_id: i64) -> Result<Vec<Webhook>> {
+    let _timer = metrics::time_query("get_webhooks");
+    let webhooks = sqlx::query_as!(
+        Webhook,
+        r#"SELECT * FROM webhooks WHERE guild_id = $1 ORDER BY id"#,
+        guild_id
+    )
+    .fetch_all(pool)
+    .await?;
+    
+    Ok(webhooks)
+}
+
+/// Queues one delivery per webhook of the guild subscribed to `event`.
+/// `payload` is built per delivery so it can embed the delivery ID.
+pub async fn enqueue_webhook_deliveries(
+    pool: &PgPool,
+    guild_id: i64,
+    event: &str,
+    payload: impl Fn(Uuid) -> serde_json::Value
+) -> Result<usize> {
+    let _timer = metrics::time_query("enqueue_webhook_deliveries");
+    let webhook_ids = sqlx::query_scalar!(
+        r#"
+        SELECT id FROM webhooks
+        WHERE guild_id = $1 AND $2 = ANY(events)
+        "#,
+        guild_id,
+        event
+    )
+    .fetch_all(pool)
+    .await?;
+    
+    for webhook_id in &webhook_ids {
+        let delivery_id = Uuid::new_v4();
+        sqlx::query!(
+            r#"
+            INSERT INTO webhook_deliveries (id, webhook_id, guild_id, event, payload)
+            VALUES ($1, $2, $3, $4, $5)
+            "#,
+            delivery_id,
+            webhook_id,
+            guild_id,
+            event,
+            payload(delivery_id)
+        )
+        .execute(pool)
+        .await?;
+    }
+    
+    Ok(webhook_ids.len())
+}
+
+pub async fn create_webhook_delivery(
+    pool: &PgPool,
+    delivery_id: Uuid,
+    webhook: &Webhook,
+    event: &str,
+    payload: serde_json::Value
+) -> Result<WebhookDelivery> {
+    let _timer = metrics::time_query("create_webhook_delivery");
+    let delivery = sqlx::query_as!(
+        WebhookDelivery,
+        r#"
+        INSERT INTO webhook_deliveries (id, webhook_id, guild_id, event, payload, status, attempts)
+        VALUES ($1, $2, $3, $4, $5, 'sending', 1)
+        RETURNING *
+        "#,
+        delivery_id,
+        webhook.id,
+        webhook.guild_id,
+        event,
+        payload
+    )
+    .fetch_one(pool)
+    .await?;
+    
+    Ok(delivery)
+}
+
+pub async fn claim_due_deliveries(pool: &PgPool, limit: i64) -> Result<Vec<WebhookDelivery>> {
+    let _timer = metrics::time_query("claim_due_deliveries");
+    let deliveries = sqlx::query_as!(
+        WebhookDelivery,
+        r#"
+        UPDATE webhook_deliveries
+        SET status = 'sending', attempts = attempts + 1, updated_at = NOW()
+        WHERE id IN (
+            SELECT id FROM webhook_deliveries
+            WHERE status = 'pending' AND next_attempt_at <= NOW()
+            ORDER BY next_attempt_at
+            LIMIT $1
+            FOR UPDATE SKIP LOCKED
+        )
+        RETURNING *
+        "#,
+        limit
+    )
+    .fetch_all(pool)
+    .await?;
+    
+    Ok(deliveries)
+}
+
+/// Puts deliveries back in the queue that were claimed by a process which
+/// died mid-request.
+pub async fn requeue_stale_deliveries(pool: &PgPool, stale_minutes: i32) -> Result<u64> {
+    let _timer = metrics::time_query("requeue_stale_deliveries");
+    let result = sqlx::query!(
+        r#"
+        UPDATE webhook_deliveries
+        SET status = 'pending', updated_at = NOW()
+        WHERE status = 'sending'
+        AND updated_at < NOW() - ($1 || ' minutes')::INTERVAL
+        "#,
+        stale_minutes.to_string()
+    )
+    .execute(pool)
+    .await?;
+    
+    Ok(result.rows_affected())
+}
+
+/// Returns a claimed delivery to the queue without counting the attempt, for
+/// claims that were never sent.
+pub async fn release_delivery(pool: &PgPool, delivery_id: Uuid) -> Result<()> {
+    let _timer = metrics::time_query("release_delivery");
+    sqlx::query!(
+        r#"
+        UPDATE webhook_deliveries
+        SET status = 'pending', attempts = attempts - 1, updated_at = NOW()
+        WHERE id = $1 AND status = 'sending'
+        "#,
+        delivery_id
+    )
+    .execute(pool)
+    .await?;
+    
+    Ok(())
+}
+
+pub async fn complete_delivery(pool: &PgPool, delivery_id: Uuid, status_code: i32) -> Result<()> {
+    let _timer = metrics::time_query("complete_delivery");
+    sqlx::query!(
+        r#"
+        UPDATE webhook_deliveries
+        SET status = 'delivered', last_status_code = $2, last_error = NULL,
+            delivered_at = NOW(), updated_at = NOW()
+        WHERE id = $1
+        "#,
+        delivery_id,
+        status_code
+    )
+    .execute(pool)
+    .await?;
+    
+    Ok(())
+}
+
+/// Records a failed attempt. With `retry_at` the delivery goes back in the
+/// queue, otherwise it is marked as permanently failed.
+pub async fn fail_delivery(
+    pool: &PgPool,
+    delivery_id: Uuid,
+    status_code: Option<i32>,
+    error: &str,
+    retry_at: Option<DateTime<Utc>>
+) -> Result<()> {
+    let _timer = metrics::time_query("fail_delivery");
+    sqlx::query!(
+        r#"
+        UPDATE webhook_deliveries
+        SET status = CASE WHEN $4::timestamptz IS NULL THEN 'failed' ELSE 'pending' END,
+            next_attempt_at = COALESCE($4, next_attempt_at),
+            last_status_code = $2,
+            last_error = $3,
+            updated_at = NOW()
+        WHERE id = $1
+        "#,
+        delivery_id,
+        status_code,
+        error,
+        retry_at
+    )
+    .execute(pool)
+    .await?;
+    
+    Ok(())
+}
+
+pub async fn get_webhook_deliveries(pool: &PgPool, guild_id: i64, webhook_id: i32, limit: i64) -> Result<Vec<WebhookDelivery>> {
+    let _timer = metrics::time_query("get_webhook_deliveries");
+    let deliveries = sqlx::query_as!(
+        WebhookDelivery,
+        r#"
+        SELECT * FROM webhook_deliveries
+        WHERE guild_id = $1 AND webhook_id = $2
+        ORDER BY created_at DESC
+        LIMIT $3
+        "#,
+        guild_id,
+        webhook_id,
+        limit
+    )
+    .fetch_all(pool)
+    .await?;
+    
+    Ok(deliveries)
+}
+
+pub async fn get_incident(pool: &PgPool, guild_id: i64, incident_id: Uuid) -> Result<Option<Incident>> {
+    let _timer = metrics::time_query("get_incident");
+    let incident = sqlx::query_as!(
+        Incident,
+        r#"SELECT * FROM incidents WHERE guild_id = $1 AND id = $2"#,
+        guild_id,
+        incident_id
+    )
+    .fetch_optional(pool)
+    .await?;
+    
+    Ok(incident)
+}
+
+/// One page of a guild's incidents between `since` and `until`, oldest first.
+/// `after` is the `(created_at, id)` of the last row of the previous page.
+pub async fn export_incidents(
+    pool: &PgPool,
+    guild_id: i64,
+    since: DateTime<Utc>,
+    until: DateTime<Utc>,
+    after: Option<(DateTime<Utc>, Uuid)>,
+    limit: i64
+) -> Result<Vec<Incident>> {
+    let _timer = metrics::time_query("export_incidents");
+    let (after_time, after_id) = after.unzip();
+    let incidents = sqlx::query_as!(
+        Incident,
+        r#"
+        SELECT * FROM incidents
+        WHERE guild_id = $1 AND created_at >= $2 AND created_at < $3
+        AND ($4::timestamptz IS NULL OR (created_at, id) > ($4, $5::uuid))
+        ORDER BY created_at, id
+        LIMIT $6
+        "#,
+        guild_id,
+        since,
+        until,
+        after_time,
+        after_id,
+        limit
+    )
+    .fetch_all(pool)
+    .await?;
+    
+    Ok(incidents)
+}
+
+pub async fn export_forensic_events(
+    pool: &PgPool,
+    guild_id: i64,
+    since: DateTime<Utc>,
+    until: DateTime<Utc>,
+    after: Option<(DateTime<Utc>, Uuid)>,
+    limit: i64
+) -> Result<Vec<ForensicEvent>> {
+    let _timer = metrics::time_query("export_forensic_events");
+    let (after_time, after_id) = after.unzip();
+    let events = sqlx::query_as!(
+        ForensicEvent,
+        r#"
+        SELECT * FROM forensic_events
+        WHERE guild_id = $1 AND created_at >= $2 AND created_at < $3
+        AND ($4::timestamptz IS NULL OR (created_at, id) > ($4, $5::uuid))
+        ORDER BY created_at, id
+        LIMIT $6
+        "#,
+        guild_id,
+        since,
+        until,
+        after_time,
+        after_id,
+        limit
+    )
+    .fetch_all(pool)
+    .await?;
+    
+    Ok(events)
+}
+
+pub async fn export_honeypot_catches(
+    pool: &PgPool,
+    guild_id: i64,
+    since: DateTime<Utc>,
+    until: DateTime<Utc>,
+    after: Option<(DateTime<Utc>, Uuid)>,
+    limit: i64
+) -> Result<Vec<HoneypotCatch>> {
+    let _timer = metrics::time_query("export_honeypot_catches");
+    let (after_time, after_id) = after.unzip();
+    let catches = sqlx::query_as!(
+        HoneypotCatch,
+        r#"
+        SELECT * FROM honeypot_catches
+        WHERE guild_id = $1 AND created_at >= $2 AND created_at < $3
+        AND ($4::timestamptz IS NULL OR (created_at, id) > ($4, $5::uuid))
+        ORDER BY created_at, id
+        LIMIT $6
+        "#,
+        guild_id,
+        since,
+        until,
+        after_time,
+        after_id,
+        limit
+    )
+    .fetch_all(pool)
+    .await?;
+    
+    Ok(catches)
+}
+
+/// Behavior profiles are rolling aggregates, so the range applies to when
+/// they were last updated.
+pub async fn export_behavior_profiles(
+    pool: &PgPool,
+    guild_id: i64,
+    since: DateTime<Utc>,
+    until: DateTime<Utc>,
+    after: Option<(DateTime<Utc>, i32)>,
+    limit: i64
+) -> Result<Vec<BehaviorProfile>> {
+    let _timer = metrics::time_query("export_behavior_profiles");
+    let (after_time, after_id) = after.unzip();
+    let profiles = sqlx::query_as!(
+        BehaviorProfile,
+        r#"
+        SELECT * FROM behavior_profiles
+        WHERE guild_id = $1 AND updated_at >= $2 AND updated_at < $3
+        AND ($4::timestamptz IS NULL OR (updated_at, id) > ($4, $5::integer))
+        ORDER BY updated_at, id
+        LIMIT $6
+        "#,
+        guild_id,
+        since,
+        until,
+        after_time,
+        after_id,
+        limit
+    )
+    .fetch_all(pool)
+    .await?;
+    
+    Ok(profiles)
+}
+
+pub async fn get_guild_ids(pool: &PgPool) -> Result<Vec<i64>> {
+    let _timer = metrics::time_query("get_guild_ids");
+    let guild_ids = sqlx::query_scalar!(
+        r#"SELECT guild_id FROM guilds ORDER BY guild_id"#
+    )
+    .fetch_all(pool)
+    .await?;
+    
+    Ok(guild_ids)
+}
+
+/// Deletes (or archives) one batch of the guild's forensic events older than
+/// `before`. Rows locked by another transaction are skipped until the next run.
+pub async fn purge_forensic_events(
+    pool: &PgPool,
+    guild_id: i64,
+    before: DateTime<Utc>,
+    batch_size: i64,
+    archive: bool
+) -> Result<u64> {
+    let _timer = metrics::time_query("purge_forensic_events");
+    let result = if archive {
+        sqlx::query!(
+            r#"
+            WITH moved AS (
+                DELETE FROM forensic_events
+                WHERE id IN (
+                    SELECT id FROM forensic_events
+                    WHERE guild_id = $1 AND created_at < $2
+                    ORDER BY created_at
+                    LIMIT $3
+                    FOR UPDATE SKIP LOCKED
+                )
+                RETURNING *
+            )
+            INSERT INTO forensic_events_archive SELECT moved.*, NOW() FROM moved
+            "#,
+            guild_id,
+            before,
+            batch_size
+        )
+        .execute(pool)
+        .await?
+    } else {
+        sqlx::query!(
+            r#"
+            DELETE FROM forensic_events
+            WHERE id IN (
+                SELECT id FROM forensic_events
+                WHERE guild_id = $1 AND created_at < $2
+                ORDER BY created_at
+                LIMIT $3
+                FOR UPDATE SKIP LOCKED
+            )
+            "#,
+            guild_id,
+            before,
+            batch_size
+        )
+        .execute(pool)
+        .await?
+    };
+    
+    Ok(result.rows_affected())
+}
+
+/// Open incidents are kept regardless of age.
+pub async fn purge_resolved_incidents(
+    pool: &PgPool,
+    guild_id: i64,
+    before: DateTime<Utc>,
+    batch_size: i64,
+    archive: bool
+) -> Result<u64> {
+    let _timer = metrics::time_query("purge_resolved_incidents");
+    let result = if archive {
+        sqlx::query!(
+            r#"
+            WITH moved AS (
+                DELETE FROM incidents
+                WHERE id IN (
+                    SELECT id FROM incidents
+                    WHERE guild_id = $1 AND created_at < $2 AND resolved = true
+                    ORDER BY created_at
+                    LIMIT $3
+                    FOR UPDATE SKIP LOCKED
+                )
+                RETURNING *
+            )
+            INSERT INTO incidents_archive SELECT moved.*, NOW() FROM moved
+            "#,
+            guild_id,
+            before,
+            batch_size
+        )
+        .execute(pool)
+        .await?
+    } else {
+        sqlx::query!(
+            r#"
+            DELETE FROM incidents
+            WHERE id IN (
+                SELECT id FROM incidents
+                WHERE guild_id = $1 AND created_at < $2 AND resolved = true
+                ORDER BY created_at
+                LIMIT $3
+                FOR UPDATE SKIP LOCKED
+            )
+            "#,
+            guild_id,
+            before,
+            batch_size
+        )
+        .execute(pool)
+        .await?
+    };
+    
+    Ok(result.rows_affected())
+}
+
+pub async fn purge_honeypot_catches(
+    pool: &PgPool,
+    guild_id: i64,
+    before: DateTime<Utc>,
+    batch_size: i64,
+    archive: bool
+) -> Result<u64> {
+    let _timer = metrics::time_query("purge_honeypot_catches");
+    let result = if archive {
+        sqlx::query!(
+            r#"
+            WITH moved AS (
+                DELETE FROM honeypot_catches
+                WHERE id IN (
+                    SELECT id FROM honeypot_catches
+                    WHERE guild_id = $1 AND created_at < $2
+                    ORDER BY created_at
+                    LIMIT $3
+                    FOR UPDATE SKIP LOCKED
+                )
+                RETURNING *
+            )
+            INSERT INTO honeypot_catches_archive SELECT moved.*, NOW() FROM moved
+            "#,
+            guild_id,
+            before,
+            batch_size
+        )
+        .execute(pool)
+        .await?
+    } else {
+        sqlx::query!(
+            r#"
+            DELETE FROM honeypot_catches
+            WHERE id IN (
+                SELECT id FROM honeypot_catches
+                WHERE guild_id = $1 AND created_at < $2
+                ORDER BY created_at
+                LIMIT $3
+                FOR UPDATE SKIP LOCKED
+            )
+            "#,
+            guild_id,
+            before,
+            batch_size
+        )
+        .execute(pool)
+        .await?
+    };
+    
+    Ok(result.rows_affected())
+}
+
+pub async fn create_api_token(
+    pool: &PgPool,
+    guild_id: i64,
+    name: &str,
+    token_hash: &str,
+    created_by: i64
+) -> Result<ApiToken> {
+    let _timer = metrics::time_query("create_api_token");
+    let token = sqlx::query_as!(
+        ApiToken,
+        r#"
+        INSERT INTO api_tokens (guild_id, name, token_hash, created_by)
+        VALUES ($1, $2, $3, $4)
+        RETURNING *
+        "#,
+        guild_id,
+        name,
+        token_hash,
+        created_by
+    )
+    .fetch_one(pool)
+    .await?;
+    
+    Ok(token)
+}
+
+pub async fn get_api_tokens(pool: &PgPool, guild_id: i64) -> Result<Vec<ApiToken>> {
+    let _timer = metrics::time_query("get_api_tokens");
+    let tokens = sqlx::query_as!(
+        ApiToken,
+        r#"SELECT * FROM api_tokens WHERE guild_id = $1 ORDER BY id"#,
+        guild_id
+    )
+    .fetch_all(pool)
+    .await?;
+    
+    Ok(tokens)
+}
+
+pub async fn revoke_api_token(pool: &PgPool, guild_id: i64, token_id: i32) -> Result<bool> {
+    let _timer = metrics::time_query("revoke_api_token");
+    let result = sqlx::query!(
+        r#"DELETE FROM api_tokens WHERE guild_id = $1 AND id = $2"#,
+        guild_id,
+        token_id
+    )
+    .execute(pool)
+    .await?;
+    
+    Ok(result.rows_affected() > 0)
+}
+
+/// Looks up a token by its hash and records that it was used.
+pub async fn authenticate_api_token(pool: &PgPool, token_hash: &str) -> Result<Option<ApiToken>> {
+    let _timer = metrics::time_query("authenticate_api_token");
+    let token = sqlx::query_as!(
+        ApiToken,
+        r#"
+        UPDATE api_tokens SET last_used_at = NOW()
+        WHERE token_hash = $1
+        RETURNING *
+        "#,
+        token_hash
+    )
+    .fetch_optional(pool)
+    .await?;
+    
+    Ok(token)
+}
+
+pub async fn ping(pool: &PgPool) -> Result<()> {
+    let _timer = metrics::time_query("ping");
+    sqlx::query!("SELECT 1 AS one").fetch_one(pool).await?;
+    
+    Ok(())
+}
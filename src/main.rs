@@ -1,6 +1,10 @@
+mod api;
 mod bot;
+mod cli;
 mod config;
 mod database;
+mod metrics;
+mod replay;
 mod security;
 mod utils;
 
@@ -18,6 +22,11 @@ async fn main() -> Result<()> {
         .with(tracing_subscriber::fmt::layer())
         .init();
 
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() {
+        return cli::run(&args).await;
+    }
+
     tracing::info!("🦊 Kitsune Guardian Fox - Starting...");
 
     let config = config::Config::from_env()
@@ -25,12 +34,37 @@ async fn main() -> Result<()> {
 
     tracing::info!("Configuration loaded successfully");
 
+    if config.metrics.enabled {
+        metrics::spawn(config.metrics.clone());
+    }
+
     let pool = database::create_pool(&config.database_url)
         .await
         .context("Failed to create database pool")?;
 
     tracing::info!("Database connection established");
 
+    if config.database.auto_migrate {
+        let applied = database::migrations::run(&pool)
+            .await
+            .context("Failed to apply database migrations")?;
+
+        if applied > 0 {
+            tracing::info!("Applied {} database migration(s)", applied);
+        }
+    } else {
+        let pending = database::migrations::status(&pool)
+            .await
+            .context("Failed to check database migrations")?
+            .iter()
+            .filter(|migration| migration.state != database::migrations::MigrationState::Applied)
+            .count();
+
+        if pending > 0 {
+            tracing::warn!("{} database migration(s) not applied; run `kitsune migrate status`", pending);
+        }
+    }
+
     let redis = utils::redis_client::create_redis_client(&config.redis_url)
         .await
         .context("Failed to connect to Redis")?;
@@ -40,10 +74,18 @@ async fn main() -> Result<()> {
     let intents = serenity::GatewayIntents::GUILDS
         | serenity::GatewayIntents::GUILD_MEMBERS
         | serenity::GatewayIntents::GUILD_MESSAGES
+        | serenity::GatewayIntents::GUILD_MESSAGE_REACTIONS
         | serenity::GatewayIntents::MESSAGE_CONTENT
         | serenity::GatewayIntents::GUILD_MODERATION;
 
-    let framework = bot::create_framework(config.clone(), pool.clone(), redis)
+    let http = serenity::Http::new(&config.discord_token);
+    let shard_plan = bot::sharding::ShardPlan::resolve(&config.sharding, &http)
+        .await
+        .context("Failed to resolve shard plan")?;
+
+    tracing::info!("Running {}", shard_plan.describe());
+
+    let (framework, health) = bot::create_framework(config.clone(), pool.clone(), redis, shard_plan)
         .await
         .context("Failed to create bot framework")?;
 
@@ -52,9 +94,14 @@ async fn main() -> Result<()> {
         .await
         .context("Failed to create Discord client")?;
 
+    health.attach_shards(client.shard_manager.clone(), client.cache.clone());
+    if config.health.enabled {
+        bot::health::spawn(config.health.clone(), health);
+    }
+
     tracing::info!("🦊 Kitsune is now online and protecting servers!");
 
-    client.start().await.context("Failed to start client")?;
+    bot::sharding::start(&mut client, shard_plan).await.context("Failed to start client")?;
 
     Ok(())
 }
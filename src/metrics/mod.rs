@@ -0,0 +1,117 @@
+mod server;
+
+pub use server::{spawn, spawn_gateway_sampler};
+
+use prometheus::{
+    Encoder, GaugeVec, Histogram, HistogramOpts, HistogramTimer, HistogramVec, IntCounter, IntCounterVec, Opts,
+    Registry, TextEncoder,
+};
+use std::sync::LazyLock;
+
+/// Process-wide Prometheus metrics. Detectors and queries record into these
+/// directly so nothing has to be threaded through their call sites.
+pub struct Metrics {
+    registry: Registry,
+    pub joins_recorded: IntCounter,
+    pub raid_score: GaugeVec,
+    pub messages_analyzed: IntCounter,
+    pub threat_score: Histogram,
+    pub mod_actions: IntCounterVec,
+    pub discord_failures: IntCounterVec,
+    pub db_query_duration: HistogramVec,
+    pub gateway_latency: GaugeVec,
+    pub rows_purged: IntCounterVec,
+}
+
+static METRICS: LazyLock<Metrics> = LazyLock::new(Metrics::new);
+
+pub fn metrics() -> &'static Metrics {
+    &METRICS
+}
+
+impl Metrics {
+    fn new() -> Self {
+        let registry = Registry::new_custom(Some("kitsune".to_string()), None)
+            .expect("valid registry prefix");
+
+        let joins_recorded = IntCounter::new("joins_recorded_total", "Member joins recorded by the raid detector")
+            .expect("valid metric");
+        let raid_score = GaugeVec::new(
+            Opts::new("raid_score", "Latest raid score computed for each guild"),
+            &["guild_id"],
+        ).expect("valid metric");
+        let messages_analyzed = IntCounter::new("messages_analyzed_total", "Messages run through the behavior analyzer")
+            .expect("valid metric");
+        let threat_score = Histogram::with_opts(
+            HistogramOpts::new("threat_score", "Combined threat scores computed for messages")
+                .buckets(vec![0.1, 0.2, 0.3, 0.4, 0.5, 0.6, 0.7, 0.8, 0.9, 0.95, 1.0]),
+        ).expect("valid metric");
+        let mod_actions = IntCounterVec::new(
+            Opts::new("mod_actions_total", "Automatic moderation actions executed"),
+            &["action"],
+        ).expect("valid metric");
+        let discord_failures = IntCounterVec::new(
+            Opts::new("discord_api_failures_total", "Discord API calls Kitsune made that failed"),
+            &["operation"],
+        ).expect("valid metric");
+        let db_query_duration = HistogramVec::new(
+            HistogramOpts::new("db_query_duration_seconds", "Time spent in database queries")
+                .buckets(vec![0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5]),
+            &["query"],
+        ).expect("valid metric");
+        let gateway_latency = GaugeVec::new(
+            Opts::new("gateway_latency_seconds", "Heartbeat latency reported by each gateway shard"),
+            &["shard"],
+        ).expect("valid metric");
+        let rows_purged = IntCounterVec::new(
+            Opts::new("retention_rows_purged_total", "Rows removed by the retention worker"),
+            &["table"],
+        ).expect("valid metric");
+
+        for collector in [
+            Box::new(joins_recorded.clone()) as Box<dyn prometheus::core::Collector>,
+            Box::new(raid_score.clone()),
+            Box::new(messages_analyzed.clone()),
+            Box::new(threat_score.clone()),
+            Box::new(mod_actions.clone()),
+            Box::new(discord_failures.clone()),
+            Box::new(db_query_duration.clone()),
+            Box::new(gateway_latency.clone()),
+            Box::new(rows_purged.clone()),
+        ] {
+            registry.register(collector).expect("metric registered once");
+        }
+
+        Self {
+            registry,
+            joins_recorded,
+            raid_score,
+            messages_analyzed,
+            threat_score,
+            mod_actions,
+            discord_failures,
+            db_query_duration,
+            gateway_latency,
+            rows_purged,
+        }
+    }
+
+    /// Renders every metric in the Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let mut buffer = Vec::new();
+        if let Err(e) = TextEncoder::new().encode(&self.registry.gather(), &mut buffer) {
+            tracing::error!("Failed to encode metrics: {}", e);
+        }
+        String::from_utf8(buffer).unwrap_or_default()
+    }
+}
+
+/// Starts timing a database query; the duration is recorded when the returned
+/// timer is dropped.
+pub fn time_query(query: &str) -> HistogramTimer {
+    metrics().db_query_duration.with_label_values(&[query]).start_timer()
+}
+
+pub fn discord_failure(operation: &str) {
+    metrics().discord_failures.with_label_values(&[operation]).inc();
+}
@@ -0,0 +1,59 @@
+use anyhow::{Context, Result};
+use axum::http::header;
+use axum::response::IntoResponse;
+use axum::routing::get;
+use axum::Router;
+use poise::serenity_prelude as serenity;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::config::MetricsConfig;
+
+use super::metrics;
+
+const GATEWAY_SAMPLE_INTERVAL: Duration = Duration::from_secs(15);
+
+pub fn spawn(config: MetricsConfig) {
+    tokio::spawn(async move {
+        if let Err(e) = serve(&config).await {
+            tracing::error!("Metrics endpoint stopped: {:#}", e);
+        }
+    });
+}
+
+async fn serve(config: &MetricsConfig) -> Result<()> {
+    let app = Router::new().route(&config.path, get(render));
+
+    let listener = tokio::net::TcpListener::bind(&config.bind_address).await
+        .with_context(|| format!("Failed to bind metrics endpoint to {}", config.bind_address))?;
+    tracing::info!("Metrics available at http://{}{}", listener.local_addr()?, config.path);
+
+    axum::serve(listener, app).await?;
+    Ok(())
+}
+
+async fn render() -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, prometheus::TEXT_FORMAT)],
+        metrics().render(),
+    )
+}
+
+/// Copies each shard's heartbeat latency into the gateway latency gauge.
+pub fn spawn_gateway_sampler(shard_manager: Arc<serenity::ShardManager>) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(GATEWAY_SAMPLE_INTERVAL);
+
+        loop {
+            interval.tick().await;
+
+            for (shard_id, runner) in shard_manager.runners.lock().await.iter() {
+                if let Some(latency) = runner.latency {
+                    metrics().gateway_latency
+                        .with_label_values(&[&shard_id.to_string()])
+                        .set(latency.as_secs_f64());
+                }
+            }
+        }
+    });
+}
@@ -0,0 +1,522 @@
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+
+use crate::config::Config;
+use crate::security::{
+    auto_mod::{AutoModerator, ModAction},
+    behavior_analyzer::BehaviorAnalyzer,
+    decisions::{self, BaitResponse, MessageVerdict},
+    guild_config::GuildConfigCache,
+    honeypot::{HoneypotSystem, RecentJoin},
+    raid_detector::RaidDetector,
+    raid_mode::{RaidModeTracker, RaidTransition},
+    state::MemoryState,
+    JoinEvent,
+};
+use crate::utils::time::Clock;
+
+/// Milliseconds between the Unix epoch and the first second of 2015, where
+/// Discord snowflakes start counting.
+const DISCORD_EPOCH_MS: u64 = 1_420_070_400_000;
+
+/// One line of a recording. Traps are armed before anything else; the other
+/// events run in `at` order.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum RecordedEvent {
+    Trap {
+        guild_id: i64,
+        trap_type: String,
+        target: String,
+    },
+    Join {
+        at: DateTime<Utc>,
+        guild_id: i64,
+        user_id: i64,
+        username: String,
+        #[serde(default)]
+        account_created: Option<DateTime<Utc>>,
+        #[serde(default)]
+        avatar_hash: Option<String>,
+        #[serde(default)]
+        label: Option<Label>,
+    },
+    Message {
+        at: DateTime<Utc>,
+        guild_id: i64,
+        user_id: i64,
+        channel_id: i64,
+        content: String,
+        #[serde(default)]
+        mentioned_roles: Vec<i64>,
+        #[serde(default)]
+        account_created: Option<DateTime<Utc>>,
+        #[serde(default)]
+        label: Option<Label>,
+    },
+    Reaction {
+        at: DateTime<Utc>,
+        guild_id: i64,
+        user_id: i64,
+        message_id: i64,
+        #[serde(default)]
+        label: Option<Label>,
+    },
+}
+
+impl RecordedEvent {
+    fn at(&self) -> Option<DateTime<Utc>> {
+        match self {
+            RecordedEvent::Trap { .. } => None,
+            RecordedEvent::Join { at, .. }
+            | RecordedEvent::Message { at, .. }
+            | RecordedEvent::Reaction { at, .. } => Some(*at),
+        }
+    }
+}
+
+/// What the event should have led to: `malicious` events are expected to be
+/// flagged, `benign` ones are not.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Label {
+    Malicious,
+    Benign,
+}
+
+/// What the bot would have done with one event.
+struct Decision {
+    kind: &'static str,
+    guild_id: i64,
+    user_id: i64,
+    score: f32,
+    /// The bot would have acted on the event: an incident for joins and
+    /// messages, a honeypot catch for bait reactions.
+    flagged: bool,
+    action: Option<ModAction>,
+    reasons: Vec<String>,
+    label: Option<Label>,
+}
+
+impl Decision {
+    fn matches_label(&self) -> Option<bool> {
+        self.label.map(|label| (label == Label::Malicious) == self.flagged)
+    }
+}
+
+/// The detectors wired the way `bot::create_framework` wires them, but on a
+/// simulated clock and in-memory state, with nothing sent to Discord or the
+/// database.
+struct Pipeline {
+    clock: Clock,
+    guild_configs: Arc<GuildConfigCache>,
+    raid_detector: RaidDetector,
+    raid_modes: RaidModeTracker,
+    behavior_analyzer: BehaviorAnalyzer,
+    honeypot: HoneypotSystem,
+    auto_mod: AutoModerator,
+    /// Ban times per guild, standing in for `moderation_actions` when
+    /// deciding on an automatic lockdown.
+    bans: HashMap<i64, Vec<DateTime<Utc>>>,
+}
+
+impl Pipeline {
+    fn new(config: Config, clock: Clock) -> Self {
+        let history_ttl = std::time::Duration::from_secs(config.state.message_history_ttl_secs);
+        let catch_ttl = std::time::Duration::from_secs(config.state.catch_ttl_secs);
+        let state = Arc::new(MemoryState::new(clock.clone()));
+        let guild_configs = Arc::new(GuildConfigCache::new(config));
+
+        Self {
+            raid_detector: RaidDetector::new(state.clone(), clock.clone()),
+            raid_modes: RaidModeTracker::new(guild_configs.clone()),
+            behavior_analyzer: BehaviorAnalyzer::new(state.clone(), history_ttl, clock.clone()),
+            honeypot: HoneypotSystem::new(state, catch_ttl),
+            auto_mod: AutoModerator::new(),
+            guild_configs,
+            clock,
+            bans: HashMap::new(),
+        }
+    }
+
+    async fn handle_join(
+        &mut self,
+        guild_id: i64,
+        user_id: i64,
+        username: String,
+        account_created: DateTime<Utc>,
+        avatar_hash: Option<String>,
+        label: Option<Label>,
+    ) -> Result<(Decision, Vec<RaidTransition>)> {
+        let now = self.clock.now();
+        let guild_config = self.guild_configs.cached(guild_id);
+        let mut transitions = Vec::new();
+
+        self.honeypot.record_join(guild_id, user_id, RecentJoin { joined_at: now, received_at: now }).await?;
+
+        self.raid_detector.record_join(guild_id, JoinEvent {
+            user_id,
+            username,
+            discriminator: None,
+            account_created,
+            join_time: now,
+            avatar_hash,
+        }).await?;
+
+        let raid_analysis = self.raid_detector.analyze_raid_risk(guild_id, &guild_config).await?;
+
+        if guild_config.security.enabled {
+            transitions.extend(self.raid_modes.observe(guild_id, &raid_analysis, now));
+        }
+
+        let flagged = decisions::join_is_incident(&guild_config, &raid_analysis);
+        let action = if flagged {
+            self.auto_mod.determine_action(&guild_config, raid_analysis.threat_score)
+        } else {
+            None
+        };
+
+        if let Some(action) = &action {
+            transitions.extend(self.apply_action(guild_id, action).await?);
+        }
+
+        Ok((Decision {
+            kind: "join",
+            guild_id,
+            user_id,
+            score: raid_analysis.threat_score,
+            flagged,
+            action,
+            reasons: raid_analysis.reasons,
+            label,
+        }, transitions))
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn handle_message(
+        &mut self,
+        guild_id: i64,
+        user_id: i64,
+        channel_id: i64,
+        content: &str,
+        mentioned_roles: &[i64],
+        account_created: DateTime<Utc>,
+        label: Option<Label>,
+    ) -> Result<(Decision, Vec<RaidTransition>)> {
+        let guild_config = self.guild_configs.cached(guild_id);
+        let mut decision = Decision {
+            kind: "message",
+            guild_id,
+            user_id,
+            score: 0.0,
+            flagged: false,
+            action: None,
+            reasons: Vec::new(),
+            label,
+        };
+
+        self.honeypot.check_hidden_channel(guild_id, channel_id, user_id).await?;
+        self.honeypot.check_bait_role(guild_id, mentioned_roles, user_id).await?;
+        self.honeypot.check_fake_command(guild_id, content, user_id).await?;
+
+        if !guild_config.behavior.enabled {
+            return Ok((decision, Vec::new()));
+        }
+
+        self.behavior_analyzer.analyze_message(guild_id, user_id, content, channel_id, &guild_config).await?;
+        let behavioral_metrics = self.behavior_analyzer.get_behavioral_metrics(guild_id, user_id, &guild_config).await?;
+
+        let honeypot_multiplier = self.honeypot.get_threat_multiplier(guild_id, user_id).await?;
+        let account_age = self.clock.now() - account_created;
+
+        let raid_analysis = self.raid_detector.analyze_raid_risk(guild_id, &guild_config).await?;
+
+        let verdict = MessageVerdict::new(&guild_config, &raid_analysis, &behavioral_metrics, honeypot_multiplier, account_age);
+        decision.score = verdict.score;
+        decision.flagged = verdict.is_incident(&guild_config);
+        if !decision.flagged {
+            return Ok((decision, Vec::new()));
+        }
+
+        let catches = self.honeypot.get_user_catches(guild_id, user_id).await?;
+        decision.reasons = verdict.reasons(&behavioral_metrics, &catches, &raid_analysis);
+
+        decision.action = self.auto_mod.determine_action(&guild_config, decision.score);
+        let transitions = match &decision.action {
+            Some(action) => self.apply_action(guild_id, action).await?,
+            None => Vec::new(),
+        };
+
+        Ok((decision, transitions))
+    }
+
+    async fn handle_reaction(
+        &mut self,
+        guild_id: i64,
+        user_id: i64,
+        message_id: i64,
+        label: Option<Label>,
+    ) -> Result<Decision> {
+        let mut decision = Decision {
+            kind: "reaction",
+            guild_id,
+            user_id,
+            score: 0.0,
+            flagged: false,
+            action: None,
+            reasons: Vec::new(),
+            label,
+        };
+
+        if !self.honeypot.is_bait_message(guild_id, message_id) {
+            return Ok(decision);
+        }
+
+        if let Some(join) = self.honeypot.take_recent_join(guild_id, user_id).await? {
+            let reaction_time_ms = BaitResponse::Reaction(self.clock.now()).delay_ms(&join);
+
+            decision.flagged = self.honeypot.check_suspicious_timing(guild_id, user_id, reaction_time_ms).await?;
+            if decision.flagged {
+                decision.reasons.push(format!("Reacted to bait {}ms after joining", reaction_time_ms));
+            }
+        }
+
+        decision.score = self.honeypot.get_threat_multiplier(guild_id, user_id).await?;
+        Ok(decision)
+    }
+
+    /// The raid mode side of `execute_mod_action`: bans can trigger an
+    /// automatic lockdown, and a lockdown escalates straight to raid mode.
+    async fn apply_action(&mut self, guild_id: i64, action: &ModAction) -> Result<Vec<RaidTransition>> {
+        let now = self.clock.now();
+        let guild_config = self.guild_configs.cached(guild_id);
+
+        let (threat_score, reason) = match action {
+            ModAction::Ban { .. } => {
+                let bans = self.bans.entry(guild_id).or_default();
+                bans.push(now);
+                bans.retain(|at| now - *at <= Duration::minutes(60));
+                let recent_bans = bans.len() as u32;
+
+                let threat_score = self.raid_detector.analyze_raid_risk(guild_id, &guild_config).await?.threat_score;
+                if !self.auto_mod.should_lockdown(&guild_config, threat_score, recent_bans) {
+                    return Ok(Vec::new());
+                }
+                (threat_score, format!("{} bans in the last hour", recent_bans))
+            }
+            ModAction::Lockdown => {
+                let threat_score = self.raid_detector.analyze_raid_risk(guild_id, &guild_config).await?.threat_score;
+                (threat_score, "Critical threat detected".to_string())
+            }
+            _ => return Ok(Vec::new()),
+        };
+
+        Ok(self.raid_modes.escalate(guild_id, threat_score, reason, now).into_iter().collect())
+    }
+}
+
+#[derive(Default)]
+struct Summary {
+    events: BTreeMap<&'static str, usize>,
+    flagged: usize,
+    actions: BTreeMap<&'static str, usize>,
+    transitions: usize,
+    true_positives: usize,
+    false_positives: usize,
+    true_negatives: usize,
+    false_negatives: usize,
+}
+
+impl Summary {
+    fn record(&mut self, decision: &Decision) {
+        *self.events.entry(decision.kind).or_default() += 1;
+
+        if decision.flagged {
+            self.flagged += 1;
+        }
+        if let Some(action) = &decision.action {
+            *self.actions.entry(action.name()).or_default() += 1;
+        }
+
+        match (decision.label, decision.flagged) {
+            (Some(Label::Malicious), true) => self.true_positives += 1,
+            (Some(Label::Malicious), false) => self.false_negatives += 1,
+            (Some(Label::Benign), true) => self.false_positives += 1,
+            (Some(Label::Benign), false) => self.true_negatives += 1,
+            (None, _) => {}
+        }
+    }
+
+    fn labelled(&self) -> usize {
+        self.true_positives + self.false_positives + self.true_negatives + self.false_negatives
+    }
+
+    fn mismatches(&self) -> usize {
+        self.false_positives + self.false_negatives
+    }
+
+    fn print(&self, catches: usize, duration: Duration) {
+        let total: usize = self.events.values().sum();
+        let breakdown: Vec<String> = self.events.iter().map(|(kind, count)| format!("{} {}", count, kind)).collect();
+        let actions: Vec<String> = self.actions.iter().map(|(name, count)| format!("{} {}", count, name)).collect();
+
+        println!();
+        println!("Replayed {} event(s) over {}s ({})", total, duration.num_seconds(), breakdown.join(", "));
+        println!("Flagged:          {}", self.flagged);
+        println!("Actions:          {}", if actions.is_empty() { "none".to_string() } else { actions.join(", ") });
+        println!("Honeypot catches: {}", catches);
+        println!("Raid mode:        {} transition(s)", self.transitions);
+
+        let labelled = self.labelled();
+        if labelled == 0 {
+            println!();
+            println!("No labelled events to score");
+            return;
+        }
+
+        println!();
+        println!("{} labelled event(s)", labelled);
+        println!("{:<12} {:>8} {:>12}", "", "flagged", "not flagged");
+        println!("{:<12} {:>8} {:>12}", "malicious", self.true_positives, self.false_negatives);
+        println!("{:<12} {:>8} {:>12}", "benign", self.false_positives, self.true_negatives);
+        println!(
+            "Precision {}  Recall {}  Accuracy {}",
+            ratio(self.true_positives, self.true_positives + self.false_positives),
+            ratio(self.true_positives, self.true_positives + self.false_negatives),
+            ratio(self.true_positives + self.true_negatives, labelled),
+        );
+    }
+}
+
+fn ratio(part: usize, whole: usize) -> String {
+    if whole == 0 {
+        "n/a".to_string()
+    } else {
+        format!("{:.2}", part as f64 / whole as f64)
+    }
+}
+
+/// Feeds a recording through the detectors and prints what the bot would
+/// have decided for each event, then how the decisions compare to the
+/// labels. Reads `config.toml` like the bot does, but needs neither Discord
+/// nor the database.
+pub async fn run(path: &str, fail_on_mismatch: bool) -> Result<()> {
+    let mut events = read_events(path)?;
+    // Stable, so events recorded at the same instant keep their file order.
+    events.sort_by_key(|event| event.at());
+
+    let Some(start) = events.iter().find_map(RecordedEvent::at) else {
+        bail!("{} contains no join, message or reaction events", path);
+    };
+
+    let config = Config::offline().context("Failed to load configuration")?;
+    let clock = Clock::simulated(start);
+    let mut pipeline = Pipeline::new(config, clock.clone());
+    let mut summary = Summary::default();
+    let mut catches = 0;
+    let mut end = start;
+
+    for event in events {
+        if let Some(at) = event.at() {
+            clock.set(at);
+            end = at;
+        }
+
+        let (decision, transitions) = match event {
+            RecordedEvent::Trap { guild_id, trap_type, target } => {
+                pipeline.honeypot.register_trap(guild_id, &trap_type, &target)
+                    .with_context(|| format!("Invalid {} trap for guild {}", trap_type, guild_id))?;
+                continue;
+            }
+            RecordedEvent::Join { guild_id, user_id, username, account_created, avatar_hash, label, .. } => {
+                let account_created = account_created.unwrap_or_else(|| snowflake_created_at(user_id));
+                pipeline.handle_join(guild_id, user_id, username, account_created, avatar_hash, label).await?
+            }
+            RecordedEvent::Message { guild_id, user_id, channel_id, content, mentioned_roles, account_created, label, .. } => {
+                let account_created = account_created.unwrap_or_else(|| snowflake_created_at(user_id));
+                let before = pipeline.honeypot.get_user_catches(guild_id, user_id).await?.len();
+                let result = pipeline.handle_message(guild_id, user_id, channel_id, &content, &mentioned_roles, account_created, label).await?;
+                catches += pipeline.honeypot.get_user_catches(guild_id, user_id).await?.len().saturating_sub(before);
+                result
+            }
+            RecordedEvent::Reaction { guild_id, user_id, message_id, label, .. } => {
+                let before = pipeline.honeypot.get_user_catches(guild_id, user_id).await?.len();
+                let decision = pipeline.handle_reaction(guild_id, user_id, message_id, label).await?;
+                catches += pipeline.honeypot.get_user_catches(guild_id, user_id).await?.len().saturating_sub(before);
+                (decision, Vec::new())
+            }
+        };
+
+        print_decision(clock.now(), &decision);
+        for transition in &transitions {
+            println!(
+                "{}  raid mode  guild {}: {} -> {} (score {:.2})",
+                clock.now().format("%H:%M:%S%.3f"),
+                decision.guild_id,
+                transition.from.as_str(),
+                transition.to.as_str(),
+                transition.threat_score,
+            );
+        }
+
+        summary.record(&decision);
+        summary.transitions += transitions.len();
+    }
+
+    summary.print(catches, end - start);
+
+    if fail_on_mismatch && summary.mismatches() > 0 {
+        bail!("{} event(s) did not match their label", summary.mismatches());
+    }
+
+    Ok(())
+}
+
+fn read_events(path: &str) -> Result<Vec<RecordedEvent>> {
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path))?;
+
+    contents
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| {
+            serde_json::from_str(line).with_context(|| format!("{}:{}: invalid event", path, index + 1))
+        })
+        .collect()
+}
+
+fn print_decision(at: DateTime<Utc>, decision: &Decision) {
+    let action = match (&decision.action, decision.flagged) {
+        (Some(action), _) => action.name(),
+        (None, true) if decision.kind == "reaction" => "catch",
+        (None, true) => "incident",
+        (None, false) => "-",
+    };
+    let verdict = match decision.matches_label() {
+        Some(true) => "✓",
+        Some(false) => "✗",
+        None => " ",
+    };
+
+    println!(
+        "{}  {:<8}  {}/{:<20}  {:.2}  {:<8}  {}  {}",
+        at.format("%H:%M:%S%.3f"),
+        decision.kind,
+        decision.guild_id,
+        decision.user_id,
+        decision.score,
+        action,
+        verdict,
+        decision.reasons.join("; "),
+    );
+}
+
+/// Account creation time encoded in a Discord ID, used when the recording
+/// doesn't say.
+fn snowflake_created_at(id: i64) -> DateTime<Utc> {
+    DateTime::from_timestamp_millis(((id as u64 >> 22) + DISCORD_EPOCH_MS) as i64).unwrap_or_default()
+}
@@ -0,0 +1,150 @@
+use chrono::{DateTime, Duration, Utc};
+use dashmap::DashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use crate::config::AntiNukeConfig;
+
+use super::guild_config::GuildConfigCache;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NukeAction {
+    ChannelDelete,
+    RoleDelete,
+    PermissionEscalation,
+    Ban,
+    Kick,
+    WebhookCreate,
+}
+
+impl NukeAction {
+    pub const ALL: [NukeAction; 6] = [
+        NukeAction::ChannelDelete,
+        NukeAction::RoleDelete,
+        NukeAction::PermissionEscalation,
+        NukeAction::Ban,
+        NukeAction::Kick,
+        NukeAction::WebhookCreate,
+    ];
+
+    pub fn parse(action: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|a| a.as_str() == action.trim().to_lowercase())
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NukeAction::ChannelDelete => "channel_delete",
+            NukeAction::RoleDelete => "role_delete",
+            NukeAction::PermissionEscalation => "permission_escalation",
+            NukeAction::Ban => "ban",
+            NukeAction::Kick => "kick",
+            NukeAction::WebhookCreate => "webhook_create",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            NukeAction::ChannelDelete => "Channels deleted",
+            NukeAction::RoleDelete => "Roles deleted",
+            NukeAction::PermissionEscalation => "Permission escalations",
+            NukeAction::Ban => "Members banned",
+            NukeAction::Kick => "Members kicked",
+            NukeAction::WebhookCreate => "Webhooks created",
+        }
+    }
+
+    pub fn threshold(&self, config: &AntiNukeConfig) -> u32 {
+        match self {
+            NukeAction::ChannelDelete => config.channel_delete,
+            NukeAction::RoleDelete => config.role_delete,
+            NukeAction::PermissionEscalation => config.permission_escalation,
+            NukeAction::Ban => config.ban,
+            NukeAction::Kick => config.kick,
+            NukeAction::WebhookCreate => config.webhook_create,
+        }
+    }
+}
+
+/// An actor who crossed a threshold, with everything they did in the window.
+#[derive(Debug, Clone)]
+pub struct NukeVerdict {
+    pub action: NukeAction,
+    pub count: usize,
+    pub threshold: u32,
+    pub window_secs: u64,
+    pub activity: Vec<(NukeAction, usize)>,
+}
+
+type ActionHistory = Vec<(NukeAction, DateTime<Utc>)>;
+
+/// How many recorded actions pass between sweeps of idle actors.
+const SWEEP_EVERY: u64 = 256;
+
+/// Sliding-window counters of destructive actions per (guild, actor).
+pub struct AntiNukeTracker {
+    configs: Arc<GuildConfigCache>,
+    actions: Arc<DashMap<(i64, i64), ActionHistory>>,
+    records: AtomicU64,
+}
+
+impl AntiNukeTracker {
+    pub fn new(configs: Arc<GuildConfigCache>) -> Self {
+        Self {
+            configs,
+            actions: Arc::new(DashMap::new()),
+            records: AtomicU64::new(0),
+        }
+    }
+
+    /// Drops actors whose last action has left their guild's window every
+    /// `SWEEP_EVERY` records, so one-off moderators don't stay in the map
+    /// forever. Must not be called while holding a reference into it.
+    fn sweep(&self, now: DateTime<Utc>) {
+        if !self.records.fetch_add(1, Ordering::Relaxed).is_multiple_of(SWEEP_EVERY) {
+            return;
+        }
+
+        self.actions.retain(|(guild_id, _), history| {
+            let window = Duration::seconds(self.configs.cached(*guild_id).anti_nuke.window_secs as i64);
+            history.last().is_some_and(|(_, at)| *at > now - window)
+        });
+    }
+
+    /// Records one action and returns a verdict once the actor reaches the
+    /// threshold for it. The actor's history is cleared on a verdict so a
+    /// single burst is only punished once.
+    pub fn record(&self, guild_id: i64, actor_id: i64, action: NukeAction, now: DateTime<Utc>) -> Option<NukeVerdict> {
+        let config = self.configs.cached(guild_id).anti_nuke.clone();
+        let threshold = action.threshold(&config);
+        if !config.enabled || threshold == 0 {
+            return None;
+        }
+
+        self.sweep(now);
+
+        let window_start = now - Duration::seconds(config.window_secs as i64);
+        let mut history = self.actions.entry((guild_id, actor_id)).or_default();
+        history.retain(|(_, at)| *at > window_start);
+        history.push((action, now));
+
+        let count = history.iter().filter(|(a, _)| *a == action).count();
+        if count < threshold as usize {
+            return None;
+        }
+
+        let activity = NukeAction::ALL.into_iter()
+            .map(|a| (a, history.iter().filter(|(h, _)| *h == a).count()))
+            .filter(|(_, n)| *n > 0)
+            .collect();
+        drop(history);
+        self.actions.remove(&(guild_id, actor_id));
+
+        Some(NukeVerdict {
+            action,
+            count,
+            threshold,
+            window_secs: config.window_secs,
+            activity,
+        })
+    }
+}
@@ -1,9 +1,10 @@
-use crate::config::AutoModConfig;
+use crate::config::GuildConfig;
 use crate::database::models::ThreatLevel;
 
-pub struct AutoModerator {
-    config: AutoModConfig,
-}
+/// Maps threat scores to actions using the guild's effective configuration,
+/// which callers load once per event and pass in.
+#[derive(Default)]
+pub struct AutoModerator;
 
 #[derive(Debug, Clone)]
 pub enum ModAction {
@@ -15,19 +16,47 @@ pub enum ModAction {
     Lockdown,
 }
 
+impl ModAction {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ModAction::Monitor => "monitor",
+            ModAction::Timeout { .. } => "timeout",
+            ModAction::Kick { .. } => "kick",
+            ModAction::Ban { .. } => "ban",
+            ModAction::Lockdown => "lockdown",
+        }
+    }
+}
+
 impl AutoModerator {
-    pub fn new(config: AutoModConfig) -> Self {
-        Self { config }
+    pub fn new() -> Self {
+        Self
     }
 
-    pub fn determine_action(&self, threat_score: f32, threat_level: ThreatLevel) -> Option<ModAction> {
-        if !self.config.enabled {
+    pub fn threat_level(&self, guild_config: &GuildConfig, threat_score: f32) -> ThreatLevel {
+        let config = &guild_config.auto_mod;
+
+        if threat_score >= config.critical_threat_threshold {
+            ThreatLevel::Critical
+        } else if threat_score >= config.high_threat_threshold {
+            ThreatLevel::High
+        } else if threat_score >= config.medium_threat_threshold {
+            ThreatLevel::Medium
+        } else {
+            ThreatLevel::Low
+        }
+    }
+
+    pub fn determine_action(&self, guild_config: &GuildConfig, threat_score: f32) -> Option<ModAction> {
+        let config = &guild_config.auto_mod;
+
+        if !config.enabled {
             return None;
         }
 
-        match threat_level {
+        match self.threat_level(guild_config, threat_score) {
             ThreatLevel::Low => {
-                if threat_score >= self.config.low_threat_threshold {
+                if threat_score >= config.low_threat_threshold {
                     Some(ModAction::Monitor)
                 } else {
                     None
@@ -52,7 +81,7 @@ impl AutoModerator {
         }
     }
 
-    pub fn should_lockdown(&self, raid_threat_score: f32, recent_bans: u32) -> bool {
-        raid_threat_score >= self.config.critical_threat_threshold && recent_bans >= 3
+    pub fn should_lockdown(&self, guild_config: &GuildConfig, raid_threat_score: f32, recent_bans: u32) -> bool {
+        raid_threat_score >= guild_config.auto_mod.critical_threat_threshold && recent_bans >= 3
     }
 }
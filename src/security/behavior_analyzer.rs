@@ -1,24 +1,21 @@
+use anyhow::Result;
 use chrono::{DateTime, Duration, Utc};
-use dashmap::DashMap;
-use std::collections::VecDeque;
 use std::sync::Arc;
 use strsim::jaro_winkler;
 
-use super::MessageAnalysis;
+use super::{MessageAnalysis, MessageRecord};
+use super::state::StateBackend;
+use crate::config::GuildConfig;
+use crate::metrics::metrics;
+use crate::utils::time::Clock;
 
 const MAX_MESSAGE_HISTORY: usize = 100;
 
 pub struct BehaviorAnalyzer {
-    message_history: Arc<DashMap<(i64, i64), VecDeque<MessageRecord>>>,
-}
-
-#[derive(Debug, Clone)]
-struct MessageRecord {
-    content: String,
-    timestamp: DateTime<Utc>,
-    _channel_id: i64,
-    has_links: bool,
-    mention_count: usize,
+    state: Arc<dyn StateBackend>,
+    /// How long a member's history is kept after their last message.
+    history_ttl: std::time::Duration,
+    clock: Clock,
 }
 
 #[derive(Debug, Clone)]
@@ -33,19 +30,28 @@ pub struct BehavioralMetrics {
 }
 
 impl BehaviorAnalyzer {
-    pub fn new() -> Self {
+    pub fn new(
+        state: Arc<dyn StateBackend>,
+        history_ttl: std::time::Duration,
+        clock: Clock,
+    ) -> Self {
         Self {
-            message_history: Arc::new(DashMap::new()),
+            state,
+            history_ttl,
+            clock,
         }
     }
 
-    pub fn analyze_message(
+    pub async fn analyze_message(
         &self,
         guild_id: i64,
         user_id: i64,
         content: &str,
         channel_id: i64,
-    ) -> MessageAnalysis {
+        config: &GuildConfig,
+    ) -> Result<MessageAnalysis> {
+        metrics().messages_analyzed.inc();
+
         let has_links = content.contains("http://") || content.contains("https://");
         let link_count = content.matches("http").count();
         
@@ -68,26 +74,22 @@ impl BehaviorAnalyzer {
 
         let record = MessageRecord {
             content: content.to_string(),
-            timestamp: Utc::now(),
-            _channel_id: channel_id,
+            timestamp: self.clock.now(),
+            channel_id,
             has_links,
+            link_count,
             mention_count,
         };
 
-        let mut history = self.message_history
-            .entry((guild_id, user_id))
-            .or_insert_with(VecDeque::new);
-        
-        history.push_back(record);
-        if history.len() > MAX_MESSAGE_HISTORY {
-            history.pop_front();
-        }
+        let history = self.state
+            .push_message(guild_id, user_id, &record, MAX_MESSAGE_HISTORY, self.history_ttl)
+            .await?;
 
         let text_similarity = self.calculate_text_similarity(&history);
         
-        let is_burst = self.detect_burst(&history);
+        let is_burst = self.detect_burst(&history, config.auto_mod.message_burst_count, config.auto_mod.message_burst_seconds, record.timestamp);
 
-        MessageAnalysis {
+        Ok(MessageAnalysis {
             has_links,
             link_count,
             mention_count,
@@ -95,17 +97,14 @@ impl BehaviorAnalyzer {
             emoji_count,
             text_similarity,
             is_burst,
-        }
+        })
     }
 
-    pub fn get_behavioral_metrics(&self, guild_id: i64, user_id: i64) -> BehavioralMetrics {
-        let history = match self.message_history.get(&(guild_id, user_id)) {
-            Some(h) => h,
-            None => return BehavioralMetrics::default(),
-        };
+    pub async fn get_behavioral_metrics(&self, guild_id: i64, user_id: i64, config: &GuildConfig) -> Result<BehavioralMetrics> {
+        let history = self.state.message_history(guild_id, user_id).await?;
 
         if history.is_empty() {
-            return BehavioralMetrics::default();
+            return Ok(BehavioralMetrics::default());
         }
 
         let messages_with_links = history.iter().filter(|m| m.has_links).count();
@@ -117,21 +116,32 @@ impl BehaviorAnalyzer {
         let spam_score = self.calculate_spam_score(&history);
         let caps_ratio = self.calculate_average_caps(&history);
         let emoji_density = self.calculate_emoji_density(&history);
-        let burst_detected = self.detect_burst(&history);
+        let now = self.clock.now();
+        let burst_detected = self.detect_burst(&history, config.auto_mod.message_burst_count, config.auto_mod.message_burst_seconds, now);
+
+        let recent_cutoff = now - Duration::minutes(1);
+        let recent_links: usize = history
+            .iter()
+            .filter(|m| m.timestamp >= recent_cutoff)
+            .map(|m| m.link_count)
+            .sum();
+        let max_mentions = history.iter().map(|m| m.mention_count).max().unwrap_or(0);
 
         let mut threat_score: f32 = 0.0;
 
-        if spam_score > 0.8 {
+        if spam_score > config.security.spam_similarity_threshold as f32 {
             threat_score += 0.3;
         } else if spam_score > 0.6 {
             threat_score += 0.15;
         }
 
-        if link_density > 0.5 && history.len() < 10 {
+        if (link_density > 0.5 && history.len() < 10)
+            || recent_links >= config.behavior.link_spam_threshold as usize
+        {
             threat_score += 0.25;
         }
 
-        if mention_ratio > 3.0 {
+        if mention_ratio > 3.0 || max_mentions >= config.behavior.mention_spam_threshold as usize {
             threat_score += 0.2;
         }
 
@@ -143,7 +153,7 @@ impl BehaviorAnalyzer {
             threat_score += 0.2;
         }
 
-        BehavioralMetrics {
+        Ok(BehavioralMetrics {
             spam_score,
             link_density,
             mention_ratio,
@@ -151,10 +161,10 @@ impl BehaviorAnalyzer {
             emoji_density,
             burst_detected,
             threat_score: threat_score.min(1.0),
-        }
+        })
     }
 
-    fn calculate_text_similarity(&self, history: &VecDeque<MessageRecord>) -> f32 {
+    fn calculate_text_similarity(&self, history: &[MessageRecord]) -> f32 {
         if history.len() < 2 {
             return 0.0;
         }
@@ -179,13 +189,13 @@ impl BehaviorAnalyzer {
         }
     }
 
-    fn calculate_spam_score(&self, history: &VecDeque<MessageRecord>) -> f32 {
+    fn calculate_spam_score(&self, history: &[MessageRecord]) -> f32 {
         if history.len() < 3 {
             return 0.0;
         }
 
         let recent: Vec<_> = history.iter().rev().take(20).collect();
-        let similarity = self.calculate_text_similarity(&history.iter().rev().take(20).cloned().collect());
+        let similarity = self.calculate_text_similarity(&history.iter().rev().take(20).cloned().collect::<Vec<_>>());
 
         let mut score = similarity;
 
@@ -226,7 +236,7 @@ impl BehaviorAnalyzer {
         variance.sqrt()
     }
 
-    fn calculate_average_caps(&self, history: &VecDeque<MessageRecord>) -> f32 {
+    fn calculate_average_caps(&self, history: &[MessageRecord]) -> f32 {
         if history.is_empty() {
             return 0.0;
         }
@@ -244,7 +254,7 @@ impl BehaviorAnalyzer {
         }
     }
 
-    fn calculate_emoji_density(&self, history: &VecDeque<MessageRecord>) -> f32 {
+    fn calculate_emoji_density(&self, history: &[MessageRecord]) -> f32 {
         if history.is_empty() {
             return 0.0;
         }
@@ -269,10 +279,10 @@ impl BehaviorAnalyzer {
         }
     }
 
-    fn detect_burst(&self, history: &VecDeque<MessageRecord>) -> bool {
-        let cutoff = Utc::now() - Duration::seconds(10);
+    fn detect_burst(&self, history: &[MessageRecord], burst_count: u32, burst_seconds: u64, now: DateTime<Utc>) -> bool {
+        let cutoff = now - Duration::seconds(burst_seconds as i64);
         let recent_count = history.iter().filter(|m| m.timestamp >= cutoff).count();
-        recent_count >= 10
+        recent_count >= burst_count as usize
     }
 }
 
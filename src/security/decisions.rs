@@ -0,0 +1,104 @@
+use chrono::{DateTime, Duration, Utc};
+
+use crate::config::GuildConfig;
+
+use super::behavior_analyzer::BehavioralMetrics;
+use super::honeypot::{HoneypotCatch, RecentJoin};
+use super::raid_detector::RaidAnalysis;
+use super::threat_calculator::ThreatCalculator;
+
+/// A join becomes an incident when it is part of a raid and protection is on.
+pub fn join_is_incident(guild_config: &GuildConfig, raid_analysis: &RaidAnalysis) -> bool {
+    guild_config.security.enabled && raid_analysis.is_raid
+}
+
+/// How a message scores once the detectors have seen it.
+#[derive(Debug, Clone)]
+pub struct MessageVerdict {
+    pub score: f32,
+    pub account_age_days: i64,
+    pub is_new_account: bool,
+}
+
+impl MessageVerdict {
+    pub fn new(
+        guild_config: &GuildConfig,
+        raid_analysis: &RaidAnalysis,
+        behavioral_metrics: &BehavioralMetrics,
+        honeypot_multiplier: f32,
+        account_age: Duration,
+    ) -> Self {
+        let is_new_account = account_age.num_days() < guild_config.security.new_account_days as i64;
+
+        Self {
+            score: ThreatCalculator::calculate_combined_threat(
+                raid_analysis,
+                behavioral_metrics,
+                honeypot_multiplier,
+                is_new_account
+            ),
+            account_age_days: account_age.num_days(),
+            is_new_account,
+        }
+    }
+
+    /// Messages over the low threshold are kept as forensic events.
+    pub fn is_logged(&self, guild_config: &GuildConfig) -> bool {
+        self.score > guild_config.auto_mod.low_threat_threshold
+    }
+
+    /// Logged messages that reach the medium threshold become incidents.
+    pub fn is_incident(&self, guild_config: &GuildConfig) -> bool {
+        self.is_logged(guild_config) && self.score >= guild_config.auto_mod.medium_threat_threshold
+    }
+
+    /// Why the message became an incident, as shown in alerts.
+    pub fn reasons(
+        &self,
+        behavioral_metrics: &BehavioralMetrics,
+        catches: &[HoneypotCatch],
+        raid_analysis: &RaidAnalysis,
+    ) -> Vec<String> {
+        let mut reasons = Vec::new();
+        if behavioral_metrics.burst_detected {
+            reasons.push("Message burst detected".to_string());
+        }
+        if self.is_new_account {
+            reasons.push(format!("Account is {} days old", self.account_age_days));
+        }
+        for catch in catches {
+            reasons.push(format!("Honeypot trap: {} ({})", catch.trap_name, catch.trap_type));
+        }
+        reasons.extend(raid_analysis.reasons.iter().cloned());
+        reasons
+    }
+}
+
+/// A member's answer to a bait message.
+#[derive(Debug, Clone, Copy)]
+pub enum BaitResponse {
+    /// Interaction creation time, from its snowflake.
+    Button(DateTime<Utc>),
+    /// When the reaction event was received.
+    Reaction(DateTime<Utc>),
+}
+
+impl BaitResponse {
+    pub fn source(&self) -> &'static str {
+        match self {
+            BaitResponse::Button(_) => "button",
+            BaitResponse::Reaction(_) => "reaction",
+        }
+    }
+
+    /// Milliseconds from the join to the answer. Buttons are compared with
+    /// Discord's join time; reactions carry no timestamp, so they are compared
+    /// with when the join was received, which had the same gateway latency.
+    pub fn delay_ms(&self, join: &RecentJoin) -> u64 {
+        let elapsed = match self {
+            BaitResponse::Button(responded_at) => *responded_at - join.joined_at,
+            BaitResponse::Reaction(received_at) => *received_at - join.received_at,
+        };
+        elapsed.num_milliseconds().max(0) as u64
+    }
+}
@@ -0,0 +1,66 @@
+use anyhow::Result;
+use dashmap::DashMap;
+use sqlx::PgPool;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::config::{Config, GuildConfig};
+use crate::database::queries;
+
+const CACHE_TTL: Duration = Duration::from_secs(300);
+
+/// Per-guild effective configuration, loaded from the `guilds` table and
+/// layered over the file defaults. Entries are reloaded whenever an admin
+/// changes a setting and expire after `CACHE_TTL` so other processes pick
+/// up changes too.
+pub struct GuildConfigCache {
+    config: Config,
+    defaults: Arc<GuildConfig>,
+    entries: Arc<DashMap<i64, (Arc<GuildConfig>, Instant)>>,
+}
+
+impl GuildConfigCache {
+    pub fn new(config: Config) -> Self {
+        let defaults = Arc::new(config.for_guild(None));
+        Self {
+            config,
+            defaults,
+            entries: Arc::new(DashMap::new()),
+        }
+    }
+
+    pub async fn get(&self, pool: &PgPool, guild_id: i64) -> Result<Arc<GuildConfig>> {
+        if let Some(entry) = self.entries.get(&guild_id) {
+            if entry.1.elapsed() < CACHE_TTL {
+                return Ok(entry.0.clone());
+            }
+        }
+
+        self.reload(pool, guild_id).await
+    }
+
+    pub async fn reload(&self, pool: &PgPool, guild_id: i64) -> Result<Arc<GuildConfig>> {
+        let guild = queries::get_guild(pool, guild_id).await?;
+        let effective = Arc::new(self.config.for_guild(guild.as_ref()));
+        self.entries.insert(guild_id, (effective.clone(), Instant::now()));
+        Ok(effective)
+    }
+
+    /// The file configuration every guild's settings are layered over.
+    pub fn file_config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Last loaded configuration for the guild, or the file defaults if the
+    /// guild has not been loaded yet.
+    pub fn cached(&self, guild_id: i64) -> Arc<GuildConfig> {
+        self.entries
+            .get(&guild_id)
+            .map(|entry| entry.0.clone())
+            .unwrap_or_else(|| self.defaults.clone())
+    }
+
+    pub fn invalidate(&self, guild_id: i64) {
+        self.entries.remove(&guild_id);
+    }
+}
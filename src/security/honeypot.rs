@@ -1,13 +1,49 @@
+use anyhow::{bail, Result};
+use chrono::{DateTime, Utc};
 use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
 use std::sync::Arc;
+use std::time::Duration;
+
+use super::state::StateBackend;
+use crate::database::queries;
+
+pub const TRAP_HIDDEN_CHANNEL: &str = "hidden_channel";
+pub const TRAP_FAKE_COMMAND: &str = "fake_command";
+pub const TRAP_BAIT_ROLE: &str = "bait_role";
+/// Target is stored as `channel_id/message_id`.
+pub const TRAP_BAIT_MESSAGE: &str = "bait_message";
+pub const TRAP_SUSPICIOUS_TIMING: &str = "suspicious_timing";
+
+const JOIN_TRACKING_WINDOW: Duration = Duration::from_secs(10 * 60);
+/// Catches kept per member. The threat multiplier averages them, so the
+/// newest ones are enough.
+const MAX_CATCH_HISTORY: usize = 50;
 
 pub struct HoneypotSystem {
     hidden_channels: Arc<DashMap<i64, Vec<i64>>>,
     fake_commands: Arc<DashMap<i64, Vec<String>>>,
-    catches: Arc<DashMap<(i64, i64), Vec<HoneypotCatch>>>,
+    bait_roles: Arc<DashMap<i64, Vec<i64>>>,
+    bait_messages: Arc<DashMap<i64, Vec<i64>>>,
+    /// Recent joins and catches live in the state backend; the traps above
+    /// are loaded from the database by every process.
+    state: Arc<dyn StateBackend>,
+    /// How long a member's catches count towards their threat score.
+    catch_ttl: Duration,
+}
+
+/// When a member joined, kept until they answer a bait message.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct RecentJoin {
+    /// Discord's join time, compared against interaction snowflakes.
+    pub joined_at: DateTime<Utc>,
+    /// When the join event reached the bot, compared against reactions, which
+    /// carry no timestamp of their own.
+    pub received_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HoneypotCatch {
     pub trap_type: String,
     pub trap_name: String,
@@ -15,15 +51,59 @@ pub struct HoneypotCatch {
 }
 
 impl HoneypotSystem {
-    pub fn new() -> Self {
+    pub fn new(state: Arc<dyn StateBackend>, catch_ttl: Duration) -> Self {
         Self {
             hidden_channels: Arc::new(DashMap::new()),
             fake_commands: Arc::new(DashMap::new()),
-            catches: Arc::new(DashMap::new()),
+            bait_roles: Arc::new(DashMap::new()),
+            bait_messages: Arc::new(DashMap::new()),
+            state,
+            catch_ttl,
+        }
+    }
+
+    /// Replaces the guild's in-memory traps with the ones stored in
+    /// `honeypot_traps`. Called when the guild becomes available and after
+    /// every change made through `/kitsune honeypot`.
+    pub async fn reload(&self, pool: &PgPool, guild_id: i64) -> Result<usize> {
+        let traps = queries::get_honeypot_traps(pool, guild_id).await?;
+
+        self.hidden_channels.remove(&guild_id);
+        self.fake_commands.remove(&guild_id);
+        self.bait_roles.remove(&guild_id);
+        self.bait_messages.remove(&guild_id);
+
+        for trap in &traps {
+            if let Err(e) = self.register_trap(guild_id, &trap.trap_type, &trap.target) {
+                tracing::warn!("Ignoring honeypot trap {}: {}", trap.id, e);
+            }
+        }
+
+        Ok(traps.len())
+    }
+
+    /// Arms one trap as stored in `honeypot_traps`.
+    pub fn register_trap(&self, guild_id: i64, trap_type: &str, target: &str) -> Result<()> {
+        match trap_type {
+            TRAP_HIDDEN_CHANNEL => match target.parse() {
+                Ok(channel_id) => self.register_hidden_channel(guild_id, channel_id),
+                Err(_) => bail!("invalid channel ID {}", target),
+            },
+            TRAP_FAKE_COMMAND => self.register_fake_command(guild_id, target.to_string()),
+            TRAP_BAIT_ROLE => match target.parse() {
+                Ok(role_id) => self.register_bait_role(guild_id, role_id),
+                Err(_) => bail!("invalid role ID {}", target),
+            },
+            TRAP_BAIT_MESSAGE => match parse_bait_message_target(target) {
+                Some((_, message_id)) => self.bait_messages.entry(guild_id).or_default().push(message_id),
+                None => bail!("invalid message target {}", target),
+            },
+            other => bail!("unknown type {}", other),
         }
+
+        Ok(())
     }
 
-    #[allow(dead_code)]
     pub fn register_hidden_channel(&self, guild_id: i64, channel_id: i64) {
         let mut channels = self.hidden_channels.entry(guild_id).or_insert_with(Vec::new);
         if !channels.contains(&channel_id) {
@@ -31,83 +111,128 @@ impl HoneypotSystem {
         }
     }
 
-    #[allow(dead_code)]
     pub fn register_fake_command(&self, guild_id: i64, command: String) {
+        let command = command.to_lowercase();
         let mut commands = self.fake_commands.entry(guild_id).or_insert_with(Vec::new);
         if !commands.contains(&command) {
             commands.push(command);
         }
     }
 
-    pub fn check_hidden_channel(&self, guild_id: i64, channel_id: i64, user_id: i64) -> bool {
-        if let Some(channels) = self.hidden_channels.get(&guild_id) {
-            if channels.contains(&channel_id) {
-                self.record_catch(guild_id, user_id, HoneypotCatch {
-                    trap_type: "hidden_channel".to_string(),
-                    trap_name: format!("channel_{}", channel_id),
-                    severity: 0.8,
-                });
-                return true;
-            }
+    pub fn register_bait_role(&self, guild_id: i64, role_id: i64) {
+        let mut roles = self.bait_roles.entry(guild_id).or_default();
+        if !roles.contains(&role_id) {
+            roles.push(role_id);
         }
-        false
-    }
-
-    pub fn check_fake_command(&self, guild_id: i64, command: &str, user_id: i64) -> bool {
-        if let Some(commands) = self.fake_commands.get(&guild_id) {
-            let command_lower = command.to_lowercase();
-            for fake_cmd in commands.iter() {
-                if command_lower.starts_with(fake_cmd) {
-                    self.record_catch(guild_id, user_id, HoneypotCatch {
-                        trap_type: "fake_command".to_string(),
-                        trap_name: fake_cmd.clone(),
-                        severity: 0.7,
-                    });
-                    return true;
-                }
-            }
+    }
+
+    pub async fn check_hidden_channel(&self, guild_id: i64, channel_id: i64, user_id: i64) -> Result<bool> {
+        let is_trap = self.hidden_channels
+            .get(&guild_id)
+            .map(|channels| channels.contains(&channel_id))
+            .unwrap_or(false);
+
+        if is_trap {
+            self.record_catch(guild_id, user_id, HoneypotCatch {
+                trap_type: TRAP_HIDDEN_CHANNEL.to_string(),
+                trap_name: format!("channel_{}", channel_id),
+                severity: 0.8,
+            }).await?;
         }
-        false
+        Ok(is_trap)
+    }
+
+    pub async fn check_fake_command(&self, guild_id: i64, command: &str, user_id: i64) -> Result<bool> {
+        let command_lower = command.to_lowercase();
+        let matched = self.fake_commands.get(&guild_id).and_then(|commands| {
+            commands.iter().find(|fake_cmd| command_lower.starts_with(fake_cmd.as_str())).cloned()
+        });
+
+        let Some(fake_cmd) = matched else {
+            return Ok(false);
+        };
+
+        self.record_catch(guild_id, user_id, HoneypotCatch {
+            trap_type: TRAP_FAKE_COMMAND.to_string(),
+            trap_name: fake_cmd,
+            severity: 0.7,
+        }).await?;
+        Ok(true)
+    }
+
+    /// Bait roles are never given to anyone, so only something that scraped the
+    /// role list (rather than a person reading the server) would mention one.
+    pub async fn check_bait_role(&self, guild_id: i64, mentioned_roles: &[i64], user_id: i64) -> Result<Option<i64>> {
+        let role_id = self.bait_roles.get(&guild_id).and_then(|roles| {
+            mentioned_roles.iter().find(|id| roles.contains(id)).copied()
+        });
+
+        let Some(role_id) = role_id else {
+            return Ok(None);
+        };
+
+        self.record_catch(guild_id, user_id, HoneypotCatch {
+            trap_type: TRAP_BAIT_ROLE.to_string(),
+            trap_name: format!("role_{}", role_id),
+            severity: 0.8,
+        }).await?;
+        Ok(Some(role_id))
     }
 
-    #[allow(dead_code)]
-    pub fn check_suspicious_timing(&self, guild_id: i64, user_id: i64, reaction_time_ms: u64) -> bool {
+    pub fn is_bait_message(&self, guild_id: i64, message_id: i64) -> bool {
+        self.bait_messages
+            .get(&guild_id)
+            .map(|messages| messages.contains(&message_id))
+            .unwrap_or(false)
+    }
+
+    /// Remembers when a member joined so a reaction to a bait message can be
+    /// timed from the join. Only the last few minutes are kept.
+    pub async fn record_join(&self, guild_id: i64, user_id: i64, join: RecentJoin) -> Result<()> {
+        self.state.set_recent_join(guild_id, user_id, &join, JOIN_TRACKING_WINDOW).await
+    }
+
+    /// The member's join, removed so only their first answer is timed.
+    pub async fn take_recent_join(&self, guild_id: i64, user_id: i64) -> Result<Option<RecentJoin>> {
+        self.state.take_recent_join(guild_id, user_id).await
+    }
+
+    pub async fn check_suspicious_timing(&self, guild_id: i64, user_id: i64, reaction_time_ms: u64) -> Result<bool> {
         if reaction_time_ms < 100 {
             self.record_catch(guild_id, user_id, HoneypotCatch {
-                trap_type: "suspicious_timing".to_string(),
+                trap_type: TRAP_SUSPICIOUS_TIMING.to_string(),
                 trap_name: format!("reaction_{}ms", reaction_time_ms),
                 severity: 0.6,
-            });
-            return true;
+            }).await?;
+            return Ok(true);
         }
-        false
+        Ok(false)
+    }
+
+    async fn record_catch(&self, guild_id: i64, user_id: i64, catch: HoneypotCatch) -> Result<()> {
+        self.state.push_catch(guild_id, user_id, &catch, MAX_CATCH_HISTORY, self.catch_ttl).await
     }
 
-    fn record_catch(&self, guild_id: i64, user_id: i64, catch: HoneypotCatch) {
-        let mut catches = self.catches.entry((guild_id, user_id)).or_insert_with(Vec::new);
-        catches.push(catch);
+    pub async fn get_user_catches(&self, guild_id: i64, user_id: i64) -> Result<Vec<HoneypotCatch>> {
+        self.state.catches(guild_id, user_id).await
     }
 
-    pub fn get_user_catches(&self, guild_id: i64, user_id: i64) -> Vec<HoneypotCatch> {
-        self.catches
-            .get(&(guild_id, user_id))
-            .map(|c| c.clone())
-            .unwrap_or_default()
+    pub async fn clear_catches(&self, guild_id: i64) -> Result<()> {
+        self.state.clear_catches(guild_id).await
     }
 
-    pub fn get_threat_multiplier(&self, guild_id: i64, user_id: i64) -> f32 {
-        let catches = self.get_user_catches(guild_id, user_id);
+    pub async fn get_threat_multiplier(&self, guild_id: i64, user_id: i64) -> Result<f32> {
+        let catches = self.get_user_catches(guild_id, user_id).await?;
         if catches.is_empty() {
-            return 0.0;
+            return Ok(0.0);
         }
 
         let total_severity: f32 = catches.iter().map(|c| c.severity).sum();
-        (total_severity / catches.len() as f32).min(1.0)
+        Ok((total_severity / catches.len() as f32).min(1.0))
     }
 }
 
-impl Default for HoneypotSystem {
-    fn default() -> Self {
-        Self::new()
-    }
+pub fn parse_bait_message_target(target: &str) -> Option<(i64, i64)> {
+    let (channel_id, message_id) = target.split_once('/')?;
+    Some((channel_id.parse().ok()?, message_id.parse().ok()?))
 }
@@ -3,6 +3,13 @@ pub mod behavior_analyzer;
 pub mod honeypot;
 pub mod auto_mod;
 pub mod threat_calculator;
+pub mod guild_config;
+pub mod raid_mode;
+pub mod anti_nuke;
+pub mod state;
+/// Verdicts shared by the gateway event handlers and `kitsune replay`, so a
+/// replay decides exactly what the bot would.
+pub mod decisions;
 
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
@@ -17,6 +24,17 @@ pub struct JoinEvent {
     pub avatar_hash: Option<String>,
 }
 
+/// One message in a member's recent history, as kept by the state backend.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MessageRecord {
+    pub content: String,
+    pub timestamp: DateTime<Utc>,
+    pub channel_id: i64,
+    pub has_links: bool,
+    pub link_count: usize,
+    pub mention_count: usize,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MessageAnalysis {
     pub has_links: bool,
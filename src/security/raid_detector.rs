@@ -1,14 +1,20 @@
+use anyhow::Result;
 use chrono::{DateTime, Duration, Utc};
-use dashmap::DashMap;
 use std::sync::Arc;
 use strsim::jaro_winkler;
 
 use super::JoinEvent;
-use crate::config::SecurityConfig;
+use super::state::StateBackend;
+use crate::config::GuildConfig;
+use crate::metrics::metrics;
+use crate::utils::time::Clock;
+
+/// How long joins are kept; longer than the widest analysis window.
+const JOIN_RETENTION_MINUTES: i64 = 10;
 
 pub struct RaidDetector {
-    config: SecurityConfig,
-    join_events: Arc<DashMap<i64, Vec<JoinEvent>>>,
+    state: Arc<dyn StateBackend>,
+    clock: Clock,
 }
 
 #[derive(Debug, Clone)]
@@ -26,27 +32,33 @@ pub struct RaidAnalysis {
 }
 
 impl RaidDetector {
-    pub fn new(config: SecurityConfig) -> Self {
+    pub fn new(state: Arc<dyn StateBackend>, clock: Clock) -> Self {
         Self {
-            config,
-            join_events: Arc::new(DashMap::new()),
+            state,
+            clock,
         }
     }
 
-    pub fn record_join(&self, guild_id: i64, event: JoinEvent) {
-        let mut events = self.join_events.entry(guild_id).or_insert_with(Vec::new);
-        events.push(event.clone());
-        
-        self.cleanup_old_events(guild_id);
+    pub async fn record_join(&self, guild_id: i64, event: JoinEvent) -> Result<()> {
+        let retention = Duration::minutes(JOIN_RETENTION_MINUTES);
+        self.state.push_join(guild_id, &event, self.clock.now() - retention, retention.to_std()?).await?;
+        metrics().joins_recorded.inc();
+
+        Ok(())
     }
 
-    pub fn analyze_raid_risk(&self, guild_id: i64) -> RaidAnalysis {
-        let events = match self.join_events.get(&guild_id) {
-            Some(e) => e,
-            None => return RaidAnalysis::safe(),
-        };
+    pub async fn analyze_raid_risk(&self, guild_id: i64, guild_config: &GuildConfig) -> Result<RaidAnalysis> {
+        let events = self.state
+            .joins_since(guild_id, self.clock.now() - Duration::minutes(JOIN_RETENTION_MINUTES))
+            .await?;
+
+        if events.is_empty() {
+            record_raid_score(guild_id, 0.0);
+            return Ok(RaidAnalysis::safe());
+        }
 
-        let now = Utc::now();
+        let config = &guild_config.security;
+        let now = self.clock.now();
         let mut reasons = Vec::new();
 
         let join_rate_5s = self.count_joins_in_window(&events, now, Duration::seconds(5));
@@ -54,33 +66,38 @@ impl RaidDetector {
         let join_rate_1m = self.count_joins_in_window(&events, now, Duration::minutes(1));
         let join_rate_5m = self.count_joins_in_window(&events, now, Duration::minutes(5));
 
-        let new_account_ratio = self.calculate_new_account_ratio(&events, now);
-        let username_similarity = self.calculate_username_similarity(&events);
-        let avatar_duplication = self.calculate_avatar_duplication(&events);
+        let new_account_ratio = self.calculate_new_account_ratio(&events, now, config.new_account_days);
+        let username_similarity = self.calculate_username_similarity(&events, now);
+        let avatar_duplication = self.calculate_avatar_duplication(&events, now);
 
         let mut threat_score = 0.0f32;
 
-        if join_rate_5s >= self.config.raid_threshold_5s {
+        if join_rate_5s >= config.raid_threshold_5s {
             threat_score += 0.3;
             reasons.push(format!("{} joins in 5 seconds", join_rate_5s));
         }
 
-        if join_rate_30s >= self.config.raid_threshold_30s {
+        if join_rate_30s >= config.raid_threshold_30s {
             threat_score += 0.25;
             reasons.push(format!("{} joins in 30 seconds", join_rate_30s));
         }
 
-        if join_rate_1m >= self.config.raid_threshold_1m {
+        if join_rate_1m >= config.raid_threshold_1m {
             threat_score += 0.2;
             reasons.push(format!("{} joins in 1 minute", join_rate_1m));
         }
 
+        if join_rate_5m >= config.raid_threshold_5m {
+            threat_score += 0.15;
+            reasons.push(format!("{} joins in 5 minutes", join_rate_5m));
+        }
+
         if new_account_ratio > 0.7 {
             threat_score += 0.25;
             reasons.push(format!("{:.0}% new accounts", new_account_ratio * 100.0));
         }
 
-        if username_similarity > self.config.username_similarity_threshold as f32 {
+        if username_similarity > config.username_similarity_threshold as f32 {
             threat_score += 0.2;
             reasons.push(format!("High username similarity ({:.2})", username_similarity));
         }
@@ -91,10 +108,12 @@ impl RaidDetector {
         }
 
         let is_raid = threat_score >= 0.6;
+        let threat_score = threat_score.min(1.0);
+        record_raid_score(guild_id, threat_score);
 
-        RaidAnalysis {
+        Ok(RaidAnalysis {
             is_raid,
-            threat_score: threat_score.min(1.0),
+            threat_score,
             join_rate_5s,
             join_rate_30s,
             join_rate_1m,
@@ -103,7 +122,7 @@ impl RaidDetector {
             username_similarity,
             avatar_duplication,
             reasons,
-        }
+        })
     }
 
     fn count_joins_in_window(
@@ -119,7 +138,7 @@ impl RaidDetector {
             .count() as u32
     }
 
-    fn calculate_new_account_ratio(&self, events: &[JoinEvent], now: DateTime<Utc>) -> f32 {
+    fn calculate_new_account_ratio(&self, events: &[JoinEvent], now: DateTime<Utc>, new_account_days: u32) -> f32 {
         if events.is_empty() {
             return 0.0;
         }
@@ -131,7 +150,7 @@ impl RaidDetector {
             return 0.0;
         }
 
-        let new_account_threshold = Duration::days(self.config.new_account_days as i64);
+        let new_account_threshold = Duration::days(new_account_days as i64);
         let new_accounts = recent_joins
             .iter()
             .filter(|e| {
@@ -143,12 +162,12 @@ impl RaidDetector {
         new_accounts as f32 / recent_joins.len() as f32
     }
 
-    fn calculate_username_similarity(&self, events: &[JoinEvent]) -> f32 {
+    fn calculate_username_similarity(&self, events: &[JoinEvent], now: DateTime<Utc>) -> f32 {
         if events.len() < 2 {
             return 0.0;
         }
 
-        let cutoff = Utc::now() - Duration::minutes(1);
+        let cutoff = now - Duration::minutes(1);
         let recent: Vec<_> = events.iter().filter(|e| e.join_time >= cutoff).collect();
 
         if recent.len() < 2 {
@@ -170,12 +189,12 @@ impl RaidDetector {
         }
     }
 
-    fn calculate_avatar_duplication(&self, events: &[JoinEvent]) -> f32 {
+    fn calculate_avatar_duplication(&self, events: &[JoinEvent], now: DateTime<Utc>) -> f32 {
         if events.is_empty() {
             return 0.0;
         }
 
-        let cutoff = Utc::now() - Duration::minutes(1);
+        let cutoff = now - Duration::minutes(1);
         let recent: Vec<_> = events.iter().filter(|e| e.join_time >= cutoff).collect();
 
         if recent.len() < 2 {
@@ -192,13 +211,6 @@ impl RaidDetector {
         let max_duplicates = avatar_counts.values().max().copied().unwrap_or(0);
         max_duplicates as f32 / recent.len() as f32
     }
-
-    fn cleanup_old_events(&self, guild_id: i64) {
-        if let Some(mut events) = self.join_events.get_mut(&guild_id) {
-            let cutoff = Utc::now() - Duration::minutes(10);
-            events.retain(|e| e.join_time >= cutoff);
-        }
-    }
 }
 
 impl RaidAnalysis {
@@ -217,3 +229,7 @@ impl RaidAnalysis {
         }
     }
 }
+
+fn record_raid_score(guild_id: i64, score: f32) {
+    metrics().raid_score.with_label_values(&[&guild_id.to_string()]).set(score as f64);
+}
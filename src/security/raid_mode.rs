@@ -0,0 +1,317 @@
+use chrono::{DateTime, Duration, Utc};
+use dashmap::DashMap;
+use std::sync::Arc;
+
+use super::guild_config::GuildConfigCache;
+use super::raid_detector::RaidAnalysis;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RaidMode {
+    Normal,
+    Elevated,
+    Raid,
+    Cooldown,
+}
+
+impl RaidMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RaidMode::Normal => "Normal",
+            RaidMode::Elevated => "Elevated",
+            RaidMode::Raid => "Raid",
+            RaidMode::Cooldown => "Cooldown",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "Normal" => Some(RaidMode::Normal),
+            "Elevated" => Some(RaidMode::Elevated),
+            "Raid" => Some(RaidMode::Raid),
+            "Cooldown" => Some(RaidMode::Cooldown),
+            _ => None,
+        }
+    }
+
+    pub fn color(&self) -> u32 {
+        match self {
+            RaidMode::Normal => 0x2ecc71,
+            RaidMode::Elevated => 0xf39c12,
+            RaidMode::Raid => 0xe74c3c,
+            RaidMode::Cooldown => 0x3498db,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RaidModeState {
+    pub mode: RaidMode,
+    pub since: DateTime<Utc>,
+    pub quiet_since: Option<DateTime<Utc>>,
+    pub auto_locked: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct RaidTransition {
+    pub from: RaidMode,
+    pub to: RaidMode,
+    pub threat_score: f32,
+    pub reasons: Vec<String>,
+    pub auto_locked: bool,
+}
+
+pub struct RaidModeTracker {
+    configs: Arc<GuildConfigCache>,
+    states: Arc<DashMap<i64, RaidModeState>>,
+}
+
+impl RaidModeTracker {
+    pub fn new(configs: Arc<GuildConfigCache>) -> Self {
+        Self {
+            configs,
+            states: Arc::new(DashMap::new()),
+        }
+    }
+
+    pub fn state(&self, guild_id: i64) -> Option<RaidModeState> {
+        self.states.get(&guild_id).map(|state| state.clone())
+    }
+
+    /// Guilds outside `Normal`, which need periodic re-evaluation so they can
+    /// step down even when nobody joins.
+    pub fn active_guilds(&self) -> Vec<i64> {
+        self.states.iter().map(|entry| *entry.key()).collect()
+    }
+
+    /// Feeds a fresh raid analysis into the guild's state machine and returns
+    /// the transition it caused, if any.
+    pub fn observe(&self, guild_id: i64, analysis: &RaidAnalysis, now: DateTime<Utc>) -> Option<RaidTransition> {
+        let config = self.configs.cached(guild_id).raid_mode.clone();
+        let score = analysis.threat_score;
+
+        let mut state = self.states.entry(guild_id).or_insert_with(|| RaidModeState {
+            mode: RaidMode::Normal,
+            since: now,
+            quiet_since: None,
+            auto_locked: false,
+        });
+
+        if score < config.exit_score {
+            state.quiet_since.get_or_insert(now);
+        } else {
+            state.quiet_since = None;
+        }
+
+        let dwelled = now - state.since >= Duration::seconds(config.min_dwell_secs as i64);
+        let quiet_for = state.quiet_since
+            .map(|quiet| now - quiet.max(state.since))
+            .unwrap_or_else(Duration::zero);
+
+        let next = match state.mode {
+            RaidMode::Normal if score >= config.raid_score => Some(RaidMode::Raid),
+            RaidMode::Normal if score >= config.elevated_score => Some(RaidMode::Elevated),
+            RaidMode::Elevated if score >= config.raid_score => Some(RaidMode::Raid),
+            RaidMode::Elevated if dwelled && quiet_for >= Duration::seconds(config.min_dwell_secs as i64) => Some(RaidMode::Normal),
+            RaidMode::Raid if dwelled && quiet_for >= Duration::seconds(config.min_dwell_secs as i64) => Some(RaidMode::Cooldown),
+            RaidMode::Cooldown if score >= config.raid_score => Some(RaidMode::Raid),
+            RaidMode::Cooldown if quiet_for >= Duration::seconds(config.cooldown_secs as i64) => Some(RaidMode::Normal),
+            _ => None,
+        };
+
+        let Some(to) = next else {
+            drop(state);
+            self.states.remove_if(&guild_id, |_, state| state.mode == RaidMode::Normal);
+            return None;
+        };
+        let from = state.mode;
+        let auto_locked = state.auto_locked;
+        state.mode = to;
+        state.since = now;
+        drop(state);
+
+        self.states.remove_if(&guild_id, |_, state| state.mode == RaidMode::Normal);
+
+        Some(RaidTransition {
+            from,
+            to,
+            threat_score: score,
+            reasons: analysis.reasons.clone(),
+            auto_locked,
+        })
+    }
+
+    /// Forces the guild into `Raid`, e.g. when moderation actions pile up
+    /// faster than the join analysis alone would catch.
+    pub fn escalate(&self, guild_id: i64, threat_score: f32, reason: String, now: DateTime<Utc>) -> Option<RaidTransition> {
+        let mut state = self.states.entry(guild_id).or_insert_with(|| RaidModeState {
+            mode: RaidMode::Normal,
+            since: now,
+            quiet_since: None,
+            auto_locked: false,
+        });
+
+        if state.mode == RaidMode::Raid {
+            return None;
+        }
+
+        let from = state.mode;
+        state.mode = RaidMode::Raid;
+        state.since = now;
+        state.quiet_since = None;
+
+        Some(RaidTransition {
+            from,
+            to: RaidMode::Raid,
+            threat_score,
+            reasons: vec![reason],
+            auto_locked: state.auto_locked,
+        })
+    }
+
+    /// Puts back a state persisted before a restart. The quiet period starts
+    /// over, so raid mode steps down no sooner than it would have.
+    pub fn restore(&self, guild_id: i64, mode: RaidMode, since: DateTime<Utc>, auto_locked: bool) {
+        if mode == RaidMode::Normal {
+            return;
+        }
+        self.states.insert(guild_id, RaidModeState {
+            mode,
+            since,
+            quiet_since: None,
+            auto_locked,
+        });
+    }
+
+    pub fn set_auto_locked(&self, guild_id: i64, auto_locked: bool) {
+        if let Some(mut state) = self.states.get_mut(&guild_id) {
+            state.auto_locked = auto_locked;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+
+    const GUILD: i64 = 1;
+
+    // Defaults: elevated 0.3, raid 0.6, exit 0.2, dwell 120s, cooldown 600s.
+    fn tracker() -> RaidModeTracker {
+        let config = Config::with_defaults(String::new(), String::new(), String::new());
+        RaidModeTracker::new(Arc::new(GuildConfigCache::new(config)))
+    }
+
+    fn analysis(threat_score: f32) -> RaidAnalysis {
+        RaidAnalysis {
+            is_raid: threat_score >= 0.6,
+            threat_score,
+            join_rate_5s: 0,
+            join_rate_30s: 0,
+            join_rate_1m: 0,
+            join_rate_5m: 0,
+            new_account_ratio: 0.0,
+            username_similarity: 0.0,
+            avatar_duplication: 0.0,
+            reasons: Vec::new(),
+        }
+    }
+
+    fn at(secs: i64) -> DateTime<Utc> {
+        DateTime::from_timestamp(1_700_000_000, 0).unwrap() + Duration::seconds(secs)
+    }
+
+    fn step(tracker: &RaidModeTracker, score: f32, secs: i64) -> Option<(RaidMode, RaidMode)> {
+        tracker.observe(GUILD, &analysis(score), at(secs)).map(|t| (t.from, t.to))
+    }
+
+    #[test]
+    fn transition_table() {
+        use RaidMode::*;
+
+        // (mode before, score, seconds quiet and in the mode, expected mode)
+        let cases = [
+            (Normal, 0.1, 0, None),
+            (Normal, 0.4, 0, Some(Elevated)),
+            (Normal, 0.7, 0, Some(Raid)),
+            (Elevated, 0.7, 0, Some(Raid)),
+            (Elevated, 0.1, 60, None),
+            (Elevated, 0.1, 121, Some(Normal)),
+            (Raid, 0.1, 60, None),
+            (Raid, 0.1, 121, Some(Cooldown)),
+            (Cooldown, 0.7, 0, Some(Raid)),
+            (Cooldown, 0.4, 0, None),
+            (Cooldown, 0.1, 300, None),
+            (Cooldown, 0.1, 601, Some(Normal)),
+        ];
+
+        for (from, score, secs, expected) in cases {
+            let tracker = tracker();
+            tracker.restore(GUILD, from, at(0), false);
+            // A first quiet observation starts the quiet period at t=0.
+            if score < 0.2 && from != Normal {
+                assert_eq!(step(&tracker, score, 0), None, "{:?} at t=0", from);
+            }
+
+            let got = step(&tracker, score, secs).map(|(_, to)| to);
+            assert_eq!(got, expected, "{:?} with score {} after {}s", from, score, secs);
+        }
+    }
+
+    #[test]
+    fn hovering_scores_keep_raid_mode() {
+        let tracker = tracker();
+        assert_eq!(step(&tracker, 0.7, 0), Some((RaidMode::Normal, RaidMode::Raid)));
+
+        // Between the exit and raid scores: never quiet, so never steps down.
+        for secs in (15..=1800).step_by(15) {
+            assert_eq!(step(&tracker, 0.4, secs), None);
+        }
+        assert_eq!(tracker.state(GUILD).map(|s| s.mode), Some(RaidMode::Raid));
+    }
+
+    #[test]
+    fn full_cycle_returns_to_normal() {
+        let tracker = tracker();
+        assert_eq!(step(&tracker, 0.7, 0), Some((RaidMode::Normal, RaidMode::Raid)));
+        assert_eq!(step(&tracker, 0.1, 30), None);
+        assert_eq!(step(&tracker, 0.1, 150), Some((RaidMode::Raid, RaidMode::Cooldown)));
+        assert_eq!(step(&tracker, 0.1, 500), None);
+        assert_eq!(step(&tracker, 0.1, 751), Some((RaidMode::Cooldown, RaidMode::Normal)));
+        assert!(tracker.state(GUILD).is_none());
+        assert!(tracker.active_guilds().is_empty());
+    }
+
+    #[test]
+    fn quiet_guilds_are_not_tracked() {
+        let tracker = tracker();
+        assert_eq!(step(&tracker, 0.1, 0), None);
+        assert!(tracker.active_guilds().is_empty());
+    }
+
+    #[test]
+    fn escalate_enters_raid_once() {
+        let tracker = tracker();
+        let transition = tracker.escalate(GUILD, 0.9, "Mass bans".to_string(), at(0)).unwrap();
+        assert_eq!((transition.from, transition.to), (RaidMode::Normal, RaidMode::Raid));
+        assert!(tracker.escalate(GUILD, 0.9, "Mass bans".to_string(), at(1)).is_none());
+    }
+
+    #[test]
+    fn restored_lockdown_is_lifted_on_exit() {
+        let tracker = tracker();
+        tracker.restore(GUILD, RaidMode::Cooldown, at(0), true);
+        assert_eq!(step(&tracker, 0.1, 0), None);
+
+        let transition = tracker.observe(GUILD, &analysis(0.1), at(601)).unwrap();
+        assert_eq!(transition.to, RaidMode::Normal);
+        assert!(transition.auto_locked);
+    }
+
+    #[test]
+    fn restoring_normal_is_ignored() {
+        let tracker = tracker();
+        tracker.restore(GUILD, RaidMode::Normal, at(0), true);
+        assert!(tracker.state(GUILD).is_none());
+    }
+}
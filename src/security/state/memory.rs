@@ -0,0 +1,210 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use super::{StateBackend, StateUsage};
+use crate::security::honeypot::{HoneypotCatch, RecentJoin};
+use crate::security::{JoinEvent, MessageRecord};
+use crate::utils::time::Clock;
+
+/// Writes between two sweeps of expired entries.
+const SWEEP_EVERY: u64 = 1000;
+
+struct Expiring<T> {
+    value: T,
+    expires_at: DateTime<Utc>,
+}
+
+impl<T> Expiring<T> {
+    fn live(&self, now: DateTime<Utc>) -> Option<&T> {
+        (self.expires_at > now).then_some(&self.value)
+    }
+}
+
+/// Per-process state. Lost on restart and not shared between processes.
+pub struct MemoryState {
+    joins: DashMap<i64, Expiring<Vec<JoinEvent>>>,
+    messages: DashMap<(i64, i64), Expiring<VecDeque<MessageRecord>>>,
+    catches: DashMap<(i64, i64), Expiring<Vec<HoneypotCatch>>>,
+    recent_joins: DashMap<(i64, i64), Expiring<RecentJoin>>,
+    writes: AtomicU64,
+    clock: Clock,
+}
+
+impl MemoryState {
+    /// Expiry follows `clock`, so replays age entries on simulated time.
+    pub fn new(clock: Clock) -> Self {
+        Self {
+            joins: DashMap::new(),
+            messages: DashMap::new(),
+            catches: DashMap::new(),
+            recent_joins: DashMap::new(),
+            writes: AtomicU64::new(0),
+            clock,
+        }
+    }
+
+    /// Drops expired entries every `SWEEP_EVERY` writes, so members that went
+    /// quiet don't stay in the maps forever. Must not be called while holding
+    /// a reference into one of them.
+    fn sweep(&self) {
+        if !self.writes.fetch_add(1, Ordering::Relaxed).is_multiple_of(SWEEP_EVERY) {
+            return;
+        }
+
+        let now = self.clock.now();
+        self.joins.retain(|_, entry| entry.expires_at > now);
+        self.messages.retain(|_, entry| entry.expires_at > now);
+        self.catches.retain(|_, entry| entry.expires_at > now);
+        self.recent_joins.retain(|_, entry| entry.expires_at > now);
+    }
+}
+
+#[async_trait]
+impl StateBackend for MemoryState {
+    fn name(&self) -> &'static str {
+        "memory"
+    }
+
+    async fn push_join(&self, guild_id: i64, event: &JoinEvent, cutoff: DateTime<Utc>, ttl: Duration) -> Result<()> {
+        let now = self.clock.now();
+        {
+            let mut entry = self.joins.entry(guild_id).or_insert_with(|| Expiring {
+                value: Vec::new(),
+                expires_at: now,
+            });
+            if entry.live(now).is_none() {
+                entry.value.clear();
+            }
+            entry.value.push(event.clone());
+            entry.value.retain(|e| e.join_time >= cutoff);
+            entry.expires_at = expiry(now, ttl);
+        }
+
+        self.sweep();
+        Ok(())
+    }
+
+    async fn joins_since(&self, guild_id: i64, since: DateTime<Utc>) -> Result<Vec<JoinEvent>> {
+        let now = self.clock.now();
+        Ok(self.joins
+            .get(&guild_id)
+            .and_then(|entry| entry.live(now).map(|events| {
+                events.iter().filter(|e| e.join_time >= since).cloned().collect()
+            }))
+            .unwrap_or_default())
+    }
+
+    async fn push_message(
+        &self,
+        guild_id: i64,
+        user_id: i64,
+        record: &MessageRecord,
+        cap: usize,
+        ttl: Duration,
+    ) -> Result<Vec<MessageRecord>> {
+        let now = self.clock.now();
+        let history = {
+            let mut entry = self.messages.entry((guild_id, user_id)).or_insert_with(|| Expiring {
+                value: VecDeque::new(),
+                expires_at: now,
+            });
+            if entry.live(now).is_none() {
+                entry.value.clear();
+            }
+            entry.value.push_back(record.clone());
+            while entry.value.len() > cap {
+                entry.value.pop_front();
+            }
+            entry.expires_at = expiry(now, ttl);
+
+            entry.value.iter().cloned().collect()
+        };
+
+        self.sweep();
+        Ok(history)
+    }
+
+    async fn message_history(&self, guild_id: i64, user_id: i64) -> Result<Vec<MessageRecord>> {
+        let now = self.clock.now();
+        Ok(self.messages
+            .get(&(guild_id, user_id))
+            .and_then(|entry| entry.live(now).map(|history| history.iter().cloned().collect()))
+            .unwrap_or_default())
+    }
+
+    async fn push_catch(&self, guild_id: i64, user_id: i64, catch: &HoneypotCatch, cap: usize, ttl: Duration) -> Result<()> {
+        let now = self.clock.now();
+        {
+            let mut entry = self.catches.entry((guild_id, user_id)).or_insert_with(|| Expiring {
+                value: Vec::new(),
+                expires_at: now,
+            });
+            if entry.live(now).is_none() {
+                entry.value.clear();
+            }
+            entry.value.push(catch.clone());
+            let excess = entry.value.len().saturating_sub(cap.max(1));
+            entry.value.drain(..excess);
+            entry.expires_at = expiry(now, ttl);
+        }
+
+        self.sweep();
+        Ok(())
+    }
+
+    async fn catches(&self, guild_id: i64, user_id: i64) -> Result<Vec<HoneypotCatch>> {
+        let now = self.clock.now();
+        Ok(self.catches
+            .get(&(guild_id, user_id))
+            .and_then(|entry| entry.live(now).cloned())
+            .unwrap_or_default())
+    }
+
+    async fn clear_catches(&self, guild_id: i64) -> Result<()> {
+        self.catches.retain(|(guild, _), _| *guild != guild_id);
+        Ok(())
+    }
+
+    async fn set_recent_join(&self, guild_id: i64, user_id: i64, join: &RecentJoin, ttl: Duration) -> Result<()> {
+        self.recent_joins.insert((guild_id, user_id), Expiring {
+            value: *join,
+            expires_at: expiry(self.clock.now(), ttl),
+        });
+
+        self.sweep();
+        Ok(())
+    }
+
+    async fn take_recent_join(&self, guild_id: i64, user_id: i64) -> Result<Option<RecentJoin>> {
+        let now = self.clock.now();
+        Ok(self.recent_joins
+            .remove(&(guild_id, user_id))
+            .and_then(|(_, entry)| entry.live(now).copied()))
+    }
+
+    fn usage(&self) -> Option<StateUsage> {
+        Some(StateUsage {
+            message_history: self.messages.len(),
+            join_events: self.joins.len(),
+            honeypot_catches: self.catches.len(),
+        })
+    }
+}
+
+fn expiry(now: DateTime<Utc>, ttl: Duration) -> DateTime<Utc> {
+    chrono::Duration::from_std(ttl)
+        .ok()
+        .and_then(|ttl| now.checked_add_signed(ttl))
+        .unwrap_or(DateTime::<Utc>::MAX_UTC)
+}
+
+impl Default for MemoryState {
+    fn default() -> Self {
+        Self::new(Clock::system())
+    }
+}
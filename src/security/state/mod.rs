@@ -0,0 +1,69 @@
+pub mod memory;
+pub mod redis;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::time::Duration;
+
+use super::honeypot::{HoneypotCatch, RecentJoin};
+use super::{JoinEvent, MessageRecord};
+
+pub use self::memory::MemoryState;
+pub use self::redis::RedisState;
+
+/// Where the detectors keep their short-lived state: join windows, message
+/// histories and honeypot catches. The in-memory backend is per process; the
+/// Redis one is shared by every process pointed at the same instance and
+/// survives restarts.
+///
+/// Backends only store what they are given; the windows, caps and TTLs are
+/// decided by the detectors.
+#[async_trait]
+pub trait StateBackend: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// Adds a join to the guild's window and drops the ones before `cutoff`.
+    async fn push_join(&self, guild_id: i64, event: &JoinEvent, cutoff: DateTime<Utc>, ttl: Duration) -> Result<()>;
+
+    /// Joins at or after `since`, oldest first.
+    async fn joins_since(&self, guild_id: i64, since: DateTime<Utc>) -> Result<Vec<JoinEvent>>;
+
+    /// Appends to the member's history, keeping the newest `cap` records, and
+    /// returns the history oldest first.
+    async fn push_message(
+        &self,
+        guild_id: i64,
+        user_id: i64,
+        record: &MessageRecord,
+        cap: usize,
+        ttl: Duration,
+    ) -> Result<Vec<MessageRecord>>;
+
+    async fn message_history(&self, guild_id: i64, user_id: i64) -> Result<Vec<MessageRecord>>;
+
+    /// Appends to the member's catches, keeping the newest `cap`.
+    async fn push_catch(&self, guild_id: i64, user_id: i64, catch: &HoneypotCatch, cap: usize, ttl: Duration) -> Result<()>;
+
+    async fn catches(&self, guild_id: i64, user_id: i64) -> Result<Vec<HoneypotCatch>>;
+
+    async fn clear_catches(&self, guild_id: i64) -> Result<()>;
+
+    async fn set_recent_join(&self, guild_id: i64, user_id: i64, join: &RecentJoin, ttl: Duration) -> Result<()>;
+
+    /// Returns and removes the member's join in one step, so concurrent
+    /// answers can't both read it.
+    async fn take_recent_join(&self, guild_id: i64, user_id: i64) -> Result<Option<RecentJoin>>;
+
+    /// Entry counts, when the backend can report them cheaply.
+    fn usage(&self) -> Option<StateUsage>;
+}
+
+/// Entries held by the in-memory backend.
+#[derive(Debug, Clone, Serialize)]
+pub struct StateUsage {
+    pub message_history: usize,
+    pub join_events: usize,
+    pub honeypot_catches: usize,
+}
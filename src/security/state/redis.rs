@@ -0,0 +1,194 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use redis::aio::ConnectionManager;
+use redis::AsyncCommands;
+use serde::de::DeserializeOwned;
+use std::time::Duration;
+
+use super::{StateBackend, StateUsage};
+use crate::security::honeypot::{HoneypotCatch, RecentJoin};
+use crate::security::{JoinEvent, MessageRecord};
+
+/// Keys deleted per `DEL` when clearing a guild's catches.
+const DELETE_BATCH: usize = 500;
+
+/// State shared through Redis, under keys starting with `prefix`:
+///
+/// - `joins:{guild}`: sorted set of joins scored by join time in milliseconds
+/// - `messages:{guild}:{user}`: list capped to the newest records
+/// - `catches:{guild}:{user}`: list of honeypot catches
+/// - `recent_join:{guild}:{user}`: join times of a member not yet timed
+///
+/// Every write refreshes the key's TTL, so idle members and guilds expire on
+/// their own.
+pub struct RedisState {
+    redis: ConnectionManager,
+    prefix: String,
+}
+
+impl RedisState {
+    pub fn new(redis: ConnectionManager, prefix: &str) -> Self {
+        Self {
+            redis,
+            prefix: prefix.trim_end_matches(':').to_string(),
+        }
+    }
+
+    fn joins_key(&self, guild_id: i64) -> String {
+        format!("{}:joins:{}", self.prefix, guild_id)
+    }
+
+    fn member_key(&self, kind: &str, guild_id: i64, user_id: i64) -> String {
+        format!("{}:{}:{}:{}", self.prefix, kind, guild_id, user_id)
+    }
+}
+
+#[async_trait]
+impl StateBackend for RedisState {
+    fn name(&self) -> &'static str {
+        "redis"
+    }
+
+    async fn push_join(&self, guild_id: i64, event: &JoinEvent, cutoff: DateTime<Utc>, ttl: Duration) -> Result<()> {
+        let key = self.joins_key(guild_id);
+        let mut conn = self.redis.clone();
+
+        redis::pipe()
+            .atomic()
+            .zadd(&key, serde_json::to_string(event)?, event.join_time.timestamp_millis()).ignore()
+            .zrembyscore(&key, "-inf", format!("({}", cutoff.timestamp_millis())).ignore()
+            .expire(&key, ttl_secs(ttl)).ignore()
+            .query_async::<_, ()>(&mut conn)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn joins_since(&self, guild_id: i64, since: DateTime<Utc>) -> Result<Vec<JoinEvent>> {
+        let mut conn = self.redis.clone();
+        let members: Vec<String> = conn
+            .zrangebyscore(self.joins_key(guild_id), since.timestamp_millis(), "+inf")
+            .await?;
+
+        Ok(decode(members))
+    }
+
+    async fn push_message(
+        &self,
+        guild_id: i64,
+        user_id: i64,
+        record: &MessageRecord,
+        cap: usize,
+        ttl: Duration,
+    ) -> Result<Vec<MessageRecord>> {
+        let key = self.member_key("messages", guild_id, user_id);
+        let mut conn = self.redis.clone();
+
+        let (history,): (Vec<String>,) = redis::pipe()
+            .atomic()
+            .rpush(&key, serde_json::to_string(record)?).ignore()
+            .ltrim(&key, -(cap.max(1) as isize), -1).ignore()
+            .expire(&key, ttl_secs(ttl)).ignore()
+            .lrange(&key, 0, -1)
+            .query_async(&mut conn)
+            .await?;
+
+        Ok(decode(history))
+    }
+
+    async fn message_history(&self, guild_id: i64, user_id: i64) -> Result<Vec<MessageRecord>> {
+        let mut conn = self.redis.clone();
+        let history: Vec<String> = conn
+            .lrange(self.member_key("messages", guild_id, user_id), 0, -1)
+            .await?;
+
+        Ok(decode(history))
+    }
+
+    async fn push_catch(&self, guild_id: i64, user_id: i64, catch: &HoneypotCatch, cap: usize, ttl: Duration) -> Result<()> {
+        let key = self.member_key("catches", guild_id, user_id);
+        let mut conn = self.redis.clone();
+
+        redis::pipe()
+            .atomic()
+            .rpush(&key, serde_json::to_string(catch)?).ignore()
+            .ltrim(&key, -(cap.max(1) as isize), -1).ignore()
+            .expire(&key, ttl_secs(ttl)).ignore()
+            .query_async::<_, ()>(&mut conn)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn catches(&self, guild_id: i64, user_id: i64) -> Result<Vec<HoneypotCatch>> {
+        let mut conn = self.redis.clone();
+        let catches: Vec<String> = conn
+            .lrange(self.member_key("catches", guild_id, user_id), 0, -1)
+            .await?;
+
+        Ok(decode(catches))
+    }
+
+    async fn clear_catches(&self, guild_id: i64) -> Result<()> {
+        let pattern = format!("{}:catches:{}:*", self.prefix, guild_id);
+        let mut conn = self.redis.clone();
+
+        let keys: Vec<String> = {
+            let mut scan = conn.scan_match::<_, String>(&pattern).await?;
+            let mut keys = Vec::new();
+            while let Some(key) = scan.next_item().await {
+                keys.push(key);
+            }
+            keys
+        };
+
+        for batch in keys.chunks(DELETE_BATCH) {
+            conn.del::<_, ()>(batch).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn set_recent_join(&self, guild_id: i64, user_id: i64, join: &RecentJoin, ttl: Duration) -> Result<()> {
+        let mut conn = self.redis.clone();
+        conn.set_ex::<_, _, ()>(
+            self.member_key("recent_join", guild_id, user_id),
+            serde_json::to_string(join)?,
+            ttl_secs(ttl) as u64,
+        ).await?;
+
+        Ok(())
+    }
+
+    async fn take_recent_join(&self, guild_id: i64, user_id: i64) -> Result<Option<RecentJoin>> {
+        let mut conn = self.redis.clone();
+        let join: Option<String> = conn.get_del(self.member_key("recent_join", guild_id, user_id)).await?;
+
+        Ok(decode(join.into_iter().collect()).pop())
+    }
+
+    /// Counting would mean scanning the keyspace on every health check.
+    fn usage(&self) -> Option<StateUsage> {
+        None
+    }
+}
+
+fn ttl_secs(ttl: Duration) -> i64 {
+    ttl.as_secs().max(1) as i64
+}
+
+/// Skips entries that no longer parse, e.g. written by a different version,
+/// rather than failing every analysis until they expire.
+fn decode<T: DeserializeOwned>(values: Vec<String>) -> Vec<T> {
+    values
+        .iter()
+        .filter_map(|value| match serde_json::from_str(value) {
+            Ok(decoded) => Some(decoded),
+            Err(e) => {
+                tracing::warn!("Skipping unreadable detector state entry: {}", e);
+                None
+            }
+        })
+        .collect()
+}
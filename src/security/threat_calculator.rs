@@ -1,5 +1,6 @@
 use super::raid_detector::RaidAnalysis;
 use super::behavior_analyzer::BehavioralMetrics;
+use crate::metrics::metrics;
 
 pub struct ThreatCalculator;
 
@@ -24,7 +25,9 @@ impl ThreatCalculator {
             base_score += 0.1;
         }
 
-        base_score.min(1.0)
+        let score = base_score.min(1.0);
+        metrics().threat_score.observe(score as f64);
+        score
     }
 
     #[allow(dead_code)]
@@ -1 +1,2 @@
 pub mod redis_client;
+pub mod time;
@@ -6,3 +6,9 @@ pub async fn create_redis_client(redis_url: &str) -> Result<ConnectionManager> {
     let manager = ConnectionManager::new(client).await?;
     Ok(manager)
 }
+
+pub async fn ping(manager: &ConnectionManager) -> Result<()> {
+    let mut connection = manager.clone();
+    redis::cmd("PING").query_async::<_, String>(&mut connection).await?;
+    Ok(())
+}
@@ -0,0 +1,50 @@
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::Arc;
+
+/// Parses `YYYY-MM-DD` or an RFC 3339 timestamp. A bare date used as the end
+/// of a range covers that whole day.
+pub fn parse_bound(input: &str, end: bool) -> Option<DateTime<Utc>> {
+    let input = input.trim();
+    if let Ok(time) = DateTime::parse_from_rfc3339(input) {
+        return Some(time.with_timezone(&Utc));
+    }
+
+    let date = NaiveDate::parse_from_str(input, "%Y-%m-%d").ok()?;
+    let start = date.and_hms_opt(0, 0, 0)?.and_utc();
+    Some(if end { start + Duration::days(1) } else { start })
+}
+
+/// Source of the current time for the detectors. Live runs read the system
+/// clock; `kitsune replay` moves a simulated one to each recorded event.
+#[derive(Debug, Clone, Default)]
+pub struct Clock {
+    /// Microseconds since the epoch, shared by every clone.
+    simulated: Option<Arc<AtomicI64>>,
+}
+
+impl Clock {
+    pub fn system() -> Self {
+        Self::default()
+    }
+
+    pub fn simulated(start: DateTime<Utc>) -> Self {
+        Self {
+            simulated: Some(Arc::new(AtomicI64::new(start.timestamp_micros()))),
+        }
+    }
+
+    pub fn now(&self) -> DateTime<Utc> {
+        match &self.simulated {
+            Some(micros) => DateTime::from_timestamp_micros(micros.load(Ordering::Relaxed)).unwrap_or_default(),
+            None => Utc::now(),
+        }
+    }
+
+    /// Moves a simulated clock to `now`. The system clock ignores this.
+    pub fn set(&self, now: DateTime<Utc>) {
+        if let Some(micros) = &self.simulated {
+            micros.store(now.timestamp_micros(), Ordering::Relaxed);
+        }
+    }
+}